@@ -1,2 +1,27 @@
+mod config; pub use config::*;
+mod bulk;
+mod coprocessor; pub use coprocessor::*;
+mod cp15; pub use cp15::*;
 mod cpu; pub use cpu::*;
+mod cursor; pub use cursor::*;
+mod dump;
+mod exception; pub use exception::*;
+mod flat;
+mod guard;
+mod labels;
+mod media;
 mod mem; pub use mem::*;
+mod mmio; pub use mmio::*;
+mod neon;
+mod psr; pub use psr::*;
+mod reg; pub use reg::*;
+mod run; pub use run::*;
+mod shared; pub use shared::*;
+mod smp; pub use smp::*;
+mod stats; pub use stats::*;
+mod thumb;
+mod timing;
+mod tlb;
+mod uninit; pub use uninit::*;
+mod vfp; pub use vfp::*;
+mod watch; pub use watch::*;
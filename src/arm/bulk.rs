@@ -0,0 +1,91 @@
+use super::*;
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+// Limitations:
+// - Not atomic: other threads can observe (or interleave with) an operation midway, and a fault leaves it partially done
+// - MMIO devices see the operation as a series of page-sized (or smaller) accesses
+
+
+
+/// Bulk operations, done a page (or less) at a time instead of byte by byte.  `try_*` variants report a [`MemFault`] instead of panicking.
+impl Memory {
+    /// Set every byte of `range` to `byte` (like `memset`.)  Zero fills of pages without data of their own don't allocate any.
+    pub fn fill(&self, range: Range<u32>, flags: MemoryFlags, byte: u8) { self.try_fill(range, flags, byte).unwrap_or_else(|fault| fault.panic()) }
+
+    /// Copy the bytes of `src` to `dst` (like `memmove` - `src` and the destination may overlap.)
+    /// `src` must be mapped for `read`, and the destination for `write`.
+    pub fn copy_within(&self, src: Range<u32>, dst: u32, read: MemoryFlags, write: MemoryFlags) { self.try_copy_within(src, dst, read, write).unwrap_or_else(|fault| fault.panic()) }
+
+    /// Compare the bytes of `a` with the same number of bytes at `b` (like `memcmp`)
+    pub fn compare(&self, a: Range<u32>, b: u32, flags: MemoryFlags) -> Ordering { self.try_compare(a, b, flags).unwrap_or_else(|fault| fault.panic()) }
+
+    /// Compare the bytes at `addr` with `bytes`
+    pub fn compare_bytes(&self, addr: u32, flags: MemoryFlags, bytes: &[u8]) -> Ordering { self.try_compare_bytes(addr, flags, bytes).unwrap_or_else(|fault| fault.panic()) }
+
+    pub fn try_fill(&self, range: Range<u32>, flags: MemoryFlags, byte: u8) -> Result<(), MemFault> {
+        let chunk = [byte; 0x1000];
+        for part in self.page_parts(range) {
+            self.try_write_bytes(part.start, flags, &chunk[.. part.len()])?;
+        }
+        Ok(())
+    }
+
+    pub fn try_copy_within(&self, src: Range<u32>, dst: u32, read: MemoryFlags, write: MemoryFlags) -> Result<(), MemFault> {
+        let mut chunk = [0u8; 0x1000];
+        let copy = |part: Range<u32>, chunk: &mut [u8; 0x1000]| -> Result<(), MemFault> {
+            let chunk = &mut chunk[.. part.len()];
+            self.try_read_bytes(part.start, read, chunk)?;
+            self.try_write_bytes(dst.wrapping_add(part.start - src.start), write, chunk)
+        };
+        let parts = self.page_parts(src.clone());
+        if dst > src.start && dst < src.end { // copying up over itself: copy the end first, so it's read before being overwritten
+            for part in parts.rev() { copy(part, &mut chunk)?; }
+        } else {
+            for part in parts { copy(part, &mut chunk)?; }
+        }
+        Ok(())
+    }
+
+    pub fn try_compare(&self, a: Range<u32>, b: u32, flags: MemoryFlags) -> Result<Ordering, MemFault> {
+        let (mut a_chunk, mut b_chunk) = ([0u8; 0x1000], [0u8; 0x1000]);
+        for part in self.page_parts(a.clone()) {
+            let (a_chunk, b_chunk) = (&mut a_chunk[.. part.len()], &mut b_chunk[.. part.len()]);
+            self.try_read_bytes(part.start, flags, a_chunk)?;
+            self.try_read_bytes(b.wrapping_add(part.start - a.start), flags, b_chunk)?;
+            match (*a_chunk).cmp(&*b_chunk) {
+                Ordering::Equal => continue,
+                unequal         => return Ok(unequal),
+            }
+        }
+        Ok(Ordering::Equal)
+    }
+
+    pub fn try_compare_bytes(&self, addr: u32, flags: MemoryFlags, bytes: &[u8]) -> Result<Ordering, MemFault> {
+        let mut chunk = [0u8; 0x1000];
+        let mut bytes = bytes;
+        let mut addr = addr;
+        while !bytes.is_empty() {
+            let n = (self.page_size() as usize - self.page_offset(addr)).min(bytes.len());
+            let chunk = &mut chunk[..n];
+            self.try_read_bytes(addr, flags, chunk)?;
+            match (*chunk).cmp(&bytes[..n]) {
+                Ordering::Equal => {},
+                unequal         => return Ok(unequal),
+            }
+            bytes = &bytes[n..];
+            addr = addr.wrapping_add(n as u32);
+        }
+        Ok(Ordering::Equal)
+    }
+
+    /// `range`, split at page boundaries
+    fn page_parts(&self, range: Range<u32>) -> impl DoubleEndedIterator<Item = Range<u32>> + '_ {
+        self.pages_overlapping(range.clone()).map(move |page_idx| {
+            let page_start = (page_idx as u64) << self.page_size().trailing_zeros();
+            let page_end = page_start + u64::from(self.page_size());
+            (page_start as u32).max(range.start) .. page_end.min(u64::from(range.end)) as u32
+        })
+    }
+}
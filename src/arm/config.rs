@@ -0,0 +1,91 @@
+use super::*;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (the introduction's architecture versions, and the ARMv6 / ARMv4 & ARMv5 differences appendices)
+//
+// Limitations:
+// - Arch granularity is coarse: ARMv5T, ARMv6K and ARMv6T2 additions are grouped with ARMv5TE, ARMv6 and ARMv7-A respectively
+// - Features::VFP gates coprocessors 10 & 11 as a whole, including the VFP encodings that move NEON scalars
+
+
+
+/// Architecture versions, oldest first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Arch {
+    /// ARMv4T: ARM7TDMI
+    V4T,
+    /// ARMv5TE: BLX, CLZ, QADD & co., SMLA<x><y> & co., PLD (e.g. ARM926EJ-S)
+    V5TE,
+    /// ARMv6(K): media instructions, exclusives, SETEND, CPS, REV, hints (e.g. ARM1176JZF-S)
+    V6,
+    /// ARMv7-A (and ARMv6T2): MOVW/MOVT, bitfields, MLS, CBZ/CBNZ, IT, barriers, interworking data processing writes of the PC (e.g. Cortex-A8)
+    V7A,
+}
+
+bitflags::bitflags! {
+    /// Optional extensions, and IMPLEMENTATION DEFINED behaviors, of a [`CpuConfig`]
+    #[repr(transparent)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Features : u32 {
+        const VFP           = 1 << 0;   // VFP as coprocessors 10 & 11 (see vfp.rs)
+        const NEON          = 1 << 1;   // Advanced SIMD (see neon.rs)
+        const SWP           = 1 << 2;   // SWP / SWPB - optional on ARMv7, where linux can trap & emulate them instead
+        const STORE_PC_12   = 1 << 3;   // STR / STM of the PC store the instruction's address + 12, instead of + 8
+    }
+}
+
+/// How single LDR / STR / LDRH / STRH (& co.) treat addresses that aren't aligned to the access size
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Unaligned {
+    /// ARMv4 & ARMv5: words load from the aligned word, rotated to put the addressed byte in bits 0 ..= 7, and halfwords & stores ignore the low address bits
+    Rotate,
+    /// Alignment fault ([`StepOutcome::DataAbort`] with [`MemFaultKind::Alignment`]) - as ARMv6+ do with SCTLR.A set, regardless of this setting
+    Fault,
+    /// ARMv6+ with SCTLR.A clear: access the unaligned bytes as addressed
+    Permit,
+}
+
+/// Which architecture [`Cpu`] implements
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuConfig {
+    pub arch:       Arch,
+    pub features:   Features,
+    pub unaligned:  Unaligned,
+}
+
+impl CpuConfig {
+    /// `arch`, with the features of the example implementation listed by [`Arch`], and its default unaligned access behavior
+    pub fn new(arch: Arch) -> Self {
+        let features = match arch {
+            Arch::V4T   => Features::SWP | Features::STORE_PC_12,
+            Arch::V5TE  => Features::SWP | Features::STORE_PC_12,
+            Arch::V6    => Features::SWP | Features::VFP,
+            Arch::V7A   => Features::SWP | Features::VFP | Features::NEON,
+        };
+        let unaligned = if arch >= Arch::V6 { Unaligned::Permit } else { Unaligned::Rotate };
+        Self { arch, features, unaligned }
+    }
+}
+
+impl Default for CpuConfig {
+    fn default() -> Self { Self::new(Arch::V7A) }
+}
+
+impl Cpu {
+    /// `true` if [`Cpu::config`] implements `arch`, otherwise abandons `op` as undefined
+    pub(super) fn require(&mut self, arch: Arch, op: u32) -> bool {
+        let ok = self.config.arch >= arch;
+        if !ok { self.undefined(op) }
+        ok
+    }
+
+    /// `true` if [`Cpu::config`] has all of `features`, otherwise abandons `op` as undefined
+    pub(super) fn require_features(&mut self, features: Features, op: u32) -> bool {
+        let ok = self.config.features.contains(features);
+        if !ok { self.undefined(op) }
+        ok
+    }
+}
@@ -0,0 +1,140 @@
+#![allow(unused_parens)]
+
+use super::*;
+
+use std::fmt::Debug;
+
+// References:
+// ARMv4: 4.14 Coprocessor Data Operations (CDP), 4.15 Coprocessor Data Transfers (LDC, STC), 4.16 Coprocessor Register Transfers (MRC, MCR)
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (A5.6 Coprocessor instructions, and Supervisor Call) for MCRR/MRRC
+
+
+
+/// A coprocessor attached to a [`Cpu`] with [`Cpu::set_coprocessor`].
+///
+/// Every method defaults to returning `None` - "this coprocessor doesn't implement that" - which the CPU reports as [`StepOutcome::Undefined`].
+/// Register numbers and opcodes are passed through exactly as encoded.
+pub trait Coprocessor : CoprocessorClone + Debug + Send {
+    /// CDP: coprocessor internal operation
+    fn cdp(&mut self, _opc1: u32, _crd: u32, _crn: u32, _crm: u32, _opc2: u32) -> Option<()> { None }
+
+    /// MRC: read a coprocessor register into an ARM register
+    fn mrc(&mut self, _opc1: u32, _crn: u32, _crm: u32, _opc2: u32) -> Option<u32> { None }
+
+    /// MCR: write an ARM register to a coprocessor register
+    fn mcr(&mut self, _opc1: u32, _crn: u32, _crm: u32, _opc2: u32, _value: u32) -> Option<()> { None }
+
+    /// MRRC: read a 64-bit coprocessor register into a pair of ARM registers, as `(Rt, Rt2)`
+    fn mrrc(&mut self, _opc1: u32, _crm: u32) -> Option<(u32, u32)> { None }
+
+    /// MCRR: write a pair of ARM registers, `(Rt, Rt2)`, to a 64-bit coprocessor register
+    fn mcrr(&mut self, _opc1: u32, _crm: u32, _value: (u32, u32)) -> Option<()> { None }
+
+    /// LDC: load `crd` from memory at `addr` (however many words the coprocessor wants). `long` is the N bit.
+    fn ldc(&mut self, _mem: &Memory, _addr: u32, _crd: u32, _long: bool) -> Option<()> { None }
+
+    /// STC: store `crd` to memory at `addr` (however many words the coprocessor wants). `long` is the N bit.
+    fn stc(&mut self, _mem: &Memory, _addr: u32, _crd: u32, _long: bool) -> Option<()> { None }
+}
+
+/// Lets [`Cpu`] stay [`Clone`] with coprocessors attached. Implemented for every `Coprocessor + Clone`.
+pub trait CoprocessorClone {
+    fn clone_box(&self) -> Box<dyn Coprocessor>;
+}
+
+impl<T: Coprocessor + Clone + 'static> CoprocessorClone for T {
+    fn clone_box(&self) -> Box<dyn Coprocessor> { Box::new(self.clone()) }
+}
+
+impl Clone for Box<dyn Coprocessor> {
+    fn clone(&self) -> Self { self.clone_box() }
+}
+
+impl Cpu {
+    /// Attach `coprocessor` as coprocessor `num` (0 ..= 15), returning whatever was attached there before.
+    ///
+    /// Attached coprocessors take priority over the built in ones (VFP/NEON as 10 & 11, [`Cp15`] as 15).
+    pub fn set_coprocessor(&mut self, num: usize, coprocessor: impl Coprocessor + 'static) -> Option<Box<dyn Coprocessor>> {
+        self.coprocessors[num].replace(Box::new(coprocessor))
+    }
+
+    /// Detach coprocessor `num` (0 ..= 15), returning it.
+    pub fn remove_coprocessor(&mut self, num: usize) -> Option<Box<dyn Coprocessor>> { self.coprocessors[num].take() }
+
+    pub fn coprocessor(&self, num: usize) -> Option<&dyn Coprocessor> { self.coprocessors[num].as_deref() }
+    pub fn coprocessor_mut(&mut self, num: usize) -> Option<&mut (dyn Coprocessor + 'static)> { self.coprocessors[num].as_deref_mut() }
+
+    /// CDP, LDC, STC, MRC, MCR, MCRR, MRRC (and their unconditional `2` variants)
+    pub(super) fn impl_coprocessor(&mut self, mem: &Memory, op: u32) {
+        let cp_num      = ((op >> 8) & 0b1111) as usize;
+        let coprocessor = match self.coprocessors[cp_num].as_deref_mut() {
+            Some(coprocessor) => coprocessor,
+            None if (cp_num == 10 || cp_num == 11) && op >> 28 != 0b1111 && self.config.features.contains(Features::VFP) => return self.impl_vfp(mem, op),
+            None if cp_num == 15 && op >> 28 != 0b1111 => {
+                self.cp15.pmu.sync(self.cycles, self.instructions);
+                &mut self.cp15
+            },
+            None => return self.undefined(op), // nothing attached
+        };
+
+        let done = match (op >> 24) & 0b1111 {
+            0b1110 if op & 0x10 == 0 => { // CDP
+                let opc1        = ((op >> 20) & 0b1111);
+                let crn         = ((op >> 16) & 0b1111);
+                let crd         = ((op >> 12) & 0b1111);
+                let opc2        = ((op >>  5) & 0b111);
+                let crm         = ((op >>  0) & 0b1111);
+                coprocessor.cdp(opc1, crd, crn, crm, opc2)
+            },
+            0b1110 => { // MRC, MCR
+                let opc1        = ((op >> 21) & 0b111);
+                let load        = ((op >> 20) & 0b1) == 1;
+                let crn         = ((op >> 16) & 0b1111);
+                let rt          = ((op >> 12) & 0b1111) as usize;
+                let opc2        = ((op >>  5) & 0b111);
+                let crm         = ((op >>  0) & 0b1111);
+                match load {
+                    false => coprocessor.mcr(opc1, crn, crm, opc2, self.registers[rt]),
+                    true  => coprocessor.mrc(opc1, crn, crm, opc2).map(|value| match rt {
+                        15 => self.cpsr = (self.cpsr - Psr::N - Psr::Z - Psr::C - Psr::V) | Psr::from_bits_truncate(value & 0xF000_0000), // APSR_nzcv
+                        rt => self.registers[rt] = value,
+                    }),
+                }
+            },
+            0b1100 if op & 0x00E0_0000 == 0x0040_0000 => { // MCRR, MRRC
+                let load        = ((op >> 20) & 0b1) == 1;
+                let rt2         = ((op >> 16) & 0b1111) as usize;
+                let rt          = ((op >> 12) & 0b1111) as usize;
+                let opc1        = ((op >>  4) & 0b1111);
+                let crm         = ((op >>  0) & 0b1111);
+                match load {
+                    false => coprocessor.mcrr(opc1, crm, (self.registers[rt], self.registers[rt2])),
+                    true  => coprocessor.mrrc(opc1, crm).map(|(lo, hi)| { self.registers[rt] = lo; self.registers[rt2] = hi; }),
+                }
+            },
+            0b1100 | 0b1101 if op & 0x01A0_0000 != 0 => { // LDC, STC (P = U = W = 0 is undefined)
+                let pre         = ((op >> 24) & 0b1) == 1;
+                let up          = ((op >> 23) & 0b1) == 1;
+                let long        = ((op >> 22) & 0b1) == 1;
+                let writeback   = ((op >> 21) & 0b1) == 1;
+                let load        = ((op >> 20) & 0b1) == 1;
+                let rn          = ((op >> 16) & 0b1111) as usize;
+                let crd         = ((op >> 12) & 0b1111);
+                let offset      = ((op >>  0) & 0xFF) * 4; // the "option" field for unindexed (P = W = 0) transfers
+
+                let base        = if rn == 15 { self.registers[15] & !3 } else { self.registers[rn] };
+                let offset_addr = if up { base.wrapping_add(offset) } else { base.wrapping_sub(offset) };
+                let addr        = if pre { offset_addr } else { base };
+                let done        = match load {
+                    true  => coprocessor.ldc(mem, addr, crd, long),
+                    false => coprocessor.stc(mem, addr, crd, long),
+                };
+                if done.is_some() && writeback { self.registers[rn] = offset_addr; }
+                done
+            },
+            _ => None,
+        };
+
+        if done.is_none() { self.undefined(op) }
+    }
+}
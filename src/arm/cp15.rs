@@ -0,0 +1,281 @@
+#![allow(unused_parens)]
+
+use super::*;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (B3.17 Organization of the CP15 registers in a VMSA implementation)
+//
+// Limitations:
+// - No privilege checks: everything is as accessible as TPIDRURW, even from User mode
+// - VBAR exists for every Arch, as if the Security Extensions were implemented (but there's no MVBAR, or Secure / Non-secure banking)
+// - TTBR*, TTBCR, DACR & CONTEXTIDR are plain storage, as is SCTLR besides V, EE & TE (see exception.rs) - there's no MMU or cache for them to configure
+// - Cache & TLB maintenance has nothing to maintain, and barriers have nothing to wait for
+// - ID registers describe a real part per Arch (see CpuId::new) adjusted for Features::SWP, not the subset uvm actually implements
+// - ARMv4T & ARMv5TE predate the CPUID scheme: their ID_* registers read as zero, like the rest of the reserved c0 space
+// - The PMU only counts SW_INCR (0x00), INST_RETIRED (0x08) and CPU_CYCLES (0x11), and never raises overflow interrupts
+
+
+
+/// CP15 System Control coprocessor registers (the commonly touched ones).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cp15 {
+    pub sctlr:      u32, // c1, 0, c0, 0: System Control Register
+    pub cpacr:      u32, // c1, 0, c0, 2: Coprocessor Access Control Register
+    pub ttbr0:      u32, // c2, 0, c0, 0: Translation Table Base Register 0
+    pub ttbr1:      u32, // c2, 0, c0, 1: Translation Table Base Register 1
+    pub ttbcr:      u32, // c2, 0, c0, 2: Translation Table Base Control Register
+    pub dacr:       u32, // c3, 0, c0, 0: Domain Access Control Register
+    pub vbar:       u32, // c12, 0, c0, 0: Vector Base Address Register (see Cpu::vector_base)
+    pub contextidr: u32, // c13, 0, c0, 1: Context ID Register
+    pub tpidrurw:   u32, // c13, 0, c0, 2: User Read/Write Thread ID Register
+    pub tpidruro:   u32, // c13, 0, c0, 3: User Read-Only Thread ID Register (linux's TLS pointer)
+    pub tpidrprw:   u32, // c13, 0, c0, 4: PL1 only Thread ID Register
+    pub pmu:        Pmu, // c9: Performance Monitors
+    pub id:         CpuId, // c0: Identification registers (read only to the guest)
+}
+
+/// CP15 c0 identification registers, as read by guest feature detection (e.g. linux's boot code & hwcaps, or glibc's ifuncs.)
+///
+/// [`Cpu::with_config`] picks values matching [`CpuConfig`] - tweak them afterwards to impersonate a different part.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuId {
+    pub midr:       u32, // c0, 0, c0, 0: Main ID Register
+    pub ctr:        u32, // c0, 0, c0, 1: Cache Type Register
+    pub mpidr:      u32, // c0, 0, c0, 5: Multiprocessor Affinity Register - 0 (uniprocessor) unless set up by Cpu::cluster
+    pub id_pfr:     [u32; 2], // c0, 0, c1, 0 & 1: Processor Feature Registers
+    pub id_dfr0:    u32, // c0, 0, c1, 2: Debug Feature Register 0
+    pub id_afr0:    u32, // c0, 0, c1, 3: Auxiliary Feature Register 0
+    pub id_mmfr:    [u32; 4], // c0, 0, c1, 4 ..= 7: Memory Model Feature Registers
+    pub id_isar:    [u32; 6], // c0, 0, c2, 0 ..= 5: Instruction Set Attribute Registers
+}
+
+impl CpuId {
+    /// ID_ISAR0.Swap_instrs: SWP & SWPB
+    const ISAR0_SWAP : u32 = 0xF << 0;
+
+    /// The identity of the example implementation listed by [`Arch`] (or a close relative with a CP15, for ARMv4T),
+    /// without SWP & SWPB unless `config` has [`Features::SWP`]
+    pub fn new(config: &CpuConfig) -> Self {
+        let mut id = match config.arch {
+            Arch::V4T => Self { // ARM920T (ARM7TDMI has no CP15)
+                midr:       0x4112_9200,
+                ctr:        0x0D17_2172, // 16 KiB 64-way instruction & data caches, 32 byte lines
+                ..Self::default()
+            },
+            Arch::V5TE => Self { // ARM926EJ-S
+                midr:       0x4106_9265,
+                ctr:        0x01DD_20D2, // 32 KiB 4-way instruction & data caches, 32 byte lines
+                ..Self::default()
+            },
+            Arch::V6 => Self { // ARM1176JZF-S
+                midr:       0x410F_B767,
+                ctr:        0x01DD_20D2,
+                mpidr:      0,
+                id_pfr:     [0x0000_0111, 0x0000_0011],
+                id_dfr0:    0x0000_0033,
+                id_afr0:    0,
+                id_mmfr:    [0x0113_0003, 0x1003_0302, 0x0122_2100, 0],
+                id_isar:    [0x0014_0011, 0x1200_2111, 0x1123_1121, 0x0110_2131, 0x0000_1141, 0],
+            },
+            Arch::V7A => Self { // Cortex-A8 r3p2
+                midr:       0x413F_C082,
+                ctr:        0x8444_C004, // ARMv7 format, 64 byte cache lines & writeback granules, PIPT instruction cache
+                mpidr:      0,
+                id_pfr:     [0x0000_1031, 0x0000_0011],
+                id_dfr0:    0x0000_0400,
+                id_afr0:    0,
+                id_mmfr:    [0x3110_0003, 0x2000_0000, 0x0120_2000, 0x0000_0011],
+                id_isar:    [0x0010_1111, 0x1211_2111, 0x2123_2031, 0x1111_2131, 0x0011_1142, 0],
+            },
+        };
+        if !config.features.contains(Features::SWP) { id.id_isar[0] &= !Self::ISAR0_SWAP; }
+        id
+    }
+
+    fn mrc(&self, crm: u32, opc2: u32) -> Option<u32> {
+        match (crm, opc2) {
+            (0, 0)          => Some(self.midr),
+            (0, 1)          => Some(self.ctr),
+            (0, 5)          => Some(self.mpidr),
+            (0, _)          => Some(0), // TCMTR, TLBTR, REVIDR: no TCMs, a unified TLB, no revisions
+            (1, 0 ..= 1)    => Some(self.id_pfr[opc2 as usize]),
+            (1, 2)          => Some(self.id_dfr0),
+            (1, 3)          => Some(self.id_afr0),
+            (1, 4 ..= 7)    => Some(self.id_mmfr[opc2 as usize - 4]),
+            (2, 0 ..= 5)    => Some(self.id_isar[opc2 as usize]),
+            (1 ..= 7, _)    => Some(0), // reserved for future ID registers: RAZ
+            _               => None,
+        }
+    }
+}
+
+/// ARMv7 Performance Monitors (CP15 c9), with [`Pmu::COUNTERS`] event counters.
+///
+/// Counts are brought up to date lazily, whenever the guest accesses CP15.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pmu {
+    pub pmcr:       u32, // c9, 0, c12, 0: Performance Monitors Control Register - the writable E, D, X & DP bits only
+    pub cntens:     u32, // c9, 0, c12, 1 & 2: PMCNTENSET / PMCNTENCLR - bit 31 enables PMCCNTR, bits 0 .. COUNTERS event counters
+    pub ovsr:       u32, // c9, 0, c12, 3: Overflow Flag Status Register
+    pub selr:       u32, // c9, 0, c12, 5: Event Counter Selection Register
+    pub ccntr:      u32, // c9, 0, c13, 0: Cycle Count Register
+    pub evtyper:    [u32; Pmu::COUNTERS], // c9, 0, c13, 1: PMXEVTYPER of each counter
+    pub evcntr:     [u32; Pmu::COUNTERS], // c9, 0, c13, 2: PMXEVCNTR of each counter
+    pub userenr:    u32, // c9, 0, c14, 0: User Enable Register
+    pub intens:     u32, // c9, 0, c14, 1 & 2: PMINTENSET / PMINTENCLR
+    counted:        (u64, u64), // (Cpu::cycles, Cpu::instructions) as of the last sync
+}
+
+impl Default for Cp15 {
+    fn default() -> Self { Self::new(&CpuConfig::default()) }
+}
+
+impl Cp15 {
+    /// Reset values, identifying as `config` (see [`CpuId::new`])
+    pub fn new(config: &CpuConfig) -> Self {
+        Self {
+            sctlr:      0x00C5_0078, // MMU, caches & alignment checking off
+            cpacr:      0,
+            ttbr0:      0,
+            ttbr1:      0,
+            ttbcr:      0,
+            dacr:       0,
+            vbar:       0,
+            contextidr: 0,
+            tpidrurw:   0,
+            tpidruro:   0,
+            tpidrprw:   0,
+            pmu:        Pmu::default(),
+            id:         CpuId::new(config),
+        }
+    }
+}
+
+impl Cp15 {
+    fn reg_mut(&mut self, opc1: u32, crn: u32, crm: u32, opc2: u32) -> Option<&mut u32> {
+        match (crn, opc1, crm, opc2) {
+            (1,  0, 0, 0) => Some(&mut self.sctlr),
+            (1,  0, 0, 2) => Some(&mut self.cpacr),
+            (2,  0, 0, 0) => Some(&mut self.ttbr0),
+            (2,  0, 0, 1) => Some(&mut self.ttbr1),
+            (2,  0, 0, 2) => Some(&mut self.ttbcr),
+            (3,  0, 0, 0) => Some(&mut self.dacr),
+            (12, 0, 0, 0) => Some(&mut self.vbar),
+            (13, 0, 0, 1) => Some(&mut self.contextidr),
+            (13, 0, 0, 2) => Some(&mut self.tpidrurw),
+            (13, 0, 0, 3) => Some(&mut self.tpidruro),
+            (13, 0, 0, 4) => Some(&mut self.tpidrprw),
+            _             => None,
+        }
+    }
+}
+
+impl Coprocessor for Cp15 {
+    fn mrc(&mut self, opc1: u32, crn: u32, crm: u32, opc2: u32) -> Option<u32> {
+        match (crn, opc1, crm, opc2) {
+            (0, 0, _, _) => self.id.mrc(crm, opc2),
+            (9, 0, _, _) => self.pmu.mrc(crm, opc2),
+            _            => self.reg_mut(opc1, crn, crm, opc2).map(|reg| *reg),
+        }
+    }
+
+    fn mcr(&mut self, opc1: u32, crn: u32, crm: u32, opc2: u32, value: u32) -> Option<()> {
+        match (crn, opc1) {
+            (7, 0) => Some(()), // cache maintenance, branch predictor maintenance, and the ARMv6 barriers: CP15ISB (c7, c5, 4), CP15DSB (c7, c10, 4), CP15DMB (c7, c10, 5)
+            (8, 0) => Some(()), // TLB maintenance
+            (9, 0) => self.pmu.mcr(crm, opc2, value),
+            (12, 0) if (crm, opc2) == (0, 0) => { self.vbar = value & !0x1F; Some(()) }, // VBAR: 32 byte aligned
+            _      => self.reg_mut(opc1, crn, crm, opc2).map(|reg| *reg = value),
+        }
+    }
+}
+
+impl Pmu {
+    /// Number of event counters (PMCR.N)
+    pub const COUNTERS : usize = 4;
+
+    /// PMCR's read only bits: implementer ARM, IDCODE 0, N = [`Pmu::COUNTERS`]
+    const PMCR_ID   : u32 = 0x4100_0000 | (Self::COUNTERS as u32) << 11;
+    const PMCR_E    : u32 = 1 << 0; // enable all counters
+    const PMCR_P    : u32 = 1 << 1; // reset the event counters (write only)
+    const PMCR_C    : u32 = 1 << 2; // reset the cycle counter (write only)
+    const PMCR_D    : u32 = 1 << 3; // PMCCNTR counts every 64th cycle
+    const PMCR_RW   : u32 = 0x39;   // E, D, X, DP
+
+    /// The bits of PMCNTENSET & co. that exist: PMCCNTR's bit 31, and one per event counter
+    const COUNTER_MASK : u32 = 1 << 31 | ((1 << Self::COUNTERS) - 1);
+
+    /// Count everything since the last sync, given [`Cpu::cycles`] and [`Cpu::instructions`]
+    pub(super) fn sync(&mut self, cycles: u64, instructions: u64) {
+        let (then_cycles, then_instructions) = std::mem::replace(&mut self.counted, (cycles, instructions));
+        if self.pmcr & Self::PMCR_E == 0 { return }
+
+        if self.cntens & (1 << 31) != 0 {
+            let ticks = match self.pmcr & Self::PMCR_D != 0 {
+                false => cycles - then_cycles,
+                true  => cycles / 64 - then_cycles / 64,
+            };
+            Self::count(&mut self.ccntr, &mut self.ovsr, 31, ticks);
+        }
+        for i in (0 .. Self::COUNTERS).filter(|i| self.cntens & (1 << i) != 0) {
+            let ticks = match self.evtyper[i] & 0xFF {
+                0x08 => instructions - then_instructions,   // INST_RETIRED
+                0x11 => cycles - then_cycles,               // CPU_CYCLES
+                _    => 0,                                  // SW_INCR (see PMSWINC) & everything uvm doesn't track
+            };
+            Self::count(&mut self.evcntr[i], &mut self.ovsr, i, ticks);
+        }
+    }
+
+    fn count(counter: &mut u32, ovsr: &mut u32, bit: usize, ticks: u64) {
+        let sum = u64::from(*counter) + ticks;
+        if sum > u64::from(u32::MAX) { *ovsr |= 1 << bit; }
+        *counter = sum as u32;
+    }
+
+    fn mrc(&mut self, crm: u32, opc2: u32) -> Option<u32> {
+        match (crm, opc2) {
+            (12, 0)     => Some(Self::PMCR_ID | self.pmcr),
+            (12, 1 | 2) => Some(self.cntens),
+            (12, 3)     => Some(self.ovsr),
+            (12, 5)     => Some(self.selr),
+            (13, 0)     => Some(self.ccntr),
+            (13, 1)     => self.evtyper.get(self.selr as usize).copied(),
+            (13, 2)     => self.evcntr.get(self.selr as usize).copied(),
+            (14, 0)     => Some(self.userenr),
+            (14, 1 | 2) => Some(self.intens),
+            _           => None,
+        }
+    }
+
+    fn mcr(&mut self, crm: u32, opc2: u32, value: u32) -> Option<()> {
+        match (crm, opc2) {
+            (12, 0) => {
+                self.pmcr = value & Self::PMCR_RW;
+                if value & Self::PMCR_P != 0 { self.evcntr = [0; Self::COUNTERS]; }
+                if value & Self::PMCR_C != 0 { self.ccntr = 0; }
+            },
+            (12, 1) => self.cntens |= value & Self::COUNTER_MASK,
+            (12, 2) => self.cntens &= !value,
+            (12, 3) => self.ovsr &= !value, // write 1 to clear
+            (12, 4) => { // PMSWINC
+                if self.pmcr & Self::PMCR_E == 0 { return Some(()) }
+                for i in (0 .. Self::COUNTERS).filter(|i| value & self.cntens & (1 << i) != 0 && self.evtyper[*i] & 0xFF == 0x00) {
+                    Self::count(&mut self.evcntr[i], &mut self.ovsr, i, 1);
+                }
+            },
+            (12, 5) => self.selr = value & 0x1F,
+            (13, 0) => self.ccntr = value,
+            (13, 1) => *self.evtyper.get_mut(self.selr as usize)? = value & 0xFF,
+            (13, 2) => *self.evcntr.get_mut(self.selr as usize)? = value,
+            (14, 0) => self.userenr = value & 1,
+            (14, 1) => self.intens |= value & Self::COUNTER_MASK,
+            (14, 2) => self.intens &= !value,
+            _       => return None,
+        }
+        Some(())
+    }
+}
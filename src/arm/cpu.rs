@@ -2,6 +2,11 @@
 
 use super::*;
 
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Display, Formatter};
+
+use super::tlb::Tlb;
+
 // References:
 // http://imrannazar.com/arm-opcode-map
 // ARMv4? https://iitd-plos.github.io/col718/ref/arm-instructionset.pdf
@@ -10,83 +15,561 @@ use super::*;
 
 
 
+/// An ARM core's architectural state.  With the `serde` feature, everything but attached coprocessors can be checkpointed & restored.
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cpu {
     // https://developer.arm.com/documentation/dui0473/c/overview-of-the-arm-architecture/arm-registers
 
-    pub registers: [u32; 16], // r0 ..= r12, sp/r13, lr/r14, pc/r15
-    // flags: bit pack? per <https://developer.arm.com/documentation/ddi0210/c/Programmer-s-Model/The-program-status-registers>?
-    pub z: bool,
-    pub c: bool,
-    pub n: bool,
-    pub v: bool,
-    // TODO: APSR?
-    // TODO: privileged registers?
+    pub config: CpuConfig, // architecture version & features (see config.rs)
+    pub registers: [u32; 16], // r0 ..= r12, sp/r13, lr/r14, pc/r15 of the current mode
+    pub cpsr: Psr, // change modes via set_cpsr / set_mode to keep the registers banked
+    spsrs: [Psr; 5], // banked SPSR_fiq, _irq, _svc, _abt, _und (see Mode::bank)
+    banked_r8_r12: [[u32; 5]; 2], // r8 ..= r12 of every mode but FIQ, and of FIQ, as of the last switch away from them
+    banked_sp_lr: [[u32; 2]; 6], // r13, r14 of User/System, FIQ, IRQ, SVC, ABT, UND, as of the last switch away from them
+    pub vfp: Vfp, // VFP & NEON registers (see vfp.rs)
+    pub cp15: Cp15, // system control coprocessor registers (see cp15.rs)
+    pub watchpoints: Vec<Watchpoint>, // data accesses to report as StepOutcome::Watchpoint (see watch.rs)
+    #[cfg_attr(feature = "serde", serde(skip))] pub(super) tlb: RefCell<Tlb>, // cached read-only pages for instruction fetches & loads (see tlb.rs)
+    #[cfg_attr(feature = "serde", serde(skip))] pub(super) coprocessors: [Option<Box<dyn Coprocessor>>; 16], // see coprocessor.rs - not serialized, reattach them after deserializing
+
+    #[cfg_attr(feature = "serde", serde(skip))] branched: bool, // the current instruction wrote the PC, don't advance past it
+    #[cfg_attr(feature = "serde", serde(skip))] undefined: Option<u32>, // the current instruction was undefined (or unimplemented) - its opcode, to report instead of advancing past it
+    #[cfg_attr(feature = "serde", serde(skip))] data_fault: Cell<Option<MemFault>>, // the current instruction's first faulting data access, to report instead of keeping its effects (see Cpu::data)
+    #[cfg_attr(feature = "serde", serde(skip))] pub(super) watch_hit: Cell<Option<WatchpointHit>>, // the current instruction's first watched access (see Cpu::watch)
+    #[cfg_attr(feature = "serde", serde(skip))] pub(super) uninit_hit: Cell<Option<UninitializedRead>>, // the current instruction's first read of undefined memory (see uninit.rs)
+    pub(super) itstate: u8, // Thumb IT block state: firstcond[7:4], mask[3:0] (see thumb.rs)
+    event: bool, // the event register: set by SEV & Cpu::send_event, consumed by WFE
+    pub(super) irq: bool, // the IRQ line, as asserted by Cpu::raise_irq (see exception.rs)
+    pub(super) fiq: bool, // the FIQ line, as asserted by Cpu::raise_fiq (see exception.rs)
+    pub(super) cycles: u64, // see timing.rs
+    pub(super) instructions: u64, // see timing.rs
+    exclusive: Option<(u32, u64)>, // local exclusive monitor: (address, value) tagged by the last LDREX*, cleared by STREX* / CLREX
+}
+
+/// What happened during a [`Cpu::step1`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StepOutcome {
+    /// The instruction executed (or was skipped by its condition) normally
+    Continue,
+    /// WFE / WFI: the guest has nothing to do until an event or interrupt arrives.
+    /// The PC has already advanced past the WFE / WFI, so stepping again resumes the guest - waking spuriously is architecturally allowed.
+    Wait,
+    /// The instruction `op` at `pc` is undefined (or not yet implemented by uvm) and wasn't executed: the PC still points at it.
+    /// Hosts can emulate it and [`Cpu::set_next_instruction_addr`] past it, [`Cpu::take_exception`] to let the guest handle it, or give up gracefully.
+    /// For Thumb, `op` is the halfword (or the first halfword in the upper 16 bits, for 32-bit encodings.)
+    Undefined { pc: u32, op: u32 },
+    /// SVC / SWI #imm: the guest wants its OS (or whatever else is emulating one) to do something.
+    /// The PC has already advanced past the SVC, and the arguments are wherever the guest ABI says (e.g. r7 & r0 ..= r6 for linux EABI).
+    /// Guests with their own SVC handler can be handed it with [`Cpu::take_exception`].
+    Svc(u32),
+    /// BKPT #imm at `pc` (ARMv5T+): the PC still points at it, for a debugger to inspect (or guests to handle as a prefetch abort.)
+    Breakpoint { pc: u32, imm: u32 },
+    /// Fetching the instruction at `pc` faulted: the PC still points at it.
+    PrefetchAbort { pc: u32, fault: MemFault },
+    /// The instruction at `pc` faulted accessing memory: the PC still points at it, and its core register & CPSR writes were undone.
+    /// Stores before the faulting one (e.g. of an STM) may have already happened, and VFP / NEON registers loaded before it (e.g. by a VLDM) stay loaded -
+    /// as they can on real hardware.  [`Cpu::cycles`] still counts the instruction.
+    DataAbort { pc: u32, fault: MemFault },
+    /// A [`StepOutcome::DataAbort`] accessing a [`Memory::guard`]ed region (e.g. below the stack, see [`Memory::map_stack`]) - most likely runaway recursion.
+    /// Guests with their own abort handler can still be handed it with [`Cpu::take_exception`].
+    StackOverflow { pc: u32, fault: MemFault },
+    /// The instruction completed, but accessed memory watched by [`Cpu::watchpoints`]
+    Watchpoint(WatchpointHit),
+    /// The instruction completed, but read memory that was never written (see [`Memory::set_uninit_tracking`])
+    UninitializedRead(UninitializedRead),
+}
+
+/// What a [`Cpu::step`] did, when it went well
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    /// See [`StepOutcome::Continue`]
+    Continue,
+    /// See [`StepOutcome::Wait`]
+    Wait,
+    /// See [`StepOutcome::Svc`]
+    Svc(u32),
+    /// See [`StepOutcome::Breakpoint`]
+    Breakpoint { pc: u32, imm: u32 },
+    /// See [`StepOutcome::Watchpoint`]
+    Watchpoint(WatchpointHit),
+    /// See [`StepOutcome::UninitializedRead`]
+    UninitializedRead(UninitializedRead),
+}
+
+/// Why a [`Cpu::step`] couldn't execute the instruction at `pc`.  The PC still points at it, so the host can fix things up and retry,
+/// [`Cpu::take_exception`] to let the guest handle it, or report it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault {
+    /// See [`StepOutcome::Undefined`]
+    Undefined { pc: u32, op: u32 },
+    /// See [`StepOutcome::PrefetchAbort`]
+    PrefetchAbort { pc: u32, fault: MemFault },
+    /// See [`StepOutcome::DataAbort`]
+    DataAbort { pc: u32, fault: MemFault },
+    /// See [`StepOutcome::StackOverflow`]
+    StackOverflow { pc: u32, fault: MemFault },
+}
+
+impl Fault {
+    /// The address of the faulting instruction
+    pub fn pc(&self) -> u32 {
+        match *self {
+            Fault::Undefined { pc, .. } | Fault::PrefetchAbort { pc, .. } | Fault::DataAbort { pc, .. } | Fault::StackOverflow { pc, .. } => pc,
+        }
+    }
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Fault::Undefined { pc, op }         => write!(f, "undefined instruction 0x{:08x} at 0x{:08x}", op, pc),
+            Fault::PrefetchAbort { pc, fault }  => write!(f, "prefetch abort at 0x{:08x}: {}", pc, fault),
+            Fault::DataAbort { pc, fault }      => write!(f, "data abort at 0x{:08x}: {}", pc, fault),
+            Fault::StackOverflow { pc, fault }  => write!(f, "stack overflow at 0x{:08x}: {}", pc, fault),
+        }
+    }
+}
+
+impl std::error::Error for Fault {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Fault::Undefined { .. }                                             => None,
+            Fault::PrefetchAbort { fault, .. } | Fault::DataAbort { fault, .. } | Fault::StackOverflow { fault, .. } => Some(fault),
+        }
+    }
+}
+
+/// A single piece of CPU state that differs between two snapshots, as reported by [`Cpu::diff`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegChange {
+    Register    { index: usize, before: u32,  after: u32  },
+    Flag        { flag: Flag,   before: bool, after: bool },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Flag { N, Z, C, V }
+
 impl Cpu {
     pub fn new() -> Self { Default::default() }
+    pub fn with_config(config: CpuConfig) -> Self { Self { config, cp15: Cp15::new(&config), ..Default::default() } }
+
+    /// The current mode's SPSR, or [`None`] in User and System modes (or a reserved mode), which don't have one
+    pub fn spsr(&self) -> Option<Psr> { self.spsr_of(self.cpsr.mode()?) }
+    pub fn spsr_mut(&mut self) -> Option<&mut Psr> { self.spsr_of_mut(self.cpsr.mode()?) }
+
+    /// `mode`'s banked SPSR, or [`None`] for User and System modes, which don't have one
+    pub fn spsr_of(&self, mode: Mode) -> Option<Psr> { Some(self.spsrs[mode.bank()?]) }
+    pub fn spsr_of_mut(&mut self, mode: Mode) -> Option<&mut Psr> { Some(&mut self.spsrs[mode.bank()?]) }
+
+    /// Write the CPSR, switching register banks if that changes the mode
+    pub fn set_cpsr(&mut self, cpsr: Psr) {
+        let (old, new) = (self.current_mode(), cpsr.mode().unwrap_or(Mode::User));
+        self.cpsr = cpsr;
+        if old == new { return }
+
+        let (old_fiq, new_fiq) = ((old == Mode::Fiq) as usize, (new == Mode::Fiq) as usize);
+        let (old_bank, new_bank) = (Self::sp_lr_bank(old), Self::sp_lr_bank(new));
+        if old_fiq != new_fiq {
+            self.banked_r8_r12[old_fiq].copy_from_slice(&self.registers[8 ..= 12]);
+            self.registers[8 ..= 12].copy_from_slice(&self.banked_r8_r12[new_fiq]);
+        }
+        if old_bank != new_bank {
+            self.banked_sp_lr[old_bank].copy_from_slice(&self.registers[13 ..= 14]);
+            self.registers[13 ..= 14].copy_from_slice(&self.banked_sp_lr[new_bank]);
+        }
+    }
+
+    /// Switch to `mode`, banking registers
+    pub fn set_mode(&mut self, mode: Mode) {
+        let mut cpsr = self.cpsr;
+        cpsr.set_mode(mode);
+        self.set_cpsr(cpsr);
+    }
+
+    /// Register `r` (0 ..= 15) as `mode` sees it, whether or not `mode` is the current mode
+    pub fn register_of(&self, mode: Mode, r: usize) -> u32 {
+        match self.banked_slot(mode, r) {
+            Some((bank, i)) if bank < 2 => self.banked_r8_r12[bank][i],
+            Some((bank, i))             => self.banked_sp_lr[bank - 2][i],
+            None                        => self.registers[r],
+        }
+    }
+
+    /// Write register `r` (0 ..= 15) as `mode` sees it, whether or not `mode` is the current mode
+    pub fn set_register_of(&mut self, mode: Mode, r: usize, value: u32) {
+        match self.banked_slot(mode, r) {
+            Some((bank, i)) if bank < 2 => self.banked_r8_r12[bank][i] = value,
+            Some((bank, i))             => self.banked_sp_lr[bank - 2][i] = value,
+            None                        => self.registers[r] = value,
+        }
+    }
+
+    /// Reserved mode encodings bank like User mode
+    fn current_mode(&self) -> Mode { self.cpsr.mode().unwrap_or(Mode::User) }
+
+    fn sp_lr_bank(mode: Mode) -> usize { mode.bank().map_or(0, |bank| bank + 1) }
+
+    /// Where `mode`'s `r` is stashed while `mode` isn't current: `(0 ..= 1, r - 8)` for `banked_r8_r12`, `(2 ..= 7, r - 13)` for `banked_sp_lr`,
+    /// or [`None`] if `r` is live in `registers` (shared with the current mode)
+    fn banked_slot(&self, mode: Mode, r: usize) -> Option<(usize, usize)> {
+        let current = self.current_mode();
+        match r {
+            8 ..= 12 if (mode == Mode::Fiq) != (current == Mode::Fiq)           => Some(((mode == Mode::Fiq) as usize, r - 8)),
+            13 | 14  if Self::sp_lr_bank(mode) != Self::sp_lr_bank(current)     => Some((2 + Self::sp_lr_bank(mode), r - 13)),
+            _                                                                   => None,
+        }
+    }
 
     // https://developer.arm.com/documentation/ddi0406/cb/Application-Level-Architecture/Application-Level-Programmers--Model/ARM-core-registers?lang=en
-    // Assume ARM mode for now
-    fn read_pc_offset(&self) -> u32 { 8 }
+    pub(super) fn read_pc_offset(&self) -> u32 { if self.cpsr.t() { 4 } else { 8 } }
+
+    pub fn next_instruction_addr(&self) -> u32 { self.registers[15].wrapping_sub(self.read_pc_offset()) }
 
     pub fn set_next_instruction_addr(&mut self, addr: u32) {
-        self.registers[15] = addr + self.read_pc_offset();
-    }
-
-    pub fn step1(&mut self, mem: &Memory) {
-        let op = mem.read_u32_aligned(self.registers[15] - self.read_pc_offset(), MemoryFlags::READ | MemoryFlags::EXECUTE);
-
-        let cond = match op >> 28 {
-            0b0000 => self.z,                           // EQ equal
-            0b0001 => !self.z,                          // NE not equal
-            0b0010 => self.c,                           // CS unsigned higher-or-same
-            0b0011 => !self.c,                          // CC unsigned lower
-            0b0100 => self.n,                           // MI (minus?) negative
-            0b0101 => !self.n,                          // PL positive or zero
-            0b0110 => self.v,                           // VS overflow
-            0b0111 => !self.v,                          // VC no overflow
-            0b1000 => self.c && !self.z,                // HI unsigned higher
-            0b1001 => !self.c || self.z,                // LS unsigned lower or same
-            0b1010 => self.n == self.v,                 // GE greater or equal
-            0b1011 => self.n != self.v,                 // LT less than
-            0b1100 => !self.z && (self.n == self.v),    // GT greater than
-            0b1101 => self.z || (self.n != self.v),     // LE less than or equal
-            0b1110 => true,                             // AL always
-            _b1111 => {                                 // Unconditional opcode
-                false // don't do the traditional cond op
+        self.registers[15] = addr.wrapping_add(self.read_pc_offset());
+    }
+
+    pub(super) fn branch_to(&mut self, addr: u32) {
+        self.set_next_instruction_addr(addr);
+        self.branched = true;
+    }
+
+    /// Branch to `target`, with bit 0 selecting Thumb (1) vs ARM (0) state
+    pub(super) fn branch_exchange(&mut self, target: u32) {
+        self.cpsr.set(Psr::T, (target & 1) == 1);
+        self.branch_to(target & !1);
+    }
+
+    /// BranchWritePC: branch to `target` without changing state
+    pub(super) fn branch_write_pc(&mut self, target: u32) {
+        self.branch_to(target & if self.cpsr.t() { !1 } else { !3 });
+    }
+
+    /// LoadWritePC: LDR, LDM & POP of the PC interwork on ARMv5T+, where ARMv4T stays in the current state
+    pub(super) fn load_write_pc(&mut self, target: u32) {
+        if self.config.arch >= Arch::V5TE { self.branch_exchange(target) } else { self.branch_write_pc(target) }
+    }
+
+    /// The value STR & STM store for the PC: the instruction's address + 8, or + 12 with [`Features::STORE_PC_12`]
+    fn stored_pc(&self) -> u32 {
+        self.registers[15].wrapping_add(if self.config.features.contains(Features::STORE_PC_12) { 4 } else { 0 })
+    }
+
+    /// Returns `true` if the instruction was advanced past by a branch, `false` if it should step past itself
+    pub(super) fn take_branched(&mut self) -> bool { std::mem::take(&mut self.branched) }
+
+    /// 4.17 Undefined Instruction: abandon the current instruction `op`, to be reported as [`StepOutcome::Undefined`]
+    pub(super) fn undefined(&mut self, op: u32) { self.undefined = Some(op); }
+
+    // Data accesses are big endian while CPSR.E is set (ARMv6+ BE8), so byte swap them vs. the little endian memory.
+    // Instruction fetches are always little endian, bytes don't care, and neither do exclusives' tagged values.
+
+    /// Unwrap a data access' `result`, or remember the instruction's first fault (see [`Cpu::step1`]) and make do with a default value
+    pub(super) fn data<T: Default>(&self, result: Result<T, MemFault>) -> T {
+        result.unwrap_or_else(|fault| {
+            if self.data_fault.get().is_none() { self.data_fault.set(Some(fault)); }
+            T::default()
+        })
+    }
+
+    /// The byte order of data accesses, per CPSR.E (see SETEND)
+    pub fn data_endian(&self) -> Endian { if self.cpsr.e() { Endian::Big } else { Endian::Little } }
+
+    pub(super) fn read_data_u8(&self, mem: &Memory, addr: u32) -> u8 {
+        self.watch(mem, addr, 1, MemoryFlags::READ, 0);
+        self.data(self.tlb.borrow_mut().read(mem, addr, MemoryFlags::READ).map(u8::from_le_bytes))
+    }
+
+    pub(super) fn read_data_u16(&self, mem: &Memory, addr: u32) -> u16 {
+        self.watch(mem, addr, 2, MemoryFlags::READ, 0);
+        self.data(self.tlb.borrow_mut().read(mem, addr, MemoryFlags::READ).map(|bytes| self.data_endian().u16(u16::from_le_bytes(bytes))))
+    }
+
+    pub(super) fn read_data_u32(&self, mem: &Memory, addr: u32) -> u32 {
+        self.watch(mem, addr, 4, MemoryFlags::READ, 0);
+        self.data(self.tlb.borrow_mut().read(mem, addr, MemoryFlags::READ).map(|bytes| self.data_endian().u32(u32::from_le_bytes(bytes))))
+    }
+
+    /// A doubleword as a single 64-bit value (e.g. VLDR Dn), from two word-aligned words
+    pub(super) fn read_data_u64(&self, mem: &Memory, addr: u32) -> u64 {
+        let (first, second) = (self.read_data_u32(mem, addr), self.read_data_u32(mem, addr.wrapping_add(4)));
+        if self.cpsr.e() { u64::from(first) << 32 | u64::from(second) } else { u64::from(second) << 32 | u64::from(first) }
+    }
+
+    pub(super) fn write_data_u8(&self, mem: &Memory, addr: u32, value: u8) {
+        self.watch(mem, addr, 1, MemoryFlags::WRITE, value.into());
+        self.data(mem.try_write_u8(addr, MemoryFlags::WRITE, value));
+    }
+
+    pub(super) fn write_data_u16(&self, mem: &Memory, addr: u32, value: u16) {
+        self.watch(mem, addr, 2, MemoryFlags::WRITE, self.data_endian().u16(value).into());
+        self.data(mem.try_write_u16_aligned_endian(addr, MemoryFlags::WRITE, value, self.data_endian()));
+    }
+
+    pub(super) fn write_data_u32(&self, mem: &Memory, addr: u32, value: u32) {
+        self.watch(mem, addr, 4, MemoryFlags::WRITE, self.data_endian().u32(value).into());
+        self.data(mem.try_write_u32_aligned_endian(addr, MemoryFlags::WRITE, value, self.data_endian()));
+    }
+
+    /// How single loads & stores treat unaligned addresses: [`CpuConfig::unaligned`], unless SCTLR.A (ARMv6+) demands alignment faults
+    fn unaligned(&self) -> Unaligned {
+        if self.config.arch >= Arch::V6 && self.cp15.sctlr & Self::SCTLR_A != 0 { Unaligned::Fault } else { self.config.unaligned }
+    }
+
+    /// Record an alignment fault for a `size` byte access at `addr`, if it's misaligned and `Unaligned::Fault`ing
+    pub(super) fn misaligned(&self, addr: u32, size: u32, access: MemoryFlags) -> bool {
+        let misaligned = addr & (size - 1) != 0 && self.unaligned() == Unaligned::Fault;
+        if misaligned { self.data::<()>(Err(MemFault { addr, access, flags: MemoryFlags::NONE, kind: MemFaultKind::Alignment })); }
+        misaligned
+    }
+
+    /// A single LDR's word (see [`Unaligned`])
+    pub(super) fn load_u32(&self, mem: &Memory, addr: u32) -> u32 {
+        if self.misaligned(addr, 4, MemoryFlags::READ) { return 0 }
+        match self.unaligned() {
+            Unaligned::Permit if addr & 3 != 0  => { self.watch(mem, addr, 4, MemoryFlags::READ, 0); self.data(mem.try_read_u32_unaligned_endian(addr, MemoryFlags::READ, self.data_endian())) },
+            _                                   => self.read_data_u32(mem, addr & !3).rotate_right(8 * (addr & 3)), // ARMv4 rotates the addressed byte into bits 0 ..= 7
+        }
+    }
+
+    /// A single LDRH's halfword (see [`Unaligned`])
+    pub(super) fn load_u16(&self, mem: &Memory, addr: u32) -> u16 {
+        if self.misaligned(addr, 2, MemoryFlags::READ) { return 0 }
+        match self.unaligned() {
+            Unaligned::Permit if addr & 1 != 0  => { self.watch(mem, addr, 2, MemoryFlags::READ, 0); self.data(mem.try_read_u16_unaligned_endian(addr, MemoryFlags::READ, self.data_endian())) },
+            _                                   => self.read_data_u16(mem, addr & !1),
+        }
+    }
+
+    /// A single STR's word (see [`Unaligned`])
+    pub(super) fn store_u32(&self, mem: &Memory, addr: u32, value: u32) {
+        if self.misaligned(addr, 4, MemoryFlags::WRITE) { return }
+        match self.unaligned() {
+            Unaligned::Permit if addr & 3 != 0  => { self.watch(mem, addr, 4, MemoryFlags::WRITE, self.data_endian().u32(value).into()); self.data(mem.try_write_u32_unaligned_endian(addr, MemoryFlags::WRITE, value, self.data_endian())) },
+            _                                   => self.write_data_u32(mem, addr & !3, value), // ARMv4 ignores the low address bits of word stores
+        }
+    }
+
+    /// A single STRH's halfword (see [`Unaligned`])
+    pub(super) fn store_u16(&self, mem: &Memory, addr: u32, value: u16) {
+        if self.misaligned(addr, 2, MemoryFlags::WRITE) { return }
+        match self.unaligned() {
+            Unaligned::Permit if addr & 1 != 0  => { self.watch(mem, addr, 2, MemoryFlags::WRITE, self.data_endian().u16(value).into()); self.data(mem.try_write_u16_unaligned_endian(addr, MemoryFlags::WRITE, value, self.data_endian())) },
+            _                                   => self.write_data_u16(mem, addr & !1, value),
+        }
+    }
+
+    /// A doubleword as a single 64-bit value (e.g. VSTR Dn), to two word-aligned words
+    pub(super) fn write_data_u64(&self, mem: &Memory, addr: u32, value: u64) {
+        let (first, second) = if self.cpsr.e() { (value >> 32, value) } else { (value, value >> 32) };
+        self.write_data_u32(mem, addr, first as u32);
+        self.write_data_u32(mem, addr.wrapping_add(4), second as u32);
+    }
+
+    /// Finish a step: report an undefined instruction, or advance past the instruction (`size` bytes) unless it branched
+    pub(super) fn end_step(&mut self, pc: u32, size: u32, outcome: StepOutcome) -> StepOutcome {
+        if let Some(op) = self.undefined.take() {
+            self.branched = false;
+            return StepOutcome::Undefined { pc, op };
+        }
+        if let StepOutcome::Breakpoint { .. } = outcome {
+            self.branched = false;
+            return outcome;
+        }
+        self.cycles += 1;
+        self.instructions += 1;
+        match self.take_branched() {
+            false => self.registers[15] = self.registers[15].wrapping_add(size),
+            true  => self.cycles += 2, // refill the pipeline
+        }
+        outcome
+    }
+
+    /// Report every register and flag whose value in `self` differs from `before`, in r0 ..= r15, N, Z, C, V order.
+    pub fn diff(&self, before: &Cpu) -> Vec<RegChange> {
+        let mut changes = Vec::new();
+        for (index, (&before, &after)) in before.registers.iter().zip(self.registers.iter()).enumerate() {
+            if before != after { changes.push(RegChange::Register { index, before, after }) }
+        }
+        for (flag, before, after) in [
+            (Flag::N, before.cpsr.n(), self.cpsr.n()),
+            (Flag::Z, before.cpsr.z(), self.cpsr.z()),
+            (Flag::C, before.cpsr.c(), self.cpsr.c()),
+            (Flag::V, before.cpsr.v(), self.cpsr.v()),
+        ] {
+            if before != after { changes.push(RegChange::Flag { flag, before, after }) }
+        }
+        changes
+    }
+
+    /// Signal an event, as another core's SEV would: the next WFE completes immediately instead of returning [`StepOutcome::Wait`]
+    pub fn send_event(&mut self) { self.event = true; }
+
+    /// Execute a single instruction (after taking any pending interrupt, see [`Cpu::raise_irq`])
+    pub fn step1(&mut self, mem: &Memory) -> StepOutcome {
+        self.take_interrupt();
+
+        // a data abort undoes the instruction's register & CPSR writes (the base restored abort model), and doesn't count it
+        let pc = self.next_instruction_addr();
+        let (registers, cpsr, itstate, instructions) = (self.registers, self.cpsr, self.itstate, self.instructions);
+        let outcome = if self.cpsr.t() { self.step1_thumb(mem) } else { self.step1_arm(mem) };
+        let watch_hit = self.watch_hit.take();
+        let uninit_hit = self.uninit_hit.take();
+        match (self.data_fault.take(), outcome) {
+            (Some(fault), _) => {
+                self.set_cpsr(cpsr); // unbank first, in case the instruction switched modes regardless
+                (self.registers, self.itstate, self.instructions) = (registers, itstate, instructions);
+                if fault.kind == MemFaultKind::Permission && mem.is_guard(fault.addr) { StepOutcome::StackOverflow { pc, fault } } else { StepOutcome::DataAbort { pc, fault } }
+            },
+            (None, StepOutcome::Undefined { .. } | StepOutcome::Breakpoint { .. } | StepOutcome::PrefetchAbort { .. }) => {
+                self.itstate = itstate; // the PC is still on the instruction, so it's still next in any IT block
+                outcome
             },
+            (None, StepOutcome::Continue) => match (watch_hit, uninit_hit) {
+                (Some(hit), _)      => StepOutcome::Watchpoint(WatchpointHit { pc, ..hit }),
+                (None, Some(hit))   => StepOutcome::UninitializedRead(UninitializedRead { pc, ..hit }),
+                (None, None)        => StepOutcome::Continue,
+            },
+            (None, outcome) => outcome,
+        }
+    }
+
+    /// [`Cpu::step1`], sorted into what the guest can continue from and what it can't
+    pub fn step(&mut self, mem: &Memory) -> Result<Event, Fault> {
+        match self.step1(mem) {
+            StepOutcome::Continue                   => Ok(Event::Continue),
+            StepOutcome::Wait                       => Ok(Event::Wait),
+            StepOutcome::Svc(imm)                   => Ok(Event::Svc(imm)),
+            StepOutcome::Breakpoint { pc, imm }     => Ok(Event::Breakpoint { pc, imm }),
+            StepOutcome::Watchpoint(hit)            => Ok(Event::Watchpoint(hit)),
+            StepOutcome::UninitializedRead(hit)     => Ok(Event::UninitializedRead(hit)),
+            StepOutcome::Undefined { pc, op }       => Err(Fault::Undefined { pc, op }),
+            StepOutcome::PrefetchAbort { pc, fault }=> Err(Fault::PrefetchAbort { pc, fault }),
+            StepOutcome::DataAbort { pc, fault }    => Err(Fault::DataAbort { pc, fault }),
+            StepOutcome::StackOverflow { pc, fault }=> Err(Fault::StackOverflow { pc, fault }),
+        }
+    }
+
+    fn step1_arm(&mut self, mem: &Memory) -> StepOutcome {
+        let pc = self.next_instruction_addr();
+        let op = match self.tlb.get_mut().fetch_u32(mem, pc) {
+            Ok(op)      => op,
+            Err(fault)  => return StepOutcome::PrefetchAbort { pc, fault },
         };
 
-        if cond {
+        let mut outcome = StepOutcome::Continue;
+        if self.condition_passed(op >> 28) {
+            self.cycles += u64::from(self.arm_cycles(op));
+
             // Ref: 4.1.1 Format summary
             // Is it just me, or are there a lot of potentially overlapping encodings in said table?
 
-            if (op >> 4) & 0xFFFFFF == 0b0001_0010_1111_1111_1111_0001 {
-                panic!("arm::Cpu::step1: BX not yet implemented");
+            if (op >> 4) & 0xFFFFFD == 0b0001_0010_1111_1111_1111_0001 { // BX, BLX (register)
+                self.impl_bx(op);
+            } else if op & 0x0F80_00F0 == 0x0000_0090 {
+                self.impl_multiply(op);
+            } else if op & 0x0F80_00F0 == 0x0080_0090 {
+                self.impl_multiply_long(op);
+            } else if op & 0x0FB0_0FF0 == 0x0100_0090 {
+                self.impl_swp(mem, op);
+            } else if op & 0x0F90_0FF0 == 0x0100_0050 {
+                self.impl_saturating_add_subtract(op);
+            } else if op & 0x0F90_0090 == 0x0100_0080 {
+                self.impl_signed_halfword_multiply(op);
+            } else if op & 0x0F80_0FF0 == 0x0180_0F90 {
+                self.impl_exclusive_transfer(mem, op);
+            } else if op & 0x0E00_0090 == 0x0000_0090 && op & 0x60 != 0 {
+                self.impl_halfword_transfer(mem, op);
             // } else if (op >> 4) & 0b1111 == 0b1001 {
             //     // ...
             } else {
                 match (op >> 20) & 0xFF {
-                    0x28 => self.impl_data_processing(op), // ADD
-                    0x3A => self.impl_data_processing(op), // MOV
+                    0x10 | 0x14 if op & 0x0FBF_0FFF == 0x010F_0000 => self.impl_mrs(op),
+                    0x12 | 0x16 if op & 0x0FB0_FFF0 == 0x0120_F000 => self.impl_msr(op),
+                    0x12 if op & 0x0000_00F0 == 0x0000_0070 => outcome = self.impl_bkpt(op),
+                    0x30 | 0x34 => self.impl_move_wide(op), // TST/CMN immediate without S
+                    0x32 if op & 0x000F_FF00 == 0x0000_F000 => outcome = self.impl_hint(op), // MSR immediate with no fields
+                    0x32 | 0x36 if op & 0x0FB0_F000 == 0x0320_F000 => self.impl_msr(op),
+                    // TST/TEQ/CMP/CMN without S encode MRS/MSR & misc instead, and I=0 with bits 7 & 4 set encodes multiplies & extra load/stores
+                    0x00 ..= 0x3F if op & 0x0190_0000 != 0x0100_0000 && op & 0x0200_0090 != 0x0000_0090 => self.impl_data_processing(op),
+
+                    0x40 ..= 0x5F => self.impl_single_data_transfer(mem, op),
+                    0x60 ..= 0x7F if op & 0x10 == 0 => self.impl_single_data_transfer(mem, op),
+                    0x60 ..= 0x7F => self.impl_media(op), // bit 4 set was 4.17 Undefined before ARMv6 (see media.rs)
+                    0x80 ..= 0x9F => self.impl_block_data_transfer(mem, op),
+                    0xA0 ..= 0xBF => self.impl_branch(op),
 
-                    // 0x00 => panic!("and?"), // AND / MUL
-                    // 0x3B => panic!("movs"),
+                    0xC0 ..= 0xEF => self.impl_coprocessor(mem, op), // includes VFP & NEON as coprocessors 10 & 11
 
-                    0xF0 ..= 0xFF => self.impl_swi(mem, op),
-                    _other => panic!("arm::Cpu::step1: unimplemented op: 0x{:08x} / 0b{:032b}", op, op),
+                    0xF0 ..= 0xFF => outcome = self.impl_swi(op),
+                    _other => self.undefined(op),
                 }
             }
+        } else if op >> 28 == 0b1111 {
+            self.impl_unconditional(mem, op);
         }
 
-        self.registers[15] += 4;
+        self.end_step(pc, 4, outcome)
     }
 
-    // 4.3 Branch and Exchange (BX)
-    // 4.4 Branch and Branch with Link (B, BL)
-    // TODO: implement
+    /// 4.2 The Condition Field
+    pub(super) fn condition_passed(&self, cond: u32) -> bool {
+        let (n, z, c, v) = (self.cpsr.n(), self.cpsr.z(), self.cpsr.c(), self.cpsr.v());
+        match cond {
+            0b0000 => z,                                // EQ equal
+            0b0001 => !z,                               // NE not equal
+            0b0010 => c,                                // CS unsigned higher-or-same
+            0b0011 => !c,                               // CC unsigned lower
+            0b0100 => n,                                // MI (minus?) negative
+            0b0101 => !n,                               // PL positive or zero
+            0b0110 => v,                                // VS overflow
+            0b0111 => !v,                               // VC no overflow
+            0b1000 => c && !z,                          // HI unsigned higher
+            0b1001 => !c || z,                          // LS unsigned lower or same
+            0b1010 => n == v,                           // GE greater or equal
+            0b1011 => n != v,                           // LT less than
+            0b1100 => !z && (n == v),                   // GT greater than
+            0b1101 => z || (n != v),                    // LE less than or equal
+            0b1110 => true,                             // AL always
+            _b1111 => {                                 // Unconditional opcode
+                false // don't do the traditional cond op
+            },
+        }
+    }
+
+    /// 4.3 Branch and Exchange (BX), ARMv5T: Branch with Link and Exchange (BLX register)
+    fn impl_bx(&mut self, op: u32) {
+        let link        = ((op >> 5) & 0b1) == 1;
+        let rn          = ((op >> 0) & 0b1111) as usize;
+        let target      = self.registers[rn]; // read before linking - `blx lr` is legal
+        if link && !self.require(Arch::V5TE, op) { return }
+        if link { self.registers[14] = self.registers[15].wrapping_sub(4); }
+        self.branch_exchange(target);
+    }
+
+    /// 4.4 Branch and Branch with Link (B, BL)
+    fn impl_branch(&mut self, op: u32) {
+        let link        = ((op >> 24) & 0b1) == 1;
+        let offset      = (((op << 8) as i32) >> 6) as u32; // sign extended imm24 << 2
+
+        if link { self.registers[14] = self.registers[15].wrapping_sub(4); }
+        self.branch_to(self.registers[15].wrapping_add(offset));
+    }
+
+    /// ARMv5T: Branch with Link and Exchange (BLX immediate) - always switches to Thumb
+    fn impl_blx_immediate(&mut self, op: u32) {
+        let h           = ((op >> 24) & 0b1);
+        let offset      = (((op << 8) as i32) >> 6) as u32 | (h << 1); // H selects the odd halfword
+
+        self.registers[14] = self.registers[15].wrapping_sub(4);
+        self.branch_exchange(self.registers[15].wrapping_add(offset) | 1);
+    }
 
     /// 4.5 Data Processing
     fn impl_data_processing(&mut self, op: u32) {
@@ -94,113 +577,636 @@ impl Cpu {
         let _sel1       = ((op >> 26) & 0b11);
         let immediate   = ((op >> 25) & 0b1) == 1;
         let opcode      = ((op >> 21) & 0b1111);
-        let _setcc      = ((op >> 20) & 0b1) == 1;
+        let setcc       = ((op >> 20) & 0b1) == 1;
         let rn          = ((op >> 16) & 0b1111) as usize; // ignored by mov
-        let op1         = self.registers[rn];
+        let op1         = self.read_operand(rn, op);
         let rd          = ((op >> 12) & 0b1111) as usize;
-        let op2         = match immediate {
-            false => {
-                let rm              = (op >> 0) & 0xF;
-                let rm              = self.registers[rm as usize];
-                let shift_type      = (op >> 5) & 0x3;
-                let shift_amount    = match ((op >> 4) & 0b1) == 1 {
-                    false => (op >> 7) & 0x1F,
-                    true => {
-                        let rs      = ((op >> 8) & 0xF) as usize;
-                        self.registers[rs] & 0x1F // "The amount by which the register should be shifted may be [...] in the bottom byte of another register (other than R15)." (4.5.2)
-                    },
-                };
-                match shift_type {
-                    0b00 => rm.wrapping_shl(shift_amount),                  // logical left
-                    0b01 => rm.wrapping_shr(shift_amount),                  // logical right
-                    0b10 => (rm as i32).wrapping_shr(shift_amount) as u32,  // arithmetic right
-                    _b11 => rm.rotate_right(shift_amount),                  // rotate right
-                }
-            },
+        let (op2, shifter_carry) = match immediate {
+            false => self.shifted_register(op),
             true => {
                 let rotate  = ((op >> 8) & 0b1111);
                 let imm     = ((op >> 0) & 0b1111_1111);
-                imm.rotate_right(2 * rotate) // 4.5.3 Immediate operand rotates
+                let op2     = imm.rotate_right(2 * rotate); // 4.5.3 Immediate operand rotates
+                (op2, if rotate == 0 { self.cpsr.c() } else { (op2 >> 31) == 1 })
             },
         };
 
         debug_assert_eq!(_sel1, 0b00);
-        assert_eq!(_setcc, false, "setcc not yet implemented");
-
-        match opcode {
-            0b0000 => self.registers[rd] = op1 & op2, // AND
-            0b0001 => self.registers[rd] = op1 ^ op2, // EOR
-            0b0010 => self.registers[rd] = op1.wrapping_sub(op2), // SUB
-            0b0011 => self.registers[rd] = op2.wrapping_sub(op1), // RSB
-            0b0100 => self.registers[rd] = op1.wrapping_add(op2), // ADD
-            0b0101 => self.registers[rd] = op1.wrapping_add(op2).wrapping_add(self.c as u32), // ADC
-            0b0110 => self.registers[rd] = op1.wrapping_sub(op2).wrapping_add(self.c as u32).wrapping_sub(1), // SBC
-            0b0111 => self.registers[rd] = op2.wrapping_sub(op1).wrapping_add(self.c as u32).wrapping_sub(1), // RSC
-            0b1000 => panic!("tst not yet implemented"), // and, but result is not written
-            0b1001 => panic!("teq not yet implemented"), // eor, but result is not written
-            0b1010 => panic!("cmp not yet implemented"), // sub, but result is not written
-            0b1011 => panic!("cmn not yet implemented"), // add, but result is not written
-            0b1100 => self.registers[rd] = op1 | op2, // ORR
-            0b1101 => self.registers[rd] = op2, // MOV
-            0b1110 => self.registers[rd] = op1 & !op2, // BIC (bit clear)
-            _b1111 => self.registers[rd] = !op2, // MVN
-        }
-    }
-
-    // 4.7 Multiply and Multiply-Accumulate (MUL, MLA)
-    // 4.8 Multiply Long and Multiply-Accumulate Long (MULL,MLAL)
-    // 4.9 Single Data Transfer (LDR, STR)
-    // 4.10 Halfword and Signed Data Transfer
-    // 4.11 Block Data Transfer (LDM, STM)
-    // 4.12 Single Data Swap (SWP)
-    // TODO: implement
 
-    /// 4.13 Software Interrupt (SWI)
-    #[inline] fn impl_swi(&mut self, mem: &Memory, op: u32) {
-        let _cond       = ((op >> 28) & 0xF);
-        let _sel1       = ((op >> 24) & 0xF);
-        let _comment    = ((op >>  0) & 0xFFFFFF); // ignored by some/many processors
+        // arithmetic ops produce (result, Some((carry, overflow))), logical ops (result, None)
+        let c = self.cpsr.c();
+        let arith = |x, y, carry_in| { let (result, carry, overflow) = Self::add_with_carry(x, y, carry_in); (result, Some((carry, overflow))) };
+        let (result, carry_overflow) = match opcode {
+            0b0000 => (op1 & op2, None),            // AND
+            0b0001 => (op1 ^ op2, None),            // EOR
+            0b0010 => arith(op1, !op2, true),       // SUB
+            0b0011 => arith(!op1, op2, true),       // RSB
+            0b0100 => arith(op1, op2, false),       // ADD
+            0b0101 => arith(op1, op2, c),           // ADC
+            0b0110 => arith(op1, !op2, c),          // SBC
+            0b0111 => arith(!op1, op2, c),          // RSC
+            0b1000 => (op1 & op2, None),            // TST (AND, but result is not written)
+            0b1001 => (op1 ^ op2, None),            // TEQ (EOR, but result is not written)
+            0b1010 => arith(op1, !op2, true),       // CMP (SUB, but result is not written)
+            0b1011 => arith(op1, op2, false),       // CMN (ADD, but result is not written)
+            0b1100 => (op1 | op2, None),            // ORR
+            0b1101 => (op2, None),                  // MOV
+            0b1110 => (op1 & !op2, None),           // BIC (bit clear)
+            _b1111 => (!op2, None),                 // MVN
+        };
 
-        debug_assert_ne!(_cond, 0b1111, "invalid cond");
-        debug_assert_eq!(_sel1, 0b1111, "swi selector wrong");
+        let write = (opcode & 0b1100) != 0b1000; // TST, TEQ, CMP, CMN
+        match (write, rd, setcc) {
+            (false, _,  _    ) => {},
+            (true,  15, false) if self.config.arch >= Arch::V7A => return self.branch_exchange(result), // ARMv7 interworks...
+            (true,  15, false) => return self.branch_write_pc(result), // ...where ARMv4 .. 6 stay in ARM state
+            (true,  15, true ) => return self.exception_return(result), // e.g. SUBS pc, lr, #4 / MOVS pc, lr
+            (true,  rd, _    ) => self.registers[rd] = result,
+        }
+
+        if setcc {
+            self.cpsr.set(Psr::N, (result >> 31) == 1);
+            self.cpsr.set(Psr::Z, result == 0);
+            match carry_overflow {
+                Some((carry, overflow)) => { self.cpsr.set(Psr::C, carry); self.cpsr.set(Psr::V, overflow); },
+                None => self.cpsr.set(Psr::C, shifter_carry), // V is unaffected by logical ops
+            }
+        }
+    }
+
+    /// 4.6 PSR Transfer (MRS)
+    fn impl_mrs(&mut self, op: u32) {
+        let spsr        = ((op >> 22) & 0b1) == 1;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        self.registers[rd] = match spsr {
+            false => self.cpsr.bits(),
+            true  => match self.spsr() {
+                Some(spsr)  => spsr.bits(),
+                None        => return self.undefined(op), // UNPREDICTABLE without an SPSR
+            },
+        };
+    }
+
+    /// 4.6 PSR Transfer (MSR)
+    fn impl_msr(&mut self, op: u32) {
+        let immediate   = ((op >> 25) & 0b1) == 1;
+        let spsr        = ((op >> 22) & 0b1) == 1;
+        let field_mask  = ((op >> 16) & 0b1111);
+        let value       = match immediate {
+            false => self.registers[(op & 0b1111) as usize],
+            true => {
+                let rotate  = ((op >> 8) & 0b1111);
+                let imm     = ((op >> 0) & 0b1111_1111);
+                imm.rotate_right(2 * rotate)
+            },
+        };
+
+        let mut fields = 0;
+        for field in 0 .. 4 { if field_mask & (1 << field) != 0 { fields |= 0xFF << (8 * field); } } // c, x, s, f
+        let writable = if spsr {
+            Psr::all()
+        } else if self.cpsr.mode() == Some(Mode::User) {
+            Psr::FLAGS | Psr::GE    // user mode can only write the flags
+        } else {
+            Psr::all() - Psr::T - Psr::IT   // execution state is changed via BX & co., never MSR
+        };
+        let mask = writable & Psr::from_bits_truncate(fields);
 
-        match self.registers[7] {
-            1 => { // SC_EXIT
-                std::process::exit(self.registers[0] as _);
+        let value = Psr::from_bits_truncate(value) & mask;
+        match spsr {
+            false => self.set_cpsr((self.cpsr - mask) | value),
+            true  => match self.spsr_mut() {
+                Some(spsr)  => *spsr = (*spsr - mask) | value,
+                None        => self.undefined(op), // UNPREDICTABLE without an SPSR
             },
-            4 => { // SC_WRITE
-                use std::io::{self, *};
+        }
+    }
+
+    /// "AddWithCarry" per the ARMv7 pseudocode: `x + y + carry_in`, returning `(result, carry_out, overflow)`
+    ///
+    /// Subtraction is `x + !y + 1`, and subtraction with borrow is `x + !y + c`.
+    pub(super) fn add_with_carry(x: u32, y: u32, carry_in: bool) -> (u32, bool, bool) {
+        let unsigned_sum    = u64::from(x) + u64::from(y) + u64::from(carry_in);
+        let result          = unsigned_sum as u32;
+        let carry_out       = unsigned_sum > u64::from(u32::MAX);
+        let overflow        = (((x ^ result) & (y ^ result)) >> 31) == 1; // both operands' signs differ from the result's
+        (result, carry_out, overflow)
+    }
+
+    /// ARMv6K+ hints (NOP, YIELD, WFE, WFI, SEV, DBG) living in the MSR immediate encoding space
+    pub(super) fn impl_hint(&mut self, op: u32) -> StepOutcome {
+        match op & 0xFF {
+            0x00 => StepOutcome::Continue,  // NOP
+            0x01 => StepOutcome::Continue,  // YIELD
+            0x02 if std::mem::take(&mut self.event) => StepOutcome::Continue, // WFE with an event already pending
+            0x02 => StepOutcome::Wait,      // WFE
+            0x03 => StepOutcome::Wait,      // WFI
+            0x04 => { self.event = true; StepOutcome::Continue }, // SEV: there are no other cores to signal, but it still sets our own event register
+            _    => StepOutcome::Continue,  // DBG / reserved hints "execute as NOP"
+        }
+    }
+
+    /// Read `r` as an operand of the data processing instruction `op`.
+    ///
+    /// The PC reads 12 bytes ahead instead of the usual 8 when the shift amount comes from a register (4.5.5 Using R15 as an operand.)
+    fn read_operand(&self, r: usize, op: u32) -> u32 {
+        let register_shift = op & 0x0200_0010 == 0x0000_0010;
+        match r {
+            15 if register_shift    => self.registers[15].wrapping_add(4),
+            r                       => self.registers[r],
+        }
+    }
+
+    /// Restore the CPSR from the SPSR and branch to `target` in whatever state that restores
+    ///
+    /// Without an SPSR to restore (User and System modes), this is UNPREDICTABLE: just branch.
+    fn exception_return(&mut self, target: u32) {
+        if let Some(spsr) = self.spsr() {
+            self.itstate = spsr.it();
+            self.set_cpsr(spsr - Psr::IT);
+        }
+        self.branch_write_pc(target);
+    }
 
-                let fileno      = self.registers[0];
-                let mut addr    = self.registers[1];
-                let mut size    = self.registers[2] as usize; // not 16-bit safe... but do you think I care?
+    /// 4.5.2 Shifts: the bottom 12 bits of `op` as a shifted register operand (also used by 4.9 register offsets)
+    ///
+    /// Returns `(value, carry_out)`.
+    fn shifted_register(&self, op: u32) -> (u32, bool) {
+        let rm              = (op >> 0) & 0xF;
+        let rm              = self.read_operand(rm as usize, op);
+        let shift_type      = (op >> 5) & 0x3;
+        let c               = self.cpsr.c();
 
-                let mut stderr : Stderr;
-                let mut stdout : Stdout;
-                let out : &mut dyn Write = match fileno {
-                    1 => { stdout = io::stdout(); &mut stdout },
-                    2 => { stderr = io::stderr(); &mut stderr },
-                    _ => { self.registers[0] = 9; return }, // r0 = EBADF (Bad file number)
+        let shift_amount = match ((op >> 4) & 0b1) == 1 {
+            true => {
+                let rs      = ((op >> 8) & 0xF) as usize;
+                self.registers[rs] & 0xFF // "The amount by which the register should be shifted may be [...] in the bottom byte of another register (other than R15)." (4.5.2)
+            },
+            false => match ((op >> 7) & 0x1F, shift_type) {
+                (0, 0b01) => 32,                                    // LSR #0 encodes LSR #32
+                (0, 0b10) => 32,                                    // ASR #0 encodes ASR #32
+                (0, 0b11) => return (rm >> 1 | (c as u32) << 31, (rm & 1) == 1), // ROR #0 encodes RRX
+                (amount, _) => amount,
+            },
+        };
+
+        self.shift(rm, shift_type, shift_amount)
+    }
+
+    /// Shift `value` by `shift_amount` (0 ..= 255, 0 meaning no shift at all) with LSL/LSR/ASR/ROR `shift_type` (0 ..= 3).
+    ///
+    /// Returns `(value, carry_out)`.
+    pub(super) fn shift(&self, value: u32, shift_type: u32, shift_amount: u32) -> (u32, bool) {
+        if shift_amount == 0 { return (value, self.cpsr.c()) } // no shift, not even special cases
+
+        // shift_amount is 1 ..= 255 from here on
+        match shift_type {
+            0b00 => match shift_amount {                            // logical left
+                1 ..= 31    => (value << shift_amount, ((value >> (32 - shift_amount)) & 1) == 1),
+                32          => (0, (value & 1) == 1),
+                _           => (0, false),
+            },
+            0b01 => match shift_amount {                            // logical right
+                1 ..= 31    => (value >> shift_amount, ((value >> (shift_amount - 1)) & 1) == 1),
+                32          => (0, (value >> 31) == 1),
+                _           => (0, false),
+            },
+            0b10 => match shift_amount {                            // arithmetic right
+                1 ..= 31    => (((value as i32) >> shift_amount) as u32, ((value >> (shift_amount - 1)) & 1) == 1),
+                _           => (((value as i32) >> 31) as u32, (value >> 31) == 1),
+            },
+            _b11 => {                                               // rotate right
+                let value = value.rotate_right(shift_amount & 0x1F);
+                (value, (value >> 31) == 1)
+            },
+        }
+    }
+
+    /// 4.7 Multiply and Multiply-Accumulate (MUL, MLA), ARMv6: UMAAL, ARMv6T2: MLS
+    fn impl_multiply(&mut self, op: u32) {
+        let opcode      = ((op >> 21) & 0b111);
+        let setcc       = ((op >> 20) & 0b1) == 1;
+        let rd          = ((op >> 16) & 0b1111) as usize; // RdHi for UMAAL
+        let rn          = ((op >> 12) & 0b1111) as usize; // accumulator, RdLo for UMAAL
+        let rs          = ((op >>  8) & 0b1111) as usize;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let product     = self.registers[rm].wrapping_mul(self.registers[rs]);
+        let result      = match opcode {
+            0b000 => product,                                   // MUL
+            0b001 => product.wrapping_add(self.registers[rn]),  // MLA
+            0b011 if self.config.arch >= Arch::V7A => self.registers[rn].wrapping_sub(product), // MLS
+            0b010 if self.config.arch >= Arch::V6 => { // UMAAL: can't overflow 64 bits, (2^32-1)^2 + 2 * (2^32-1) == 2^64-1
+                let result = u64::from(self.registers[rm]) * u64::from(self.registers[rs]) + u64::from(self.registers[rd]) + u64::from(self.registers[rn]);
+                self.registers[rn] = result as u32;
+                self.registers[rd] = (result >> 32) as u32;
+                return;
+            },
+            _ => return self.undefined(op),
+        };
+
+        self.registers[rd] = result;
+        if setcc {
+            self.cpsr.set(Psr::N, (result >> 31) == 1);
+            self.cpsr.set(Psr::Z, result == 0);
+            // ARMv4 leaves C meaningless and ARMv5+ leaves it unchanged - leave it be
+        }
+    }
+
+    /// 4.8 Multiply Long and Multiply-Accumulate Long (MULL,MLAL)
+    fn impl_multiply_long(&mut self, op: u32) {
+        let signed      = ((op >> 22) & 0b1) == 1;
+        let accumulate  = ((op >> 21) & 0b1) == 1;
+        let setcc       = ((op >> 20) & 0b1) == 1;
+        let rdhi        = ((op >> 16) & 0b1111) as usize;
+        let rdlo        = ((op >> 12) & 0b1111) as usize;
+        let rs          = ((op >>  8) & 0b1111) as usize;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let mut result = match signed {
+            false => u64::from(self.registers[rm]).wrapping_mul(u64::from(self.registers[rs])),
+            true  => i64::from(self.registers[rm] as i32).wrapping_mul(i64::from(self.registers[rs] as i32)) as u64,
+        };
+        if accumulate { result = result.wrapping_add(u64::from(self.registers[rdhi]) << 32 | u64::from(self.registers[rdlo])); }
+
+        self.registers[rdlo] = result as u32;
+        self.registers[rdhi] = (result >> 32) as u32;
+
+        if setcc {
+            self.cpsr.set(Psr::N, (result >> 63) == 1);
+            self.cpsr.set(Psr::Z, result == 0);
+            // "Both the C and V flags are set to meaningless values" (4.8.2) - leave them be
+        }
+    }
+
+    /// 4.9 Single Data Transfer (LDR, STR)
+    fn impl_single_data_transfer(&mut self, mem: &Memory, op: u32) {
+        let register    = ((op >> 25) & 0b1) == 1; // "I" bit, but inverted vs. data processing
+        let pre         = ((op >> 24) & 0b1) == 1;
+        let up          = ((op >> 23) & 0b1) == 1;
+        let byte        = ((op >> 22) & 0b1) == 1;
+        let writeback   = ((op >> 21) & 0b1) == 1; // for post-indexed transfers, this is the "T" bit instead, which we don't distinguish
+        let load        = ((op >> 20) & 0b1) == 1;
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let offset      = match register {
+            false => (op >> 0) & 0xFFF,
+            true  => self.shifted_register(op & !0x10).0, // shift amounts can't come from a register here
+        };
+
+        let base        = self.registers[rn];
+        let offset_base = if up { base.wrapping_add(offset) } else { base.wrapping_sub(offset) };
+        let addr        = if pre { offset_base } else { base };
+        if !pre || writeback { self.registers[rn] = offset_base; } // post-indexed transfers always write back
+
+        match (load, byte) {
+            (false, false)  => self.store_u32(mem, addr, if rd == 15 { self.stored_pc() } else { self.registers[rd] }),
+            (false, true)   => self.write_data_u8(mem, addr, self.registers[rd] as u8),
+            (true,  byte)   => {
+                let value = match byte {
+                    true  => self.read_data_u8(mem, addr).into(),
+                    false => self.load_u32(mem, addr),
                 };
+                match rd {
+                    15 => self.load_write_pc(value),
+                    rd => self.registers[rd] = value,
+                }
+            },
+        }
+    }
+
+    /// 4.10 Halfword and Signed Data Transfer
+    fn impl_halfword_transfer(&mut self, mem: &Memory, op: u32) {
+        let pre         = ((op >> 24) & 0b1) == 1;
+        let up          = ((op >> 23) & 0b1) == 1;
+        let immediate   = ((op >> 22) & 0b1) == 1;
+        let writeback   = ((op >> 21) & 0b1) == 1;
+        let load        = ((op >> 20) & 0b1) == 1;
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let sh          = ((op >>  5) & 0b11);
+        let offset      = match immediate {
+            false => self.registers[(op & 0b1111) as usize],
+            true  => ((op >> 4) & 0xF0) | (op & 0xF),
+        };
+
+        let doubleword  = !load && sh != 0b01; // LDRD / STRD (ARMv5TE)
+        if doubleword && !self.require(Arch::V5TE, op) { return }
+        if doubleword && (rd & 1 != 0 || rd == 14) { return self.undefined(op) } // odd Rt, or Rt2 = PC, are UNPREDICTABLE
+
+        let base        = self.registers[rn];
+        let offset_base = if up { base.wrapping_add(offset) } else { base.wrapping_sub(offset) };
+        let addr        = if pre { offset_base } else { base };
+        let stored      = (self.registers[rd], self.registers[rd | 1]); // STRD's Rt & Rt2, before any writeback
+        if !pre || writeback { self.registers[rn] = offset_base; }
+
+        if doubleword {
+            let align = if self.config.arch >= Arch::V6 { 4 } else { 8 }; // ARMv5TE requires doubleword alignment, ARMv6+ word alignment
+            if self.misaligned(addr, align, if sh == 0b10 { MemoryFlags::READ } else { MemoryFlags::WRITE }) { return }
+            let addr = addr & !3; // otherwise the low address bits are ignored
+            match sh {
+                0b10 => { // LDRD
+                    self.registers[rd]      = self.read_data_u32(mem, addr);
+                    self.registers[rd + 1]  = self.read_data_u32(mem, addr.wrapping_add(4));
+                },
+                _b11 => { // STRD
+                    self.write_data_u32(mem, addr, stored.0);
+                    self.write_data_u32(mem, addr.wrapping_add(4), stored.1);
+                },
+            }
+            return
+        }
+
+        if !load { self.store_u16(mem, addr, self.registers[rd] as u16); return } // STRH
+
+        let value = match sh {
+            0b01 => self.load_u16(mem, addr).into(),                    // LDRH
+            0b10 => self.read_data_u8(mem, addr) as i8 as u32,          // LDRSB
+            _b11 => self.load_u16(mem, addr) as i16 as u32,             // LDRSH
+        };
+        match rd {
+            15 => self.branch_to(value & !3),
+            rd => self.registers[rd] = value,
+        }
+    }
+
+    /// 4.11 Block Data Transfer (LDM, STM)
+    fn impl_block_data_transfer(&mut self, mem: &Memory, op: u32) {
+        let pre         = ((op >> 24) & 0b1) == 1;
+        let up          = ((op >> 23) & 0b1) == 1;
+        let psr_user    = ((op >> 22) & 0b1) == 1; // "S" bit - restores the CPSR when loading the PC, otherwise forces user bank transfers
+        let writeback   = ((op >> 21) & 0b1) == 1;
+        let load        = ((op >> 20) & 0b1) == 1;
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rlist       = ((op >>  0) & 0xFFFF) as u16;
+        let user_bank   = psr_user && !(load && rlist & 0x8000 != 0); // LDM^ without the PC, STM^
+
+        // registers are always transferred lowest-numbered to lowest address, regardless of direction
+        let bytes       = 4 * rlist.count_ones();
+        let base        = self.registers[rn];
+        let (mut addr, final_base) = match (up, pre) {
+            (true,  false)  => (base,                                       base.wrapping_add(bytes)), // IA
+            (true,  true)   => (base.wrapping_add(4),                       base.wrapping_add(bytes)), // IB
+            (false, false)  => (base.wrapping_sub(bytes).wrapping_add(4),   base.wrapping_sub(bytes)), // DA
+            (false, true)   => (base.wrapping_sub(bytes),                   base.wrapping_sub(bytes)), // DB
+        };
 
-                let mut buffer = [0u8; 512];
-                while size > 0 {
-                    let read = size.min(buffer.len());
-                    mem.read_bytes(addr, MemoryFlags::READ, &mut buffer[..read]);
-                    out.write_all(&buffer[..read]).unwrap();
-                    addr += read as u32;
-                    size -= read;
+        if load {
+            if writeback { self.registers[rn] = final_base; } // a loaded base takes priority over write-back
+            for r in (0 .. 16).filter(|r| rlist & (1 << r) != 0) {
+                let value = self.read_data_u32(mem, addr & !3);
+                match r {
+                    15 if psr_user => self.exception_return(value), // e.g. LDMFD sp!, {..., pc}^
+                    15 => self.load_write_pc(value),
+                    r if user_bank => self.set_register_of(Mode::User, r, value),
+                    r  => self.registers[r] = value,
                 }
+                addr = addr.wrapping_add(4);
+            }
+        } else {
+            for r in (0 .. 16).filter(|r| rlist & (1 << r) != 0) {
+                let value = match r {
+                    15              => self.stored_pc(),
+                    r if user_bank  => self.register_of(Mode::User, r),
+                    r               => self.registers[r],
+                };
+                self.write_data_u32(mem, addr & !3, value); // a stored base is its original value
+                addr = addr.wrapping_add(4);
+            }
+            if writeback { self.registers[rn] = final_base; }
+        }
+    }
+
+    /// 4.12 Single Data Swap (SWP)
+    fn impl_swp(&mut self, mem: &Memory, op: u32) {
+        if !self.require_features(Features::SWP, op) { return }
+        let byte        = ((op >> 22) & 0b1) == 1;
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let addr        = self.registers[rn];
+        let flags       = MemoryFlags::READ | MemoryFlags::WRITE;
+        match byte {
+            true  => self.watch(mem, addr, 1, flags, (self.registers[rm] as u8).into()),
+            false => self.watch(mem, addr & !3, 4, flags, self.data_endian().u32(self.registers[rm]).into()),
+        }
+        self.registers[rd] = match byte {
+            true  => self.data(mem.try_swap_u8(addr, flags, self.registers[rm] as u8)).into(),
+            false => match self.cpsr.e() {
+                false => self.data(mem.try_swap_u32_aligned(addr & !3, flags, self.registers[rm])).rotate_right(8 * (addr & 3)), // rotates like LDR
+                true  => self.data(mem.try_swap_u32_aligned(addr & !3, flags, self.registers[rm].swap_bytes())).swap_bytes().rotate_right(8 * (addr & 3)),
             },
-            _other => {
-                panic!("swi #{} - unimplemented SC_??? {}", _comment, self.registers[7]);
+        };
+    }
+
+    /// ARMv6: Load/Store exclusive (LDREX, STREX, and the ARMv6K B/H/D variants)
+    ///
+    /// Rather than snooping every store for writes to the tagged address, STREX* succeeds iff memory still holds
+    /// the value LDREX* loaded, checked and written atomically via [`Memory::compare_exchange_u32_aligned`] & co.
+    /// This lets other threads' plain stores break the reservation, at the cost of not noticing ABA writes.
+    fn impl_exclusive_transfer(&mut self, mem: &Memory, op: u32) {
+        if !self.require(Arch::V6, op) { return }
+        let size        = ((op >> 21) & 0b11);
+        let load        = ((op >> 20) & 0b1) == 1;
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let rt          = ((op >>  0) & 0b1111) as usize; // STREX* only
+
+        let addr        = self.registers[rn];
+        let flags       = MemoryFlags::READ | MemoryFlags::WRITE;
+        let be          = self.cpsr.e();
+        let swap16      = |v: u16| if be { v.swap_bytes() } else { v };
+        let swap32      = |v: u32| if be { v.swap_bytes() } else { v };
+        let bytes       = match size { 0b00 => 4, 0b01 => 8, 0b10 => 1, _ => 2 };
+        let pair        = if load { rd } else { rt }; // LDREXD / STREXD's Rt, Rt+1
+        if size == 0b01 && (pair & 1 != 0 || pair == 14) { return self.undefined(op) } // UNPREDICTABLE
+        if addr & (bytes - 1) != 0 { // exclusives always fault when misaligned, regardless of SCTLR.A
+            let access = if load { MemoryFlags::READ } else { MemoryFlags::WRITE };
+            return self.data::<()>(Err(MemFault { addr, access, flags: MemoryFlags::NONE, kind: MemFaultKind::Alignment }));
+        }
+        if load {
+            // the monitor remembers the value as it was in memory (e.g. not byte swapped)
+            self.watch(mem, addr, bytes, MemoryFlags::READ, 0);
+            let raw = match size {
+                0b00 => u64::from(self.data(mem.try_read_u32_aligned(addr, MemoryFlags::READ))),    // LDREX
+                0b01 => self.data(mem.try_read_u64_aligned(addr, MemoryFlags::READ)),               // LDREXD
+                0b10 => u64::from(self.data(mem.try_read_u8(addr, MemoryFlags::READ))),             // LDREXB
+                _    => u64::from(self.data(mem.try_read_u16_aligned(addr, MemoryFlags::READ))),    // LDREXH
+            };
+            self.registers[rd] = match size {
+                0b10 => raw as u32,
+                0b11 => swap16(raw as u16).into(),
+                _    => swap32(raw as u32),
+            };
+            if size == 0b01 { self.registers[rd + 1] = swap32((raw >> 32) as u32); }
+            self.exclusive = Some((addr, raw));
+        } else {
+            let value = self.registers[rt];
+            let new = match size {
+                0b00 => u64::from(swap32(value)),
+                0b01 => u64::from(swap32(self.registers[rt + 1])) << 32 | u64::from(swap32(value)),
+                0b10 => u64::from(value as u8),
+                _    => u64::from(swap16(value as u16)),
+            };
+            self.watch(mem, addr, bytes, MemoryFlags::WRITE, new); // even if the STREX* fails
+            let stored = match self.exclusive.take() {
+                Some((tagged, current)) if tagged == addr => match size {
+                    0b00 => self.data(mem.try_compare_exchange_u32_aligned(addr, flags, current as u32, swap32(value)).map(|r| r.is_ok())),
+                    0b01 => self.data(mem.try_compare_exchange_u64_aligned(addr, flags, current, u64::from(swap32(self.registers[rt + 1])) << 32 | u64::from(swap32(value))).map(|r| r.is_ok())),
+                    0b10 => self.data(mem.try_compare_exchange_u8(addr, flags, current as u8, value as u8).map(|r| r.is_ok())),
+                    _    => self.data(mem.try_compare_exchange_u16_aligned(addr, flags, current as u16, swap16(value as u16)).map(|r| r.is_ok())),
+                },
+                _ => false,
+            };
+            self.registers[rd] = if stored { 0 } else { 1 };
+        }
+    }
+
+    /// ARMv6: Change Processor State (CPS): `imod` 0b10 clears (enables) & 0b11 sets (disables) the `aif` bits, and/or switch to `mode`.
+    /// A no-op outside privileged modes.
+    pub(super) fn impl_cps(&mut self, imod: u32, aif: Psr, mode: Option<u32>) {
+        if !self.current_mode().is_privileged() { return }
+        let mut cpsr = self.cpsr;
+        match imod {
+            0b10 => cpsr -= aif,
+            0b11 => cpsr |= aif,
+            _    => {},
+        }
+        if let Some(mode) = mode { cpsr = (cpsr - Psr::MODE) | Psr::from_bits_truncate(mode); }
+        self.set_cpsr(cpsr);
+    }
+
+    /// Instructions with the 0b1111 condition field, which aren't conditional at all
+    fn impl_unconditional(&mut self, mem: &Memory, op: u32) {
+        match op {
+            _ if self.config.arch < Arch::V5TE => {}, // ARMv4's NV condition: never executed
+            0xF57F_F01F if self.config.arch >= Arch::V6 => self.exclusive = None, // CLREX (ARMv6K)
+            _ if op & 0xFFFF_FDFF == 0xF101_0000 && self.config.arch >= Arch::V6 => self.cpsr.set(Psr::E, ((op >> 9) & 1) == 1), // SETEND (ARMv6)
+            _ if op & 0xFFF1_FE20 == 0xF100_0000 && self.config.arch >= Arch::V6 => { // CPS (ARMv6)
+                let imod = (op >> 18) & 0b11;
+                let mode = if (op >> 17) & 1 == 1 { Some(op & 0x1F) } else { None };
+                self.impl_cps(imod, Psr::from_bits_truncate(op & 0x1C0), mode);
             },
+            // DSB, DMB, ISB: every access is already sequentially consistent & there's no prefetch to flush
+            0xF57F_F040 ..= 0xF57F_F06F if self.config.arch >= Arch::V7A => {},
+            _ if op & 0xFE00_0000 == 0xFA00_0000 => self.impl_blx_immediate(op),
+            // PLD (ARMv5TE), PLDW & PLI (ARMv7) (immediate & register): there's no cache to preload into
+            _ if op & 0xFD70_F000 == 0xF550_F000 => {},
+            _ if (op & 0xFD70_F000 == 0xF510_F000 || op & 0xFD70_F000 == 0xF450_F000) && self.config.arch >= Arch::V7A => {},
+            _ if op & 0xFE00_0000 == 0xF200_0000 || op & 0xFF10_0000 == 0xF400_0000 => if self.require_features(Features::NEON, op) { self.impl_neon(mem, op) },
+            _ if op & 0x0C00_0000 == 0x0C00_0000 => self.impl_coprocessor(mem, op), // LDC2, STC2, MCRR2, MRRC2, CDP2, MCR2, MRC2
+            _other      => self.undefined(op),
         }
     }
 
-    // 4.14 Coprocessor Data Operations (CDP)
-    // 4.15 Coprocessor Data Transfers (LDC, STC)
-    // 4.16 Coprocessor Register Transfers (MRC, MCR)
-    // 4.17 Undefined Instruction
-    // TODO: implement
+    /// ARMv6T2: Move wide (MOVW, MOVT)
+    fn impl_move_wide(&mut self, op: u32) {
+        if !self.require(Arch::V7A, op) { return }
+        let top         = ((op >> 22) & 0b1) == 1;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let imm16       = ((op >> 4) & 0xF000) | (op & 0x0FFF);
+
+        self.registers[rd] = match top {
+            false => imm16,                                         // MOVW
+            true  => (self.registers[rd] & 0xFFFF) | (imm16 << 16), // MOVT
+        };
+    }
+
+    /// ARMv5TE: Saturating add/subtract (QADD, QSUB, QDADD, QDSUB)
+    fn impl_saturating_add_subtract(&mut self, op: u32) {
+        if !self.require(Arch::V5TE, op) { return }
+        let opcode      = ((op >> 21) & 0b11);
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let m           = i64::from(self.registers[rm] as i32);
+        let n           = i64::from(self.registers[rn] as i32);
+        let (n, doubling_saturated) = match opcode & 0b10 {
+            0     => (n, false),
+            _     => { let (n, sat) = Self::signed_saturate(2 * n, 32); (i64::from(n as i32), sat) }, // QDADD, QDSUB
+        };
+        let (result, saturated) = match opcode & 0b01 {
+            0     => Self::signed_saturate(m + n, 32), // QADD, QDADD
+            _     => Self::signed_saturate(m - n, 32), // QSUB, QDSUB
+        };
+
+        self.registers[rd] = result;
+        if doubling_saturated || saturated { self.cpsr.insert(Psr::Q); } // sticky
+    }
+
+    /// ARMv5TE: Signed multiply (SMLAxy, SMLAWy, SMULWy, SMLALxy, SMULxy)
+    fn impl_signed_halfword_multiply(&mut self, op: u32) {
+        if !self.require(Arch::V5TE, op) { return }
+        let opcode      = ((op >> 21) & 0b11);
+        let rd          = ((op >> 16) & 0b1111) as usize; // RdHi for SMLALxy
+        let rn          = ((op >> 12) & 0b1111) as usize; // RdLo for SMLALxy
+        let rs          = ((op >>  8) & 0b1111) as usize;
+        let y           = ((op >>  6) & 0b1) == 1;
+        let x           = ((op >>  5) & 0b1) == 1;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let half        = |value: u32, top: bool| i32::from((if top { value >> 16 } else { value }) as u16 as i16);
+        let m           = self.registers[rm];
+        let s           = half(self.registers[rs], y);
+
+        match (opcode, x) {
+            (0b00, _) => { // SMLAxy
+                let (result, overflow) = (half(m, x) * s).overflowing_add(self.registers[rn] as i32);
+                self.registers[rd] = result as u32;
+                if overflow { self.cpsr.insert(Psr::Q); }
+            },
+            (0b01, false) => { // SMLAWy
+                let product = ((i64::from(m as i32) * i64::from(s)) >> 16) as i32;
+                let (result, overflow) = product.overflowing_add(self.registers[rn] as i32);
+                self.registers[rd] = result as u32;
+                if overflow { self.cpsr.insert(Psr::Q); }
+            },
+            (0b01, true) => { // SMULWy
+                self.registers[rd] = ((i64::from(m as i32) * i64::from(s)) >> 16) as u32;
+            },
+            (0b10, _) => { // SMLALxy - wraps silently, no Q
+                let acc = u64::from(self.registers[rd]) << 32 | u64::from(self.registers[rn]);
+                let result = acc.wrapping_add(i64::from(half(m, x) * s) as u64);
+                self.registers[rn] = result as u32;
+                self.registers[rd] = (result >> 32) as u32;
+            },
+            (_, _) => { // SMULxy
+                self.registers[rd] = (half(m, x) * s) as u32;
+            },
+        }
+    }
+
+    /// Clamp `value` to the range of a `bits`-bit signed integer, returning `(result, saturated)`
+    pub(super) fn signed_saturate(value: i64, bits: u32) -> (u32, bool) {
+        let max = (1i64 << (bits - 1)) - 1;
+        let min = -(1i64 << (bits - 1));
+        if value > max { (max as u32, true) } else if value < min { (min as u32, true) } else { (value as u32, false) }
+    }
+
+    /// Clamp `value` to the range of a `bits`-bit unsigned integer, returning `(result, saturated)`
+    pub(super) fn unsigned_saturate(value: i64, bits: u32) -> (u32, bool) {
+        let max = (1i64 << bits) - 1;
+        if value > max { (max as u32, true) } else if value < 0 { (0, true) } else { (value as u32, false) }
+    }
+
+    /// ARMv5T: Breakpoint (BKPT): a [`StepOutcome::Breakpoint`] with the 16-bit immediate, leaving the PC on the instruction
+    fn impl_bkpt(&mut self, op: u32) -> StepOutcome {
+        if !self.require(Arch::V5TE, op) { return StepOutcome::Continue }
+        let imm         = ((op >> 4) & 0xFFF0) | (op & 0xF);
+        StepOutcome::Breakpoint { pc: self.next_instruction_addr(), imm }
+    }
+
+    /// 4.13 Software Interrupt (SWI)
+    #[inline] pub(super) fn impl_swi(&mut self, op: u32) -> StepOutcome {
+        let _cond       = ((op >> 28) & 0xF);
+        let _sel1       = ((op >> 24) & 0xF);
+        let comment     = ((op >>  0) & 0xFFFFFF); // ignored by some/many processors
+
+        debug_assert_ne!(_cond, 0b1111, "invalid cond");
+        debug_assert_eq!(_sel1, 0b1111, "swi selector wrong");
+
+        StepOutcome::Svc(comment)
+    }
 }
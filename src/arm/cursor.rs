@@ -0,0 +1,71 @@
+use super::*;
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+
+
+/// [`io::Read`], [`io::Write`] & [`io::Seek`] over guest memory, from [`Memory::cursor`]
+///
+/// The position is a guest address (like `/proc/self/mem`.)  Reads & writes stop at the end of the address space, and short at the first
+/// page that isn't mapped for the cursor's flags - failing with [`io::ErrorKind::PermissionDenied`] (wrapping the [`MemFault`]) only if nothing was transferred.
+#[derive(Clone, Copy)]
+pub struct MemoryCursor<'m> {
+    mem:    &'m Memory,
+    pos:    u64,
+    flags:  MemoryFlags,
+}
+
+impl Memory {
+    /// A cursor at `addr`, reading with `flags` (typically [`MemoryFlags::READ`]) and writing with `flags` (typically [`MemoryFlags::WRITE`])
+    pub fn cursor(&self, addr: u32, flags: MemoryFlags) -> MemoryCursor<'_> { MemoryCursor { mem: self, pos: addr.into(), flags } }
+}
+
+impl MemoryCursor<'_> {
+    pub fn position(&self) -> u64 { self.pos }
+
+    /// Transfer up to `len` bytes at the cursor with `transfer`, and advance past however many made it
+    fn transfer(&mut self, len: usize, transfer: impl FnOnce(u32, usize) -> Result<(), MemFault>) -> io::Result<usize> {
+        let len = len.min(usize::try_from(END - self.pos).unwrap_or(usize::MAX));
+        if len == 0 { return Ok(0) }
+        let addr = self.pos as u32;
+        let n = match transfer(addr, len) {
+            Ok(())                                  => len,
+            Err(fault) if fault.addr != addr        => fault.addr.wrapping_sub(addr) as usize, // everything before the faulting page was transferred
+            Err(fault)                              => return Err(io::Error::new(io::ErrorKind::PermissionDenied, fault)),
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Read for MemoryCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (mem, flags) = (self.mem, self.flags);
+        self.transfer(buf.len(), |addr, len| mem.try_read_bytes(addr, flags, &mut buf[..len]))
+    }
+}
+
+impl Write for MemoryCursor<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (mem, flags) = (self.mem, self.flags);
+        self.transfer(buf.len(), |addr, len| mem.try_write_bytes(addr, flags, &buf[..len]))
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+impl Seek for MemoryCursor<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(n)      => Some(n),
+            SeekFrom::End(n)        => END.checked_add_signed(n),
+            SeekFrom::Current(n)    => self.pos.checked_add_signed(n),
+        };
+        match pos {
+            Some(pos) if pos <= END => { self.pos = pos; Ok(pos) },
+            _                       => Err(io::Error::new(io::ErrorKind::InvalidInput, "arm::MemoryCursor: tried to seek outside the address space")),
+        }
+    }
+}
+
+const END : u64 = 1 << 32;
@@ -0,0 +1,84 @@
+use super::*;
+
+use std::io::{self, Write};
+use std::ops::{Bound, Range, RangeBounds};
+
+
+
+impl Memory {
+    /// Write a canonical hexdump (like `hexdump -C`) of `range` to `out`, for crash reports & debuggers.
+    ///
+    /// Never faults or touches devices: unmapped & MMIO bytes are shown as `--`.  Runs of identical lines are collapsed into a `*`.
+    pub fn dump(&self, range: Range<u32>, out: &mut impl Write) -> io::Result<()> {
+        let mut prev = None;
+        let mut collapsed = false;
+        let mut addr = u64::from(range.start);
+        let end = u64::from(range.end);
+        while addr < end {
+            let len = (end - addr).min(16) as usize;
+            let mut line = [None; 16];
+            self.inspect(addr as u32, &mut line[..len]);
+
+            let last = addr + 16 >= end;
+            if prev == Some(line) && !last {
+                if !collapsed { writeln!(out, "*")?; }
+                collapsed = true;
+            } else {
+                collapsed = false;
+                write!(out, "{:08x} ", addr)?;
+                for (i, byte) in line[..len].iter().enumerate() {
+                    if i == 8 { write!(out, " ")?; }
+                    match byte {
+                        Some(byte)  => write!(out, " {:02x}", byte)?,
+                        None        => write!(out, " --")?,
+                    }
+                }
+                for i in len .. 16 { write!(out, "{}   ", if i == 8 { " " } else { "" })?; }
+                write!(out, "  |")?;
+                for byte in &line[..len] {
+                    let c = match byte { Some(b @ 0x20 ..= 0x7E) => char::from(*b), Some(_) => '.', None => ' ' };
+                    write!(out, "{}", c)?;
+                }
+                writeln!(out, "|")?;
+            }
+            prev = Some(line);
+            addr += 16;
+        }
+        writeln!(out, "{:08x}", end)
+    }
+
+    /// Write the raw bytes of `range` to `out` (e.g. to load into a disassembler.)  Unmapped & MMIO bytes are written as zeros.
+    /// Inclusive ranges can reach the very end of the address space (e.g. `0xFFFF_0000 ..= 0xFFFF_FFFF`.)
+    pub fn dump_raw(&self, range: impl RangeBounds<u32>, out: &mut impl Write) -> io::Result<()> {
+        let mut addr = match range.start_bound() {
+            Bound::Included(start)  => u64::from(*start),
+            Bound::Excluded(start)  => u64::from(*start) + 1,
+            Bound::Unbounded        => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end)    => u64::from(*end) + 1,
+            Bound::Excluded(end)    => u64::from(*end),
+            Bound::Unbounded        => 1 << 32,
+        };
+        let mut chunk = [None; 0x1000];
+        while addr < end {
+            let len = (end - addr).min(0x1000 - (addr & 0xFFF)) as usize;
+            self.inspect(addr as u32, &mut chunk[..len]);
+            let bytes : Vec<u8> = chunk[..len].iter().map(|b| b.unwrap_or(0)).collect();
+            out.write_all(&bytes)?;
+            addr += len as u64;
+        }
+        Ok(())
+    }
+
+    /// Read `bytes` at `addr` without faulting or touching devices - [`None`] where unmapped or MMIO
+    fn inspect(&self, addr: u32, bytes: &mut [Option<u8>]) {
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let addr = addr.wrapping_add(i as u32);
+            *byte = match self.page_inspectable(self.page_idx(addr)) {
+                true    => self.try_read_u8(addr, MemoryFlags::NONE).ok(),
+                false   => None,
+            };
+        }
+    }
+}
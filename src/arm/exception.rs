@@ -0,0 +1,131 @@
+use super::*;
+
+// References:
+// ARMv4? https://developer.arm.com/documentation/ddi0210/c/Programmer-s-Model/Exceptions
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (B1.8 Exception handling)
+//
+// Limitations:
+// - No Security or Virtualization Extensions: no Monitor/Hyp modes, and SCR / HCR routing doesn't exist
+
+
+
+/// An exception to enter with [`Cpu::take_exception`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Exception {
+    Reset,
+    /// Call with the PC still on the undefined instruction, as [`StepOutcome::Undefined`] leaves it
+    Undefined,
+    /// Call with the PC already past the SVC, as [`StepOutcome::Svc`] leaves it
+    Svc,
+    /// Call with the PC on the instruction whose fetch aborted, as [`StepOutcome::PrefetchAbort`] (or [`StepOutcome::Breakpoint`]) leaves it
+    PrefetchAbort,
+    /// Call with the PC on the instruction whose load / store aborted, as [`StepOutcome::DataAbort`] (or [`StepOutcome::StackOverflow`]) leaves it
+    DataAbort,
+    /// Taken by [`Cpu::step1`] while [`Cpu::raise_irq`]ed and unmasked. If calling this directly, call between instructions, with the PC on the next instruction to execute
+    Irq,
+    /// Taken by [`Cpu::step1`] while [`Cpu::raise_fiq`]ed and unmasked. If calling this directly, call between instructions, with the PC on the next instruction to execute
+    Fiq,
+}
+
+impl Exception {
+    /// Offset of this exception's entry into the vector table
+    pub fn vector_offset(self) -> u32 {
+        match self {
+            Exception::Reset            => 0x00,
+            Exception::Undefined        => 0x04,
+            Exception::Svc              => 0x08,
+            Exception::PrefetchAbort    => 0x0C,
+            Exception::DataAbort        => 0x10,
+            // 0x14 is unused (ARMv4's address exception, or Hyp traps with the Virtualization Extensions)
+            Exception::Irq              => 0x18,
+            Exception::Fiq              => 0x1C,
+        }
+    }
+
+    /// The mode this exception is taken in
+    pub fn mode(self) -> Mode {
+        match self {
+            Exception::Reset            => Mode::Supervisor,
+            Exception::Undefined        => Mode::Undefined,
+            Exception::Svc              => Mode::Supervisor,
+            Exception::PrefetchAbort    => Mode::Abort,
+            Exception::DataAbort        => Mode::Abort,
+            Exception::Irq              => Mode::Irq,
+            Exception::Fiq              => Mode::Fiq,
+        }
+    }
+}
+
+impl Cpu {
+    /// SCTLR.A: alignment fault checking
+    pub(super) const SCTLR_A : u32 = 1 << 1;
+    /// SCTLR.V: high exception vectors
+    const SCTLR_V   : u32 = 1 << 13;
+    /// SCTLR.EE: exceptions are taken with CPSR.E set
+    const SCTLR_EE  : u32 = 1 << 25;
+    /// SCTLR.TE: exceptions are taken in Thumb state
+    const SCTLR_TE  : u32 = 1 << 30;
+
+    /// The address of the exception vector table: 0xFFFF_0000 with SCTLR.V ("hivecs"), otherwise VBAR (0x0000_0000 by default)
+    pub fn vector_base(&self) -> u32 { if self.cp15.sctlr & Self::SCTLR_V != 0 { 0xFFFF_0000 } else { self.cp15.vbar } }
+
+    /// Enter `exception` the way the hardware would: save the CPSR to the new mode's SPSR, switch modes (banking registers),
+    /// set the new mode's LR to the return address (plus the architectural offset - e.g. `SUBS pc, lr, #4` returns from an IRQ),
+    /// mask interrupts, and continue at the exception's vector.
+    ///
+    /// This is how a host hands [`StepOutcome::Undefined`], [`StepOutcome::Svc`] and aborts to the guest's own handlers instead of emulating them.
+    /// See each [`Exception`] for where the PC is expected to be when calling this.
+    pub fn take_exception(&mut self, exception: Exception) {
+        let thumb   = self.cpsr.t();
+        let ret     = self.next_instruction_addr();
+        let lr      = match exception {
+            Exception::Reset            => None, // UNKNOWN
+            Exception::Undefined        => Some(ret.wrapping_add(if thumb { 2 } else { 4 })),
+            Exception::Svc              => Some(ret),
+            Exception::PrefetchAbort    => Some(ret.wrapping_add(4)),
+            Exception::DataAbort        => Some(ret.wrapping_add(8)),
+            Exception::Irq              => Some(ret.wrapping_add(4)),
+            Exception::Fiq              => Some(ret.wrapping_add(4)),
+        };
+
+        let mut spsr = self.cpsr;
+        spsr.set_it(std::mem::take(&mut self.itstate));
+        let mode = exception.mode();
+
+        let mut cpsr = self.cpsr - Psr::J - Psr::T - Psr::E - Psr::IT;
+        cpsr.set_mode(mode);
+        cpsr |= Psr::I;
+        if matches!(exception, Exception::Reset | Exception::Fiq) { cpsr |= Psr::F; }
+        if !matches!(exception, Exception::Undefined | Exception::Svc) { cpsr |= Psr::A; }
+        if self.cp15.sctlr & Self::SCTLR_EE != 0 { cpsr |= Psr::E; }
+        if self.cp15.sctlr & Self::SCTLR_TE != 0 { cpsr |= Psr::T; }
+        self.set_cpsr(cpsr);
+
+        if let Some(s) = self.spsr_mut() { *s = spsr; }
+        if let Some(lr) = lr { self.registers[14] = lr; }
+        self.set_next_instruction_addr(self.vector_base().wrapping_add(exception.vector_offset()));
+        self.cycles += 2; // refill the pipeline
+    }
+
+    /// Assert the IRQ line: [`Cpu::step1`] takes an IRQ before its instruction whenever CPSR.I is clear, until [`Cpu::lower_irq`].
+    /// Like real hardware, the line is level sensitive - device models should lower it once the guest acknowledges the device, or the guest will be interrupted again.
+    pub fn raise_irq(&mut self) { self.irq = true; }
+    pub fn lower_irq(&mut self) { self.irq = false; }
+    pub fn irq_raised(&self) -> bool { self.irq }
+
+    /// Assert the FIQ line: [`Cpu::step1`] takes an FIQ before its instruction whenever CPSR.F is clear, until [`Cpu::lower_fiq`].
+    /// FIQs take priority over IRQs.
+    pub fn raise_fiq(&mut self) { self.fiq = true; }
+    pub fn lower_fiq(&mut self) { self.fiq = false; }
+    pub fn fiq_raised(&self) -> bool { self.fiq }
+
+    /// Take an FIQ or IRQ if one is raised and unmasked
+    pub(super) fn take_interrupt(&mut self) {
+        if self.fiq && !self.cpsr.f() {
+            self.take_exception(Exception::Fiq);
+        } else if self.irq && !self.cpsr.i() {
+            self.take_exception(Exception::Irq);
+        }
+    }
+}
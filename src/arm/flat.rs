@@ -0,0 +1,271 @@
+use super::*;
+
+use bytemuck::allocation::zeroed_slice_box;
+
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+// Limitations:
+// - Needs a 64-bit host, and an allocator that lazily commits large zeroed allocations (e.g. calloc → mmap on Linux) - otherwise 4 GiB is committed up front
+// - fork / snapshot / restore copy page data eagerly instead of sharing it copy-on-write
+// - MMIO pages are still looked up (and locked) in Memory's page tables
+// - Accesses that straddle 8-byte words aren't single-copy atomic (neither are they on real hardware)
+
+
+
+/// [`Memory::new_flat`]'s backing: the whole address space in one host allocation, with a state byte per page
+pub(super) struct Flat {
+    words:      Box<[AtomicU64]>,   // byte `addr` of guest memory is byte `addr % 8` of little endian `words[addr / 8]`
+    pages:      Box<[AtomicU8]>,    // per page: MemoryFlags bits | MAPPED | MMIO
+    page_shift: u32,                // log2 of the page size, as in Memory
+}
+
+const MAPPED        : u8 = 0x40; // distinguishes pages mapped without any flags from unmapped ones
+const MMIO          : u8 = 0x80; // accesses go to the Page's Mmio in Memory's page tables
+
+impl Memory {
+    /// Memory backed by one contiguous host allocation spanning the whole 4 GiB address space, plus a table of page flags.
+    /// Translating an address is just an index, with no page tables to walk or page locks to take - trading host address space for speed.
+    ///
+    /// Host pages are only committed as the guest touches them (given a 64-bit host that lazily commits large zeroed allocations.)
+    /// Everything else works the same as [`Memory::new`]'s memory.  See also [`MemoryConfig::flat`].
+    pub fn new_flat() -> Self { Self::with_config(MemoryConfig { flat: true, ..MemoryConfig::default() }) }
+
+    pub(super) fn flat_read(&self, flat: &Flat, addr: u32, flags: MemoryFlags, mut bytes: &mut [u8]) -> Result<(), MemFault> {
+        let mut addr = addr;
+        while !bytes.is_empty() {
+            let read = flat.page_remaining(addr).min(bytes.len());
+            if flat.check(addr, flags)? & MMIO != 0 {
+                self.flat_mmio(addr).read(addr, &mut bytes[..read]);
+            } else {
+                flat.load(addr, &mut bytes[..read]);
+            }
+            bytes = &mut bytes[read..];
+            addr = addr.wrapping_add(read as u32); // wrap around the end of the address space
+        }
+        Ok(())
+    }
+
+    pub(super) fn flat_write(&self, flat: &Flat, addr: u32, flags: MemoryFlags, mut bytes: &[u8]) -> Result<(), MemFault> {
+        let mut addr = addr;
+        while !bytes.is_empty() {
+            let write = flat.page_remaining(addr).min(bytes.len());
+            if flat.check(addr, flags)? & MMIO != 0 {
+                self.flat_mmio(addr).write(addr, &bytes[..write]);
+            } else {
+                flat.store(addr, &bytes[..write]);
+                self.flat_wrote(flat, addr, write);
+            }
+            bytes = &bytes[write..];
+            addr = addr.wrapping_add(write as u32);
+        }
+        Ok(())
+    }
+
+    /// `bytes` must be aligned to its size, of at most 8 bytes
+    pub(super) fn flat_swap(&self, flat: &Flat, addr: u32, flags: MemoryFlags, bytes: &mut [u8]) -> Result<(), MemFault> {
+        if flat.check(addr, flags)? & MMIO != 0 {
+            let mmio = self.flat_mmio(addr);
+            let new = bytes.to_vec();
+            mmio.read(addr, bytes);
+            mmio.write(addr, &new);
+            return Ok(());
+        }
+        let shift = (addr & 7) as usize;
+        let old = flat.words[(addr >> 3) as usize].fetch_update(Ordering::AcqRel, Ordering::Acquire, |word| {
+            let mut word = word.to_le_bytes();
+            word[shift..][..bytes.len()].copy_from_slice(bytes);
+            Some(u64::from_le_bytes(word))
+        }).unwrap();
+        bytes.copy_from_slice(&old.to_le_bytes()[shift..][..bytes.len()]);
+        self.flat_wrote(flat, addr, bytes.len());
+        Ok(())
+    }
+
+    /// `current` & `new` must be aligned to their size, of at most 8 bytes.  On failure, `current` is overwritten with the value actually in memory.
+    pub(super) fn flat_compare_exchange(&self, flat: &Flat, addr: u32, flags: MemoryFlags, current: &mut [u8], new: &[u8]) -> Result<bool, MemFault> {
+        if flat.check(addr, flags)? & MMIO != 0 {
+            let mmio = self.flat_mmio(addr);
+            let mut actual = current.to_vec();
+            mmio.read(addr, &mut actual);
+            if actual != current { current.copy_from_slice(&actual); return Ok(false) }
+            mmio.write(addr, new);
+            return Ok(true);
+        }
+        let shift = (addr & 7) as usize;
+        let result = flat.words[(addr >> 3) as usize].fetch_update(Ordering::AcqRel, Ordering::Acquire, |word| {
+            let mut word = word.to_le_bytes();
+            if word[shift..][..new.len()] != *current { return None }
+            word[shift..][..new.len()].copy_from_slice(new);
+            Some(u64::from_le_bytes(word))
+        });
+        match result {
+            Ok(_)       => { self.flat_wrote(flat, addr, new.len()); Ok(true) },
+            Err(actual) => { current.copy_from_slice(&actual.to_le_bytes()[shift..][..new.len()]); Ok(false) },
+        }
+    }
+
+    /// `previous` must be aligned to its size, of at most 8 bytes.  `update` is retried if another thread changed the word first.
+    pub(super) fn flat_update(&self, flat: &Flat, addr: u32, flags: MemoryFlags, previous: &mut [u8], update: &mut dyn FnMut(&mut [u8]) -> bool) -> Result<bool, MemFault> {
+        if flat.check(addr, flags)? & MMIO != 0 {
+            let mmio = self.flat_mmio(addr);
+            mmio.read(addr, previous);
+            let mut new = previous.to_vec();
+            let updated = update(&mut new);
+            if updated { mmio.write(addr, &new) }
+            return Ok(updated);
+        }
+        let shift = (addr & 7) as usize;
+        let result = flat.words[(addr >> 3) as usize].fetch_update(Ordering::AcqRel, Ordering::Acquire, |word| {
+            let mut word = word.to_le_bytes();
+            if !update(&mut word[shift..][..previous.len()]) { return None }
+            Some(u64::from_le_bytes(word))
+        });
+        let (Ok(old) | Err(old)) = result;
+        previous.copy_from_slice(&old.to_le_bytes()[shift..][..previous.len()]);
+        if result.is_ok() { self.flat_wrote(flat, addr, previous.len()) }
+        Ok(result.is_ok())
+    }
+
+    /// Zero page `page_idx` and set its state
+    fn flat_reset_page(&self, flat: &Flat, page_idx: usize, state: u8) {
+        if flat.pages[page_idx].load(Ordering::Acquire) & MMIO != 0 { *self.page_populated(page_idx).lock().unwrap() = Page::new(); }
+        for word in flat.page_words(page_idx) {
+            if word.load(Ordering::Relaxed) != 0 { word.store(0, Ordering::Relaxed) } // don't commit host memory that's already zero
+        }
+        flat.pages[page_idx].store(state, Ordering::Release);
+    }
+
+    pub(super) fn flat_map(&self, flat: &Flat, page_idx: usize, flags: MemoryFlags) { self.flat_reset_page(flat, page_idx, flags.bits() as u8 | MAPPED) }
+    pub(super) fn flat_unmap(&self, flat: &Flat, page_idx: usize) { self.flat_reset_page(flat, page_idx, 0) }
+
+    pub(super) fn flat_map_mmio(&self, flat: &Flat, page_idx: usize, flags: MemoryFlags) {
+        flat.pages[page_idx].store(flags.bits() as u8 | MAPPED | MMIO, Ordering::Release);
+    }
+
+    pub(super) fn flat_protect(&self, flat: &Flat, page_idx: usize, flags: MemoryFlags) {
+        let state = &flat.pages[page_idx];
+        state.store((state.load(Ordering::Acquire) & (MAPPED | MMIO)) | flags.bits() as u8, Ordering::Release);
+    }
+
+    pub(super) fn flat_init_pages(&self, flat: &Flat, base: u32, flags: MemoryFlags, mut bytes: u32, mut on_page: impl FnMut(&mut Page, Range<u32>) -> io::Result<()>) -> io::Result<()> {
+        let mut addr = u64::from(base);
+        while bytes > 0 {
+            if addr >> 32 != 0 { return Err(io::Error::new(io::ErrorKind::OutOfMemory, "arm::Memory: tried to initialize beyond address space")) }
+            let offset = self.page_offset(addr as u32) as u32;
+            let size = (self.page_size() - offset).min(bytes);
+            let page_idx = self.page_idx(addr as u32);
+
+            let mut page = Page::new(); // on_page only fills in data if written - otherwise the range is zeroed
+            on_page(&mut page, offset .. offset + size)?;
+            flat.store(addr as u32, &page.bytes()[offset as usize ..][.. size as usize]);
+            flat.pages[page_idx].fetch_or(flags.bits() as u8 | MAPPED, Ordering::AcqRel);
+            self.flat_wrote(flat, addr as u32, size as usize);
+
+            addr += u64::from(size);
+            bytes -= size;
+        }
+        self.invalidate_tlbs();
+        Ok(())
+    }
+
+    /// Page `page_idx` as a standalone [`Page`] (e.g. for [`Memory::snapshot`]), or [`None`] if unmapped
+    pub(super) fn flat_page(&self, flat: &Flat, page_idx: usize) -> Option<Page> {
+        let state = flat.pages[page_idx].load(Ordering::Acquire);
+        if state & MAPPED == 0 { return None }
+        if state & MMIO != 0 { return Some(self.page(page_idx)?.lock().unwrap().clone()) }
+
+        let mut words = [0u64; 512];
+        for (dst, src) in words.iter_mut().zip(flat.page_words(page_idx)) { *dst = u64::from_le(src.load(Ordering::Acquire)); }
+        let data = if words.iter().any(|w| *w != 0) { Some(Arc::new(words)) } else { None };
+        Some(Page { data, flags: MemoryFlags::from_bits_truncate(u32::from(state & 0x7)), mmio: None, mapped: true })
+    }
+
+    /// Replace page `page_idx` with `page` (e.g. for [`Memory::restore`])
+    pub(super) fn flat_set_page(&self, flat: &Flat, page_idx: usize, page: &Page) {
+        self.flat_unmap(flat, page_idx);
+        if page.is_unmapped() { return }
+        if page.mmio.is_some() {
+            *self.page_populated(page_idx).lock().unwrap() = page.clone();
+            return self.flat_map_mmio(flat, page_idx, page.flags);
+        }
+        if page.data.is_some() { flat.store((page_idx << flat.page_shift) as u32, &page.bytes()[.. self.page_size() as usize]); }
+        flat.pages[page_idx].store(page.flags.bits() as u8 | MAPPED, Ordering::Release);
+    }
+
+    pub(super) fn flat_mapped(&self, flat: &Flat, page_idx: usize) -> bool { flat.pages[page_idx].load(Ordering::Acquire) & MAPPED != 0 }
+
+    pub(super) fn flat_page_inspectable(&self, flat: &Flat, page_idx: usize) -> bool { flat.pages[page_idx].load(Ordering::Acquire) & (MAPPED | MMIO) == MAPPED }
+
+    /// The device of a flat page marked MMIO
+    fn flat_mmio(&self, addr: u32) -> Mmio {
+        self.page(self.page_idx(addr)).and_then(|page| page.lock().unwrap().mmio.clone()).expect("arm::Memory: flat page marked MMIO without a device")
+    }
+
+    fn flat_wrote(&self, flat: &Flat, addr: u32, len: usize) {
+        let page_idx = self.page_idx(addr);
+        let state = flat.pages[page_idx].load(Ordering::Acquire);
+        if state & MAPPED == 0 { flat.pages[page_idx].fetch_or(MAPPED, Ordering::AcqRel); } // the host wrote to an unmapped page, which has data now
+        self.mark_dirty(page_idx as u32);
+        self.define(addr, len);
+        if state & MemoryFlags::WRITE.bits() as u8 == 0 { self.invalidate_tlbs() } // the host wrote to a page Tlbs might be caching
+    }
+}
+
+impl Flat {
+    pub(super) fn new(page_shift: u32) -> Self {
+        Self { words: zeroed_slice_box(1 << 29), pages: zeroed_slice_box(1 << (32 - page_shift)), page_shift }
+    }
+
+    /// Bytes from `addr` to the end of its page
+    fn page_remaining(&self, addr: u32) -> usize { (1 << self.page_shift) - (addr & ((1 << self.page_shift) - 1)) as usize }
+
+    /// The words backing page `page_idx`
+    fn page_words(&self, page_idx: usize) -> &[AtomicU64] {
+        let page_words = 1 << (self.page_shift - 3);
+        &self.words[page_idx * page_words ..][.. page_words]
+    }
+
+    /// The page's state, if it's mapped for `access`
+    fn check(&self, addr: u32, access: MemoryFlags) -> Result<u8, MemFault> {
+        let state = self.pages[(addr >> self.page_shift) as usize].load(Ordering::Acquire);
+        let flags = MemoryFlags::from_bits_truncate(u32::from(state & 0x7));
+        if flags.contains(access) { Ok(state) } else { Err(MemFault { addr, access, flags, kind: MemFaultKind::Permission }) }
+    }
+
+    /// Read `bytes` from a single page
+    fn load(&self, mut addr: u32, mut bytes: &mut [u8]) {
+        while !bytes.is_empty() {
+            let shift = (addr & 7) as usize;
+            let n = (8 - shift).min(bytes.len());
+            let word = self.words[(addr >> 3) as usize].load(Ordering::Acquire).to_le_bytes();
+            bytes[..n].copy_from_slice(&word[shift..][..n]);
+            bytes = &mut bytes[n..];
+            addr = addr.wrapping_add(n as u32);
+        }
+    }
+
+    /// Write `bytes` to a single page.  Zeros written over zeros are skipped, so the host never commits memory for them.
+    fn store(&self, mut addr: u32, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let shift = (addr & 7) as usize;
+            let n = (8 - shift).min(bytes.len());
+            let word = &self.words[(addr >> 3) as usize];
+            if bytes[..n].iter().all(|b| *b == 0) && word.load(Ordering::Acquire) == 0 {
+                // already zero
+            } else if n == 8 {
+                word.store(u64::from_le_bytes(bytes[..8].try_into().unwrap()), Ordering::Release);
+            } else {
+                let _ = word.fetch_update(Ordering::AcqRel, Ordering::Acquire, |word| {
+                    let mut word = word.to_le_bytes();
+                    word[shift..][..n].copy_from_slice(&bytes[..n]);
+                    Some(u64::from_le_bytes(word))
+                });
+            }
+            bytes = &bytes[n..];
+            addr = addr.wrapping_add(n as u32);
+        }
+    }
+}
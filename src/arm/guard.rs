@@ -0,0 +1,40 @@
+use super::*;
+
+use std::ops::Range;
+
+// Limitations:
+// - Only accesses by the guest (through Cpu::step1) are reported as stack overflows - the host's own accesses fail with a plain MemFault
+// - Guards don't grow the stack on demand: hosts wanting that can map more stack & move the guard down on StepOutcome::StackOverflow
+
+
+
+impl Memory {
+    /// Unmap `range` (every page it overlaps) and mark it as a guard region: [`Cpu::step1`] reports faulting accesses to it as [`StepOutcome::StackOverflow`]
+    /// instead of [`StepOutcome::DataAbort`].  Mapping over a guard region leaves it marked, but accesses to mapped pages don't fault.
+    pub fn guard(&self, range: Range<u32>) {
+        if range.is_empty() { return }
+        self.unmap(range.clone());
+        let mut guards = self.guards.lock().unwrap();
+        super::labels::remove_ranges(&mut guards, range.clone());
+        guards.insert(range.start, (range.end, ()));
+    }
+
+    /// Stop treating `range` as a guard region.  Doesn't map anything.
+    pub fn unguard(&self, range: Range<u32>) { super::labels::remove_ranges(&mut self.guards.lock().unwrap(), range) }
+
+    /// `true` if `addr` is in a [`Memory::guard`]ed region
+    pub fn is_guard(&self, addr: u32) -> bool {
+        let guards = self.guards.lock().unwrap();
+        guards.range(..= addr).next_back().is_some_and(|(_, (end, ()))| addr < *end)
+    }
+
+    /// Map `stack` for reading & writing, with a `guard_size` byte [`Memory::guard`] region immediately below it (as stacks grow down.)
+    /// Both are [`Memory::label`]ed (`"stack"` & `"stack guard"`) for fault messages.
+    pub fn map_stack(&self, stack: Range<u32>, guard_size: u32) {
+        let guard = stack.start.saturating_sub(guard_size) .. stack.start;
+        self.guard(guard.clone());
+        self.map(stack.clone(), MemoryFlags::READ | MemoryFlags::WRITE);
+        self.label(guard, "stack guard");
+        self.label(stack, "stack");
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::sync::Arc;
+
+// Limitations:
+// - Labels are purely diagnostic: they don't map anything, and survive unmapping (handy for naming guard regions)
+// - snapshot / restore leave labels alone
+
+
+
+impl Memory {
+    /// Name `range` (e.g. `".text"`, `"stack"`, `"mmio:uart"`) in [`Memory::regions`] and [`MemFault::labeled`] messages.
+    /// Replaces any labels previously overlapping `range`.
+    pub fn label(&self, range: Range<u32>, label: impl Into<Arc<str>>) {
+        if range.is_empty() { return }
+        self.unlabel(range.clone());
+        self.labels.lock().unwrap().insert(range.start, (range.end, label.into()));
+    }
+
+    /// Remove labels from `range`, trimming any labels that only partially overlap it
+    pub fn unlabel(&self, range: Range<u32>) { remove_ranges(&mut self.labels.lock().unwrap(), range) }
+
+    /// The label of the range containing `addr`, if any
+    pub fn label_at(&self, addr: u32) -> Option<Arc<str>> { self.labeled_range(addr).map(|(_, label)| label) }
+
+    /// Every labeled range, in ascending order
+    pub fn labels(&self) -> Vec<(Range<u32>, Arc<str>)> {
+        self.labels.lock().unwrap().iter().map(|(start, (end, label))| (*start .. *end, label.clone())).collect()
+    }
+
+    fn labeled_range(&self, addr: u32) -> Option<(Range<u32>, Arc<str>)> {
+        let labels = self.labels.lock().unwrap();
+        let (start, (end, label)) = labels.range(..= addr).next_back()?;
+        if addr < *end { Some((*start .. *end, label.clone())) } else { None }
+    }
+
+    /// Describe where `addr` is relative to the labels: `in "stack" (...)`, or how far it is from the nearest label
+    fn write_location(&self, f: &mut Formatter, addr: u32) -> fmt::Result {
+        if let Some((range, label)) = self.labeled_range(addr) { return write!(f, " in {:?} (0x{:08x} .. 0x{:08x})", label, range.start, range.end) }
+
+        let labels = self.labels.lock().unwrap();
+        let below   = labels.range(..= addr).next_back().map(|(start, (end, label))| (addr - (end - 1), "above", *start .. *end, label));
+        let above   = labels.range(addr ..).next().map(|(start, (end, label))| (start - addr, "below", *start .. *end, label));
+        let nearest = match (below, above) {
+            (Some(b), Some(a))  => Some(if a.0 < b.0 { a } else { b }),
+            (b, a)              => b.or(a),
+        };
+        match nearest {
+            Some((distance, dir, range, label)) => write!(f, ", 0x{:x} bytes {} {:?} (0x{:08x} .. 0x{:08x})", distance, dir, label, range.start, range.end),
+            None                                => Ok(()),
+        }
+    }
+}
+
+/// Remove `range` from `ranges` (start → (end, value)), trimming any ranges that only partially overlap it
+pub(super) fn remove_ranges<T: Clone>(ranges: &mut BTreeMap<u32, (u32, T)>, range: Range<u32>) {
+    if range.is_empty() { return }
+    let overlapping : Vec<_> = ranges.range(..range.end).filter(|(_, (end, _))| *end > range.start).map(|(start, (end, value))| (*start, *end, value.clone())).collect();
+    for (start, end, value) in overlapping {
+        ranges.remove(&start);
+        if start < range.start  { ranges.insert(start, (range.start, value.clone())); }
+        if end > range.end      { ranges.insert(range.end, (end, value)); }
+    }
+}
+
+impl MemFault {
+    /// Display this fault along with where it is relative to `mem`'s [`Memory::label`]s, e.g.
+    /// `0x0001f400 not mapped for READ (its page is mapped for (empty)), 0xc00 bytes below "stack" (0x00020000 .. 0x00021000)`
+    pub fn labeled<'m>(&self, mem: &'m Memory) -> impl Display + 'm { Labeled { mem, inner: *self } }
+}
+
+impl Fault {
+    /// Display this fault along with where its address (or the PC, for undefined instructions) is relative to `mem`'s [`Memory::label`]s
+    pub fn labeled<'m>(&self, mem: &'m Memory) -> impl Display + 'm { Labeled { mem, inner: *self } }
+}
+
+struct Labeled<'m, T> {
+    mem:    &'m Memory,
+    inner:  T,
+}
+
+impl Display for Labeled<'_, MemFault> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.inner)?;
+        self.mem.write_location(f, self.inner.addr)
+    }
+}
+
+impl Display for Labeled<'_, Fault> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.inner)?;
+        match self.inner {
+            Fault::Undefined { pc, .. }         => self.mem.write_location(f, pc),
+            Fault::PrefetchAbort { fault, .. }  => self.mem.write_location(f, fault.addr),
+            Fault::DataAbort { fault, .. }      => self.mem.write_location(f, fault.addr),
+            Fault::StackOverflow { fault, .. }  => self.mem.write_location(f, fault.addr),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+#![allow(unused_parens)]
+
+use super::*;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (A5.4 Media instructions)
+
+
+
+impl Cpu {
+    /// ARMv6+ media instructions: the `cond 011x xxxx xxxx xxxx xxxx xxx1 xxxx` space 4.17 had left undefined
+    pub(super) fn impl_media(&mut self, op: u32) {
+        if !self.require(Arch::V6, op) { return }
+        if op & 0x0FBF_0F70 == 0x06BF_0F30 {
+            self.impl_reverse(op);
+        } else if op & 0x0F80_0F10 == 0x0600_0F10 {
+            self.impl_parallel_add_subtract(op);
+        } else if op & 0x0FF0_0FF0 == 0x0680_0FB0 {
+            self.impl_select(op);
+        } else if op & 0x0FA0_0030 == 0x06A0_0010 || op & 0x0FB0_0FF0 == 0x06A0_0F30 {
+            self.impl_saturate(op);
+        } else if op & 0x0FA0_0070 == 0x07A0_0050 || op & 0x0FE0_0070 == 0x07C0_0010 {
+            self.impl_bitfield(op);
+        } else if op & 0x0F80_03F0 == 0x0680_0070 && (op >> 20) & 0b011 != 0b001 {
+            self.impl_extend(op);
+        } else {
+            self.undefined(op);
+        }
+    }
+
+    /// {S,Q,SH,U,UQ,UH}{ADD16,ASX,SAX,SUB16,ADD8,SUB8}
+    fn impl_parallel_add_subtract(&mut self, op: u32) {
+        let prefix      = ((op >> 20) & 0b111);
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let opcode      = ((op >>  5) & 0b111);
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        if prefix & 0b11 == 0b00 { return self.undefined(op) } // op1 = 000 & 100 have no prefix
+        let signed      = prefix & 0b100 == 0; // S, Q, SH vs U, UQ, UH
+        let (n, m)      = (self.registers[rn], self.registers[rm]);
+
+        // (lane bits, [(lane n, lane m, add?)]) from least to most significant lane
+        let (bits, lanes): (u32, &[(u32, u32, bool)]) = match opcode {
+            0b000 => (16, &[(n, m, true), (n >> 16, m >> 16, true)]),           // ADD16
+            0b001 => (16, &[(n, m >> 16, false), (n >> 16, m, true)]),          // ASX
+            0b010 => (16, &[(n, m >> 16, true), (n >> 16, m, false)]),          // SAX
+            0b011 => (16, &[(n, m, false), (n >> 16, m >> 16, false)]),         // SUB16
+            0b100 => ( 8, &[(n, m, true), (n >> 8, m >> 8, true), (n >> 16, m >> 16, true), (n >> 24, m >> 24, true)]),         // ADD8
+            0b111 => ( 8, &[(n, m, false), (n >> 8, m >> 8, false), (n >> 16, m >> 16, false), (n >> 24, m >> 24, false)]),     // SUB8
+            _     => return self.undefined(op),
+        };
+        let lane_mask   = (1u32 << bits) - 1;
+        let extend      = |v: u32| -> i64 { let v = v & lane_mask; if signed { i64::from(((v << (32 - bits)) as i32) >> (32 - bits)) } else { i64::from(v) } };
+
+        let mut result  = 0;
+        let mut ge      = 0;
+        for (i, &(a, b, add)) in lanes.iter().enumerate() {
+            let sum     = if add { extend(a) + extend(b) } else { extend(a) - extend(b) };
+            let lane    = match prefix & 0b11 {
+                0b01 => { // S, U: modular with GE flags
+                    let ge_set = if signed || !add { sum >= 0 } else { sum >= (1 << bits) };
+                    if ge_set { ge |= (if bits == 16 { 0b11 } else { 0b1 }) << (i as u32 * bits / 8); }
+                    sum as u32
+                },
+                0b10 if signed  => Self::signed_saturate(sum, bits).0,   // Q
+                0b10            => Self::unsigned_saturate(sum, bits).0, // UQ
+                _               => (sum >> 1) as u32,                   // SH, UH (0b11): the 1 bit wider sum can't overflow
+            };
+            result |= (lane & lane_mask) << (i as u32 * bits);
+        }
+
+        self.registers[rd] = result;
+        if prefix & 0b11 == 0b01 { self.cpsr.set_ge(ge); }
+    }
+
+    /// SEL: pick each byte from Rn or Rm by the matching GE flag
+    fn impl_select(&mut self, op: u32) {
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let ge          = self.cpsr.ge();
+        let mask        = (0 .. 4).filter(|i| ge & (1 << i) != 0).fold(0u32, |mask, i| mask | (0xFF << (8 * i)));
+        self.registers[rd] = (self.registers[rn] & mask) | (self.registers[rm] & !mask);
+    }
+
+    /// SSAT, USAT, SSAT16, USAT16
+    fn impl_saturate(&mut self, op: u32) {
+        let unsigned    = ((op >> 22) & 0b1) == 1;
+        let sat_imm     = ((op >> 16) & 0b11111); // 4 bits for SSAT16/USAT16
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let imm5        = ((op >>  7) & 0b11111);
+        let asr         = ((op >>  6) & 0b1) == 1;
+        let halves      = ((op >>  5) & 0b1) == 1;
+        let rn          = ((op >>  0) & 0b1111) as usize;
+
+        let saturate    = |value: i64, bits: u32| if unsigned { Self::unsigned_saturate(value, bits) } else { Self::signed_saturate(value, bits + 1) };
+        let value       = self.registers[rn];
+        let (result, saturated) = match halves {
+            false => {
+                let shifted = match asr {
+                    false => value << imm5,
+                    true  => ((value as i32) >> if imm5 == 0 { 31 } else { imm5 }) as u32, // ASR #0 encodes #32, which matches #31 for the sign
+                };
+                saturate(i64::from(shifted as i32), sat_imm)
+            },
+            true => {
+                let sat_imm = sat_imm & 0b1111;
+                let (lo, lo_sat) = saturate(i64::from(value as u16 as i16), sat_imm);
+                let (hi, hi_sat) = saturate(i64::from((value >> 16) as u16 as i16), sat_imm);
+                ((lo & 0xFFFF) | (hi << 16), lo_sat || hi_sat)
+            },
+        };
+
+        self.registers[rd] = result;
+        if saturated { self.cpsr.insert(Psr::Q); }
+    }
+
+    /// ARMv6T2: BFC, BFI, SBFX, UBFX
+    fn impl_bitfield(&mut self, op: u32) {
+        if !self.require(Arch::V7A, op) { return }
+        let opcode      = ((op >> 21) & 0b11);
+        let msb         = ((op >> 16) & 0b11111); // widthminus1 for SBFX/UBFX
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let lsb         = ((op >>  7) & 0b11111);
+        let rn          = ((op >>  0) & 0b1111) as usize;
+
+        let mask        = |width: u32| ((1u64 << width) - 1) as u32;
+        match opcode {
+            0b10 => { // BFC, BFI
+                if msb < lsb { return } // UNPREDICTABLE, leave Rd alone
+                let field   = mask(msb - lsb + 1) << lsb;
+                let insert  = if rn == 15 { 0 } else { self.registers[rn] << lsb }; // Rn == PC encodes BFC
+                self.registers[rd] = (self.registers[rd] & !field) | (insert & field);
+            },
+            _ => { // SBFX (0b01), UBFX (0b11)
+                let width   = (msb + 1).min(32 - lsb); // lsb + width > 32 is UNPREDICTABLE, clamp
+                let value   = (self.registers[rn] >> lsb) & mask(width);
+                let shift   = 32 - width;
+                self.registers[rd] = match opcode {
+                    0b01 => (((value << shift) as i32) >> shift) as u32,
+                    _    => value,
+                };
+            },
+        }
+    }
+
+    /// SXTB, SXTH, SXTB16, UXTB, UXTH, UXTB16, and their accumulating SXTA* / UXTA* forms
+    fn impl_extend(&mut self, op: u32) {
+        let unsigned    = ((op >> 22) & 0b1) == 1;
+        let size        = ((op >> 20) & 0b11); // 0b00 = B16, 0b10 = B, 0b11 = H
+        let rn          = ((op >> 16) & 0b1111) as usize; // PC means no accumulate
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let rotate      = ((op >> 10) & 0b11) * 8;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let value       = self.registers[rm].rotate_right(rotate);
+        let acc         = if rn == 15 { 0 } else { self.registers[rn] };
+        self.registers[rd] = match (size, unsigned) {
+            (0b00, _) => { // xXTAB16: extend & add bytes 0 and 2 into separate halfwords
+                let extend = |b: u32| if unsigned { b & 0xFF } else { b as u8 as i8 as u32 };
+                let lo = (acc as u16).wrapping_add(extend(value) as u16);
+                let hi = ((acc >> 16) as u16).wrapping_add(extend(value >> 16) as u16);
+                u32::from(lo) | u32::from(hi) << 16
+            },
+            (0b10, false) => acc.wrapping_add(value as u8  as i8  as u32),
+            (0b10, true ) => acc.wrapping_add(value as u8  as u32),
+            (_,    false) => acc.wrapping_add(value as u16 as i16 as u32),
+            (_,    true ) => acc.wrapping_add(value as u16 as u32),
+        };
+    }
+
+    /// REV, REV16, REVSH, and ARMv6T2's RBIT
+    fn impl_reverse(&mut self, op: u32) {
+        let rd          = ((op >> 12) & 0b1111) as usize;
+        let rm          = ((op >>  0) & 0b1111) as usize;
+        let value       = self.registers[rm];
+
+        self.registers[rd] = match ((op >> 22) & 0b1, (op >> 7) & 0b1) {
+            (0, 0) => value.swap_bytes(),                                                                   // REV
+            (0, _) => ((value as u16).swap_bytes() as u32) | (((value >> 16) as u16).swap_bytes() as u32) << 16, // REV16
+            (_, 0) if !self.require(Arch::V7A, op) => return,
+            (_, 0) => value.reverse_bits(),                                                                 // RBIT (ARMv6T2)
+            (_, _) => (value as u16).swap_bytes() as i16 as u32,                                            // REVSH
+        };
+    }
+}
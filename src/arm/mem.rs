@@ -1,17 +1,25 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
 use std::ops::{DerefMut, Range};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use bytemuck::{bytes_of, bytes_of_mut};
 
 use read_write_at::ReadAtMut;
 
+use super::Mmio;
+use super::flat::Flat;
+use super::tlb::next_tlb_generation;
+use super::uninit::Shadow;
+
 
 
 
 bitflags::bitflags! {
     #[derive(Default)]
     #[repr(transparent)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct MemoryFlags : u32 {
         const NONE      = 0;
         const READ      = 0x00000001;
@@ -20,22 +28,80 @@ bitflags::bitflags! {
     }
 }
 
+/// Byte order for the `*_endian` accessors.  The plain accessors are little endian.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Endian {
+    #[default] Little,
+    Big,
+}
+
+/// A memory access that needed `access`, at `addr` on a page only mapped for `flags` (or that was misaligned, per `kind`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemFault {
+    pub addr:   u32,
+    pub access: MemoryFlags,
+    pub flags:  MemoryFlags,
+    pub kind:   MemFaultKind,
+}
+
+/// Why a [`MemFault`] happened
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemFaultKind {
+    /// The page wasn't mapped for the access
+    #[default] Permission,
+    /// The access wasn't aligned to its size, and the CPU was configured to fault (see [`Unaligned::Fault`](super::Unaligned::Fault).)  `flags` isn't meaningful.
+    Alignment,
+}
+
+/// How [`Memory::with_config`] lays out memory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryConfig {
+    /// The granularity of mappings, flags & dirty tracking: a power of two from 1 KiB to 4 KiB (e.g. 4 KiB to match ELF `p_align` & linux's `mmap`)
+    pub page_size:  u32,
+    /// One flat host allocation instead of page tables (see [`Memory::new_flat`])
+    pub flat:       bool,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self { Self { page_size: 0x400, flat: false } }
+}
+
 pub struct Memory {
-    pub pages: Vec<Mutex<Page>>, // 1<<22 entries is 4M * size_of::<Page>(), too big to fit on stack
+    page_shift:     u32, // log2(MemoryConfig::page_size)
+    directory:      Box<[OnceLock<Table>]>, // lazily populated tables of TABLE_PAGES pages each - see Memory::page
+    track_dirty:    AtomicBool,
+    dirty:          Mutex<HashSet<u32>>, // page indices modified since the last clear_dirty, if track_dirty
+    pub(super) tlb_generation: AtomicU64, // see tlb.rs
+    pub(super) flat: Option<Flat>, // if Memory::new_flat: pages live here instead, except for MMIO (see flat.rs)
+    pub(super) labels: Mutex<BTreeMap<u32, (u32, Arc<str>)>>, // start → (end, label), non-overlapping (see labels.rs)
+    pub(super) guards: Mutex<BTreeMap<u32, (u32, ())>>, // start → end, non-overlapping (see guard.rs)
+    pub(super) track_uninit: AtomicBool,
+    pub(super) undefined: Mutex<HashMap<usize, Shadow>>, // page index → never written bytes, if track_uninit (see uninit.rs)
+}
+
+type Table = Box<[Mutex<Page>]>;
+
+/// The mapped pages of a [`Memory`], to [`Memory::restore`] later.  Page data is shared copy-on-write, like [`Memory::fork`].
+#[derive(Clone)]
+pub struct MemorySnapshot {
+    page_shift: u32,
+    pages:      Vec<(usize, Page)>, // sorted by page index, skipping unmapped pages
 }
 
+#[derive(Clone)]
 pub struct Page {
-    pub data:   Option<Box<[u64; 4096/8]>>,
+    pub data:   Option<Arc<[u64; 4096/8]>>, // shared with forks until written (see Memory::fork)
     pub flags:  MemoryFlags,
+    pub(super) mmio: Option<Mmio>, // accesses go to a device instead of data (see mmio.rs)
+    pub(super) mapped: bool, // distinguishes pages mapped without any flags (e.g. PROT_NONE reservations) from unmapped ones
 }
 
 impl Default for Memory {
-    fn default() -> Self {
-        let mut pages = Vec::new();
-        pages.reserve_exact(1 << 22);
-        for _ in 0 .. 1 << 22 { pages.push(Mutex::new(Page::new())); }
-        Self { pages }
-    }
+    fn default() -> Self { Self::with_config(MemoryConfig::default()) }
 }
 
 impl Default for Page {
@@ -43,6 +109,8 @@ impl Default for Page {
         Self {
             data:   None,
             flags:  MemoryFlags::NONE,
+            mmio:   None,
+            mapped: false,
         }
     }
 }
@@ -50,7 +118,20 @@ impl Default for Page {
 impl Memory {
     pub fn new() -> Self { Default::default() }
 
-    pub fn init_copy_io(&mut self, base: u32, flags: MemoryFlags, io: &mut impl ReadAtMut, mut offset: u64, io_bytes: u32) -> io::Result<()> {
+    pub fn with_config(config: MemoryConfig) -> Self {
+        let page_size = config.page_size;
+        assert!(page_size.is_power_of_two() && (0x400 ..= 0x1000).contains(&page_size), "arm::Memory::with_config: page_size must be a power of two from 1 KiB to 4 KiB, not {page_size:#x}");
+        let page_shift = page_size.trailing_zeros();
+        let directory = (0 .. (1 << (32 - page_shift)) / TABLE_PAGES).map(|_| OnceLock::new()).collect();
+        let flat = if config.flat { Some(Flat::new(page_shift)) } else { None };
+        Self { page_shift, directory, track_dirty: AtomicBool::new(false), dirty: Default::default(), tlb_generation: AtomicU64::new(next_tlb_generation()), flat, labels: Default::default(), guards: Default::default(), track_uninit: AtomicBool::new(false), undefined: Default::default() }
+    }
+
+    pub fn config(&self) -> MemoryConfig { MemoryConfig { page_size: self.page_size(), flat: self.flat.is_some() } }
+
+    pub fn page_size(&self) -> u32 { 1 << self.page_shift }
+
+    pub fn init_copy_io(&self, base: u32, flags: MemoryFlags, io: &mut impl ReadAtMut, mut offset: u64, io_bytes: u32) -> io::Result<()> {
         self.init_pages(base, flags, io_bytes, |page, range| {
             let data = page.alloc_bytes_mut();
             io.read_exact_at(&mut data[range.start as usize .. range.end as usize], offset)?;
@@ -59,57 +140,456 @@ impl Memory {
         })
     }
 
-    pub fn init_zero(&mut self, base: u32, flags: MemoryFlags, zero_bytes: u32) -> io::Result<()> {
-        self.init_pages(base, flags, zero_bytes, |_page, _bytes| {
-            //let _ = page.alloc_bytes_mut();
+    /// Zero memory (e.g. an ELF segment's BSS.)  Whole pages go back to sharing the zero page, without host memory of their own, until written.
+    pub fn init_zero(&self, base: u32, flags: MemoryFlags, zero_bytes: u32) -> io::Result<()> {
+        let page_size = self.page_size();
+        self.init_pages(base, flags, zero_bytes, |page, range| {
+            if range.len() as u32 == page_size {
+                page.data = None;
+            } else if page.data.is_some() {
+                page.alloc_bytes_mut()[range.start as usize .. range.end as usize].fill(0);
+            }
             Ok(())
         })
     }
 
-    pub fn read_u8(&self, addr: u32, flags: MemoryFlags) -> u8 { let mut result = 0u8; self.read_aligned(addr, flags, bytes_of_mut(&mut result)); result }
-    pub fn read_u16_aligned(&self, addr: u32, flags: MemoryFlags) -> u16 { let mut result = 0u16; self.read_aligned(addr, flags, bytes_of_mut(&mut result)); u16::from_le(result) }
-    pub fn read_u32_aligned(&self, addr: u32, flags: MemoryFlags) -> u32 { let mut result = 0u32; self.read_aligned(addr, flags, bytes_of_mut(&mut result)); u32::from_le(result) }
-    pub fn read_u64_aligned(&self, addr: u32, flags: MemoryFlags) -> u64 { let mut result = 0u64; self.read_aligned(addr, flags, bytes_of_mut(&mut result)); u64::from_le(result) }
-    pub fn read_u16_unaligned(&self, addr: u32, flags: MemoryFlags) -> u16 { let mut result = 0u16; self.read_unaligned(addr, flags, bytes_of_mut(&mut result)); u16::from_le(result) }
-    pub fn read_u32_unaligned(&self, addr: u32, flags: MemoryFlags) -> u32 { let mut result = 0u32; self.read_unaligned(addr, flags, bytes_of_mut(&mut result)); u32::from_le(result) }
-    pub fn read_u64_unaligned(&self, addr: u32, flags: MemoryFlags) -> u64 { let mut result = 0u64; self.read_unaligned(addr, flags, bytes_of_mut(&mut result)); u64::from_le(result) }
+    /// A copy-on-write clone of this memory: pages are shared until either side writes to them, at which point the writer gets its own copy.
+    /// Dirty & uninitialized memory tracking state, [`Memory::label`]s and [`Memory::guard`]s are cloned too.
+    ///
+    /// Pages are cloned one at a time, so fork while nothing else is writing to get a consistent snapshot.
+    /// [`Memory::new_flat`] memory copies every mapped page up front instead.
+    /// MMIO pages stay mapped to the same (shared) devices.
+    pub fn fork(&self) -> Self {
+        if self.flat.is_some() {
+            let fork = Self::with_config(self.config());
+            fork.restore(&self.snapshot());
+            fork.track_dirty.store(self.track_dirty.load(Ordering::Relaxed), Ordering::Relaxed);
+            *fork.dirty.lock().unwrap() = self.dirty_pages();
+            *fork.labels.lock().unwrap() = self.labels.lock().unwrap().clone();
+            *fork.guards.lock().unwrap() = self.guards.lock().unwrap().clone();
+            fork.track_uninit.store(self.uninit_tracking(), Ordering::Relaxed);
+            *fork.undefined.lock().unwrap() = self.undefined.lock().unwrap().clone();
+            return fork;
+        }
+        let directory = self.directory.iter().map(|table| match table.get() {
+            None        => OnceLock::new(),
+            Some(table) => OnceLock::from(table.iter().map(|page| Mutex::new(page.lock().unwrap().clone())).collect::<Table>()),
+        }).collect();
+        Self { page_shift: self.page_shift, directory, track_dirty: AtomicBool::new(self.track_dirty.load(Ordering::Relaxed)), dirty: Mutex::new(self.dirty_pages()), tlb_generation: AtomicU64::new(next_tlb_generation()), flat: None, labels: Mutex::new(self.labels.lock().unwrap().clone()), guards: Mutex::new(self.guards.lock().unwrap().clone()),
+            track_uninit: AtomicBool::new(self.uninit_tracking()), undefined: Mutex::new(self.undefined.lock().unwrap().clone()) }
+    }
+
+    /// Capture every mapped page's flags & data, to roll back to with [`Memory::restore`].  Doesn't capture dirty tracking state.
+    pub fn snapshot(&self) -> MemorySnapshot {
+        let mut pages = Vec::new();
+        self.for_each_mapped_page(|page_idx, page| pages.push((page_idx, page.clone())));
+        MemorySnapshot { page_shift: self.page_shift, pages }
+    }
+
+    /// Replace every page with its state in `snapshot`: pages mapped since are unmapped, and pages written since are reverted.
+    /// Every page mapped before or after is marked dirty.  Panics if `snapshot` was of memory with a different page size.
+    pub fn restore(&self, snapshot: &MemorySnapshot) {
+        assert_eq!(snapshot.page_shift, self.page_shift, "arm::Memory::restore: snapshot has a different page size");
+        let mut saved = snapshot.pages.iter().peekable();
+        if let Some(flat) = &self.flat {
+            for page_idx in 0 .. self.pages() {
+                let restored = saved.next_if(|(saved_idx, _)| *saved_idx == page_idx).map(|(_, page)| page);
+                if restored.is_none() && !self.flat_mapped(flat, page_idx) { continue }
+                self.flat_set_page(flat, page_idx, restored.unwrap_or(&UNMAPPED_PAGE));
+                self.mark_dirty(page_idx as u32);
+            }
+            return self.invalidate_tlbs();
+        }
+        for table_idx in 0 .. self.directory.len() {
+            let table_pages = table_idx * TABLE_PAGES .. (table_idx + 1) * TABLE_PAGES;
+            let saved_in_table = saved.peek().is_some_and(|(page_idx, _)| table_pages.contains(page_idx));
+            if !saved_in_table && self.directory[table_idx].get().is_none() { continue } // unmapped then & now
+
+            for page_idx in table_pages {
+                let restored = match saved.next_if(|(saved_idx, _)| *saved_idx == page_idx) {
+                    Some((_, page))     => page.clone(),
+                    None                => Page::new(),
+                };
+                let mut page = self.page_populated(page_idx).lock().unwrap();
+                if page.is_unmapped() && restored.is_unmapped() { continue }
+                *page = restored;
+                self.mark_dirty(page_idx as u32);
+            }
+        }
+        self.invalidate_tlbs();
+    }
+
+    /// Map every page overlapping `range` as fresh zeroed memory with `flags`, discarding whatever was there before (like `mmap(MAP_FIXED)`.)
+    pub fn map(&self, range: Range<u32>, flags: MemoryFlags) {
+        let pages = self.pages_overlapping(range);
+        if self.uninit_tracking() { self.set_undefined(((pages.start as u64) << self.page_shift) .. ((pages.end as u64) << self.page_shift), true) }
+        for page_idx in pages {
+            match &self.flat {
+                Some(flat)  => self.flat_map(flat, page_idx, flags),
+                None        => *self.page_populated(page_idx).lock().unwrap() = Page { flags, mapped: true, ..Page::new() },
+            }
+            self.mark_dirty(page_idx as u32);
+        }
+        self.invalidate_tlbs();
+    }
+
+    /// Unmap every page overlapping `range`, discarding their contents.
+    pub fn unmap(&self, range: Range<u32>) {
+        self.forget_undefined(self.pages_overlapping(range.clone()));
+        for page_idx in self.pages_overlapping(range) {
+            if let Some(flat) = &self.flat { self.flat_unmap(flat, page_idx); self.mark_dirty(page_idx as u32); continue }
+            let Some(page) = self.page(page_idx) else { continue }; // never populated, so already unmapped
+            *page.lock().unwrap() = Page::new();
+            self.mark_dirty(page_idx as u32);
+        }
+        self.invalidate_tlbs();
+    }
+
+    /// Change the flags of every page overlapping `range`, keeping their contents (like `mprotect`.)
+    /// Fails without changing anything if part of `range` isn't mapped, reporting the first unmapped page.
+    pub fn protect(&self, range: Range<u32>, flags: MemoryFlags) -> Result<(), MemFault> {
+        for page_idx in self.pages_overlapping(range.clone()) {
+            if !self.page_mapped(page_idx) {
+                return Err(MemFault { addr: (page_idx << self.page_shift) as u32, access: flags, flags: MemoryFlags::NONE, kind: MemFaultKind::Permission });
+            }
+        }
+        for page_idx in self.pages_overlapping(range) {
+            match &self.flat {
+                Some(flat)  => self.flat_protect(flat, page_idx, flags),
+                None        => self.page_populated(page_idx).lock().unwrap().flags = flags,
+            }
+        }
+        self.invalidate_tlbs();
+        Ok(())
+    }
+
+    /// Enable or disable recording which pages get modified.  Disabled by default to keep normal runs lean.
+    pub fn set_dirty_tracking(&self, enabled: bool) { self.track_dirty.store(enabled, Ordering::Relaxed) }
+
+    /// Page indices (address / [`Memory::page_size`]) modified since the last [`Memory::clear_dirty`], while dirty tracking was enabled.
+    pub fn dirty_pages(&self) -> HashSet<u32> { self.dirty.lock().unwrap().clone() }
+
+    /// [`Memory::dirty_pages`] in ascending order
+    pub fn iter_dirty(&self) -> impl Iterator<Item = u32> {
+        let mut dirty = Vec::from_iter(self.dirty.lock().unwrap().iter().copied());
+        dirty.sort_unstable();
+        dirty.into_iter()
+    }
+
+    pub fn clear_dirty(&self) { self.dirty.lock().unwrap().clear() }
+
+    /// [`Memory::dirty_pages`] and [`Memory::clear_dirty`] in one step, so pages dirtied by other threads in between aren't missed.
+    pub fn take_dirty(&self) -> HashSet<u32> { std::mem::take(&mut *self.dirty.lock().unwrap()) }
+
+    /// Find the lowest `align`ed address within `search` that has `bytes` worth of unmapped pages.
+    pub fn find_unmapped(&self, search: Range<u32>, bytes: u32, align: u32) -> Option<u32> {
+        debug_assert!(align.is_power_of_two() && align >= self.page_size(), "arm::Memory::find_unmapped: align must be a power of two of at least a page");
+        let align = u64::from(align);
+        let bytes = u64::from(bytes);
+        let mut start = (u64::from(search.start) + align - 1) & !(align - 1);
+        'candidates: while start + bytes <= u64::from(search.end) {
+            let shift = self.page_shift;
+            for page_idx in (start >> shift) .. ((start + bytes + (1 << shift) - 1) >> shift) {
+                if self.page_mapped(page_idx as usize) {
+                    // skip past the conflicting page
+                    start = (((page_idx + 1) << shift) + align - 1) & !(align - 1);
+                    continue 'candidates;
+                }
+            }
+            return Some(start as u32);
+        }
+        None
+    }
+
+    // The accessors below panic with the MemFault if `flags` aren't all mapped - use the `try_*` counterparts to handle faults instead.
+
+    pub fn read_u8(&self, addr: u32, flags: MemoryFlags) -> u8 { self.try_read_u8(addr, flags).unwrap_or_else(|fault| fault.panic()) }
+    pub fn read_u16_aligned(&self, addr: u32, flags: MemoryFlags) -> u16 { self.try_read_u16_aligned(addr, flags).unwrap_or_else(|fault| fault.panic()) }
+    pub fn read_u32_aligned(&self, addr: u32, flags: MemoryFlags) -> u32 { self.try_read_u32_aligned(addr, flags).unwrap_or_else(|fault| fault.panic()) }
+    pub fn read_u64_aligned(&self, addr: u32, flags: MemoryFlags) -> u64 { self.try_read_u64_aligned(addr, flags).unwrap_or_else(|fault| fault.panic()) }
+    pub fn read_u16_unaligned(&self, addr: u32, flags: MemoryFlags) -> u16 { self.try_read_u16_unaligned(addr, flags).unwrap_or_else(|fault| fault.panic()) }
+    pub fn read_u32_unaligned(&self, addr: u32, flags: MemoryFlags) -> u32 { self.try_read_u32_unaligned(addr, flags).unwrap_or_else(|fault| fault.panic()) }
+    pub fn read_u64_unaligned(&self, addr: u32, flags: MemoryFlags) -> u64 { self.try_read_u64_unaligned(addr, flags).unwrap_or_else(|fault| fault.panic()) }
+
+    pub fn read_bytes(&self, addr: u32, flags: MemoryFlags, bytes: &mut [u8]) { self.try_read_bytes(addr, flags, bytes).unwrap_or_else(|fault| fault.panic()) }
+
+    pub fn write_u8(&self, addr: u32, flags: MemoryFlags, value: u8) { self.try_write_u8(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    pub fn write_u16_aligned(&self, addr: u32, flags: MemoryFlags, value: u16) { self.try_write_u16_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    pub fn write_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) { self.try_write_u32_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    pub fn write_u64_aligned(&self, addr: u32, flags: MemoryFlags, value: u64) { self.try_write_u64_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
 
-    pub fn read_bytes(&self, addr: u32, flags: MemoryFlags, bytes: &mut [u8]) { self.read_unaligned(addr, flags, bytes) }
+    pub fn write_u16_unaligned(&self, addr: u32, flags: MemoryFlags, value: u16) { self.try_write_u16_unaligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    pub fn write_u32_unaligned(&self, addr: u32, flags: MemoryFlags, value: u32) { self.try_write_u32_unaligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    pub fn write_u64_unaligned(&self, addr: u32, flags: MemoryFlags, value: u64) { self.try_write_u64_unaligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
 
-    fn read_aligned(&self, addr: u32, flags: MemoryFlags, bytes: &mut [u8]) {
-        let page_idx = usize::try_from(addr >> 10).unwrap();
-        let offset = (addr & 0x3FF) as usize;
-        let page = self.pages[page_idx].lock().unwrap();
-        assert!(page.flags.contains(flags), "arm::Memory::read_aligned: page 0x{:08x} not mapped for read", page_idx << 10);
+    pub fn write_bytes(&self, addr: u32, flags: MemoryFlags, bytes: &[u8]) { self.try_write_bytes(addr, flags, bytes).unwrap_or_else(|fault| fault.panic()) }
+
+    /// Atomically replace the value at `addr` with `value`, returning the previous value.
+    pub fn swap_u8(&self, addr: u32, flags: MemoryFlags, value: u8) -> u8 { self.try_swap_u8(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    /// Atomically replace the value at `addr` with `value`, returning the previous value.
+    pub fn swap_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> u32 { self.try_swap_u32_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+
+    /// Atomically replace the value at `addr` with `new` if it's still `current`.
+    /// Returns the previous value, as `Ok` if the exchange happened or `Err` if it didn't.
+    pub fn compare_exchange_u8(&self, addr: u32, flags: MemoryFlags, current: u8, new: u8) -> Result<u8, u8> { self.try_compare_exchange_u8(addr, flags, current, new).unwrap_or_else(|fault| fault.panic()) }
+    /// Atomically replace the value at `addr` with `new` if it's still `current`.
+    /// Returns the previous value, as `Ok` if the exchange happened or `Err` if it didn't.
+    pub fn compare_exchange_u16_aligned(&self, addr: u32, flags: MemoryFlags, current: u16, new: u16) -> Result<u16, u16> { self.try_compare_exchange_u16_aligned(addr, flags, current, new).unwrap_or_else(|fault| fault.panic()) }
+    /// Atomically replace the value at `addr` with `new` if it's still `current`.
+    /// Returns the previous value, as `Ok` if the exchange happened or `Err` if it didn't.
+    pub fn compare_exchange_u32_aligned(&self, addr: u32, flags: MemoryFlags, current: u32, new: u32) -> Result<u32, u32> { self.try_compare_exchange_u32_aligned(addr, flags, current, new).unwrap_or_else(|fault| fault.panic()) }
+    /// Atomically replace the value at `addr` with `new` if it's still `current`.
+    /// Returns the previous value, as `Ok` if the exchange happened or `Err` if it didn't.
+    pub fn compare_exchange_u64_aligned(&self, addr: u32, flags: MemoryFlags, current: u64, new: u64) -> Result<u64, u64> { self.try_compare_exchange_u64_aligned(addr, flags, current, new).unwrap_or_else(|fault| fault.panic()) }
+
+    /// Atomically replace the value at `addr` with `f(previous)`, unless that's `None` - holding the page's lock throughout, so `f` should be quick.
+    /// Returns the previous value, as `Ok` if it was replaced or `Err` if it wasn't (like [`AtomicU32::fetch_update`](std::sync::atomic::AtomicU32::fetch_update).)
+    /// [`Memory::new_flat`] memory has no page locks, so `f` is retried if another thread changed the value first.
+    pub fn fetch_update_u8(&self, addr: u32, flags: MemoryFlags, f: impl FnMut(u8) -> Option<u8>) -> Result<u8, u8> { self.try_fetch_update_u8(addr, flags, f).unwrap_or_else(|fault| fault.panic()) }
+    pub fn fetch_update_u16_aligned(&self, addr: u32, flags: MemoryFlags, f: impl FnMut(u16) -> Option<u16>) -> Result<u16, u16> { self.try_fetch_update_u16_aligned(addr, flags, f).unwrap_or_else(|fault| fault.panic()) }
+    pub fn fetch_update_u32_aligned(&self, addr: u32, flags: MemoryFlags, f: impl FnMut(u32) -> Option<u32>) -> Result<u32, u32> { self.try_fetch_update_u32_aligned(addr, flags, f).unwrap_or_else(|fault| fault.panic()) }
+    pub fn fetch_update_u64_aligned(&self, addr: u32, flags: MemoryFlags, f: impl FnMut(u64) -> Option<u64>) -> Result<u64, u64> { self.try_fetch_update_u64_aligned(addr, flags, f).unwrap_or_else(|fault| fault.panic()) }
+
+    /// Atomically add `value` to the value at `addr` (wrapping), returning the previous value
+    pub fn fetch_add_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> u32 { self.try_fetch_add_u32_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    /// Atomically AND the value at `addr` with `value`, returning the previous value
+    pub fn fetch_and_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> u32 { self.try_fetch_and_u32_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    /// Atomically OR the value at `addr` with `value`, returning the previous value
+    pub fn fetch_or_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> u32 { self.try_fetch_or_u32_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+    /// Atomically XOR the value at `addr` with `value`, returning the previous value
+    pub fn fetch_xor_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> u32 { self.try_fetch_xor_u32_aligned(addr, flags, value).unwrap_or_else(|fault| fault.panic()) }
+}
+
+/// The `try_*` counterparts of the accessors above: a [`MemFault`] instead of a panic if `flags` aren't all mapped.
+impl Memory {
+    pub fn try_read_u8(&self, addr: u32, flags: MemoryFlags) -> Result<u8, MemFault> { let mut result = 0u8; self.read_aligned(addr, flags, bytes_of_mut(&mut result))?; Ok(result) }
+    pub fn try_read_u16_aligned(&self, addr: u32, flags: MemoryFlags) -> Result<u16, MemFault> { let mut result = 0u16; self.read_aligned(addr, flags, bytes_of_mut(&mut result))?; Ok(u16::from_le(result)) }
+    pub fn try_read_u32_aligned(&self, addr: u32, flags: MemoryFlags) -> Result<u32, MemFault> { let mut result = 0u32; self.read_aligned(addr, flags, bytes_of_mut(&mut result))?; Ok(u32::from_le(result)) }
+    pub fn try_read_u64_aligned(&self, addr: u32, flags: MemoryFlags) -> Result<u64, MemFault> { let mut result = 0u64; self.read_aligned(addr, flags, bytes_of_mut(&mut result))?; Ok(u64::from_le(result)) }
+    pub fn try_read_u16_unaligned(&self, addr: u32, flags: MemoryFlags) -> Result<u16, MemFault> { let mut result = 0u16; self.read_unaligned(addr, flags, bytes_of_mut(&mut result))?; Ok(u16::from_le(result)) }
+    pub fn try_read_u32_unaligned(&self, addr: u32, flags: MemoryFlags) -> Result<u32, MemFault> { let mut result = 0u32; self.read_unaligned(addr, flags, bytes_of_mut(&mut result))?; Ok(u32::from_le(result)) }
+    pub fn try_read_u64_unaligned(&self, addr: u32, flags: MemoryFlags) -> Result<u64, MemFault> { let mut result = 0u64; self.read_unaligned(addr, flags, bytes_of_mut(&mut result))?; Ok(u64::from_le(result)) }
+
+    pub fn try_read_bytes(&self, addr: u32, flags: MemoryFlags, bytes: &mut [u8]) -> Result<(), MemFault> { self.read_unaligned(addr, flags, bytes) }
+
+    pub fn try_write_u8(&self, addr: u32, flags: MemoryFlags, value: u8) -> Result<(), MemFault> { self.write_aligned(addr, flags, bytes_of(&value)) }
+    pub fn try_write_u16_aligned(&self, addr: u32, flags: MemoryFlags, value: u16) -> Result<(), MemFault> { self.write_aligned(addr, flags, bytes_of(&value.to_le())) }
+    pub fn try_write_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> Result<(), MemFault> { self.write_aligned(addr, flags, bytes_of(&value.to_le())) }
+    pub fn try_write_u64_aligned(&self, addr: u32, flags: MemoryFlags, value: u64) -> Result<(), MemFault> { self.write_aligned(addr, flags, bytes_of(&value.to_le())) }
+
+    pub fn try_write_u16_unaligned(&self, addr: u32, flags: MemoryFlags, value: u16) -> Result<(), MemFault> { self.write_unaligned(addr, flags, bytes_of(&value.to_le())) }
+    pub fn try_write_u32_unaligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> Result<(), MemFault> { self.write_unaligned(addr, flags, bytes_of(&value.to_le())) }
+    pub fn try_write_u64_unaligned(&self, addr: u32, flags: MemoryFlags, value: u64) -> Result<(), MemFault> { self.write_unaligned(addr, flags, bytes_of(&value.to_le())) }
+
+    pub fn try_write_bytes(&self, addr: u32, flags: MemoryFlags, bytes: &[u8]) -> Result<(), MemFault> { self.write_unaligned(addr, flags, bytes) }
+
+    pub fn try_swap_u8(&self, addr: u32, flags: MemoryFlags, value: u8) -> Result<u8, MemFault> { let mut value = value; self.swap_aligned(addr, flags, bytes_of_mut(&mut value))?; Ok(value) }
+    pub fn try_swap_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> Result<u32, MemFault> { let mut value = value.to_le(); self.swap_aligned(addr, flags, bytes_of_mut(&mut value))?; Ok(u32::from_le(value)) }
+
+    pub fn try_compare_exchange_u8(&self, addr: u32, flags: MemoryFlags, current: u8, new: u8) -> Result<Result<u8, u8>, MemFault> { let mut value = current; Ok(if self.compare_exchange_aligned(addr, flags, bytes_of_mut(&mut value), bytes_of(&new))? { Ok(value) } else { Err(value) }) }
+    pub fn try_compare_exchange_u16_aligned(&self, addr: u32, flags: MemoryFlags, current: u16, new: u16) -> Result<Result<u16, u16>, MemFault> { let mut value = current.to_le(); Ok(if self.compare_exchange_aligned(addr, flags, bytes_of_mut(&mut value), bytes_of(&new.to_le()))? { Ok(u16::from_le(value)) } else { Err(u16::from_le(value)) }) }
+    pub fn try_compare_exchange_u32_aligned(&self, addr: u32, flags: MemoryFlags, current: u32, new: u32) -> Result<Result<u32, u32>, MemFault> { let mut value = current.to_le(); Ok(if self.compare_exchange_aligned(addr, flags, bytes_of_mut(&mut value), bytes_of(&new.to_le()))? { Ok(u32::from_le(value)) } else { Err(u32::from_le(value)) }) }
+    pub fn try_compare_exchange_u64_aligned(&self, addr: u32, flags: MemoryFlags, current: u64, new: u64) -> Result<Result<u64, u64>, MemFault> { let mut value = current.to_le(); Ok(if self.compare_exchange_aligned(addr, flags, bytes_of_mut(&mut value), bytes_of(&new.to_le()))? { Ok(u64::from_le(value)) } else { Err(u64::from_le(value)) }) }
+
+    pub fn try_fetch_update_u8(&self, addr: u32, flags: MemoryFlags, f: impl FnMut(u8) -> Option<u8>) -> Result<Result<u8, u8>, MemFault> { self.fetch_update_aligned(addr, flags, f) }
+    pub fn try_fetch_update_u16_aligned(&self, addr: u32, flags: MemoryFlags, mut f: impl FnMut(u16) -> Option<u16>) -> Result<Result<u16, u16>, MemFault> { self.fetch_update_aligned(addr, flags, |v| f(u16::from_le(v)).map(u16::to_le)).map(|r| r.map(u16::from_le).map_err(u16::from_le)) }
+    pub fn try_fetch_update_u32_aligned(&self, addr: u32, flags: MemoryFlags, mut f: impl FnMut(u32) -> Option<u32>) -> Result<Result<u32, u32>, MemFault> { self.fetch_update_aligned(addr, flags, |v| f(u32::from_le(v)).map(u32::to_le)).map(|r| r.map(u32::from_le).map_err(u32::from_le)) }
+    pub fn try_fetch_update_u64_aligned(&self, addr: u32, flags: MemoryFlags, mut f: impl FnMut(u64) -> Option<u64>) -> Result<Result<u64, u64>, MemFault> { self.fetch_update_aligned(addr, flags, |v| f(u64::from_le(v)).map(u64::to_le)).map(|r| r.map(u64::from_le).map_err(u64::from_le)) }
+
+    pub fn try_fetch_add_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> Result<u32, MemFault> { self.try_fetch_update_u32_aligned(addr, flags, |v| Some(v.wrapping_add(value))).map(|r| r.unwrap_or_else(|v| v)) }
+    pub fn try_fetch_and_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> Result<u32, MemFault> { self.try_fetch_update_u32_aligned(addr, flags, |v| Some(v & value)).map(|r| r.unwrap_or_else(|v| v)) }
+    pub fn try_fetch_or_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> Result<u32, MemFault> { self.try_fetch_update_u32_aligned(addr, flags, |v| Some(v | value)).map(|r| r.unwrap_or_else(|v| v)) }
+    pub fn try_fetch_xor_u32_aligned(&self, addr: u32, flags: MemoryFlags, value: u32) -> Result<u32, MemFault> { self.try_fetch_update_u32_aligned(addr, flags, |v| Some(v ^ value)).map(|r| r.unwrap_or_else(|v| v)) }
+}
+
+/// Byte order parameterized accessors (e.g. for big endian data & SETEND BE guests.)
+impl Memory {
+    pub fn read_u16_aligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> u16 { endian.u16(self.read_u16_aligned(addr, flags)) }
+    pub fn read_u32_aligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> u32 { endian.u32(self.read_u32_aligned(addr, flags)) }
+    pub fn read_u64_aligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> u64 { endian.u64(self.read_u64_aligned(addr, flags)) }
+    pub fn read_u16_unaligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> u16 { endian.u16(self.read_u16_unaligned(addr, flags)) }
+    pub fn read_u32_unaligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> u32 { endian.u32(self.read_u32_unaligned(addr, flags)) }
+    pub fn read_u64_unaligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> u64 { endian.u64(self.read_u64_unaligned(addr, flags)) }
+    pub fn write_u16_aligned_endian(&self, addr: u32, flags: MemoryFlags, value: u16, endian: Endian) { self.write_u16_aligned(addr, flags, endian.u16(value)) }
+    pub fn write_u32_aligned_endian(&self, addr: u32, flags: MemoryFlags, value: u32, endian: Endian) { self.write_u32_aligned(addr, flags, endian.u32(value)) }
+    pub fn write_u64_aligned_endian(&self, addr: u32, flags: MemoryFlags, value: u64, endian: Endian) { self.write_u64_aligned(addr, flags, endian.u64(value)) }
+    pub fn write_u16_unaligned_endian(&self, addr: u32, flags: MemoryFlags, value: u16, endian: Endian) { self.write_u16_unaligned(addr, flags, endian.u16(value)) }
+    pub fn write_u32_unaligned_endian(&self, addr: u32, flags: MemoryFlags, value: u32, endian: Endian) { self.write_u32_unaligned(addr, flags, endian.u32(value)) }
+    pub fn write_u64_unaligned_endian(&self, addr: u32, flags: MemoryFlags, value: u64, endian: Endian) { self.write_u64_unaligned(addr, flags, endian.u64(value)) }
+
+    pub fn try_read_u16_aligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> Result<u16, MemFault> { self.try_read_u16_aligned(addr, flags).map(|v| endian.u16(v)) }
+    pub fn try_read_u32_aligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> Result<u32, MemFault> { self.try_read_u32_aligned(addr, flags).map(|v| endian.u32(v)) }
+    pub fn try_read_u64_aligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> Result<u64, MemFault> { self.try_read_u64_aligned(addr, flags).map(|v| endian.u64(v)) }
+    pub fn try_read_u16_unaligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> Result<u16, MemFault> { self.try_read_u16_unaligned(addr, flags).map(|v| endian.u16(v)) }
+    pub fn try_read_u32_unaligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> Result<u32, MemFault> { self.try_read_u32_unaligned(addr, flags).map(|v| endian.u32(v)) }
+    pub fn try_read_u64_unaligned_endian(&self, addr: u32, flags: MemoryFlags, endian: Endian) -> Result<u64, MemFault> { self.try_read_u64_unaligned(addr, flags).map(|v| endian.u64(v)) }
+    pub fn try_write_u16_aligned_endian(&self, addr: u32, flags: MemoryFlags, value: u16, endian: Endian) -> Result<(), MemFault> { self.try_write_u16_aligned(addr, flags, endian.u16(value)) }
+    pub fn try_write_u32_aligned_endian(&self, addr: u32, flags: MemoryFlags, value: u32, endian: Endian) -> Result<(), MemFault> { self.try_write_u32_aligned(addr, flags, endian.u32(value)) }
+    pub fn try_write_u64_aligned_endian(&self, addr: u32, flags: MemoryFlags, value: u64, endian: Endian) -> Result<(), MemFault> { self.try_write_u64_aligned(addr, flags, endian.u64(value)) }
+    pub fn try_write_u16_unaligned_endian(&self, addr: u32, flags: MemoryFlags, value: u16, endian: Endian) -> Result<(), MemFault> { self.try_write_u16_unaligned(addr, flags, endian.u16(value)) }
+    pub fn try_write_u32_unaligned_endian(&self, addr: u32, flags: MemoryFlags, value: u32, endian: Endian) -> Result<(), MemFault> { self.try_write_u32_unaligned(addr, flags, endian.u32(value)) }
+    pub fn try_write_u64_unaligned_endian(&self, addr: u32, flags: MemoryFlags, value: u64, endian: Endian) -> Result<(), MemFault> { self.try_write_u64_unaligned(addr, flags, endian.u64(value)) }
+}
+
+impl Endian {
+    // convert between little endian and `self` (an involution, so this works in both directions)
+    pub(super) fn u16(self, value: u16) -> u16 { if self == Endian::Big { value.swap_bytes() } else { value } }
+    pub(super) fn u32(self, value: u32) -> u32 { if self == Endian::Big { value.swap_bytes() } else { value } }
+    pub(super) fn u64(self, value: u64) -> u64 { if self == Endian::Big { value.swap_bytes() } else { value } }
+}
+
+impl Memory {
+    fn read_aligned(&self, addr: u32, flags: MemoryFlags, bytes: &mut [u8]) -> Result<(), MemFault> {
+        if let Some(flat) = &self.flat { return self.flat_read(flat, addr, flags, bytes) }
+        let page_idx = self.page_idx(addr);
+        let offset = self.page_offset(addr);
+        let page = self.page(page_idx).unwrap_or(&UNMAPPED).lock().unwrap();
+        page.check(addr, flags)?;
+        if let Some(mmio) = page.mmio.clone() { drop(page); mmio.read(addr, bytes); return Ok(()) } // unlocked, in case the device accesses memory too
         bytes.copy_from_slice(&page.bytes()[offset..][..bytes.len()]);
+        Ok(())
     }
 
-    fn read_unaligned(&self, addr: u32, flags: MemoryFlags, mut bytes: &mut [u8]) {
-        let mut page_idx = usize::try_from(addr >> 10).unwrap();
-        let mut offset = (addr & 0x3FF) as usize;
+    fn read_unaligned(&self, addr: u32, flags: MemoryFlags, mut bytes: &mut [u8]) -> Result<(), MemFault> {
+        if let Some(flat) = &self.flat { return self.flat_read(flat, addr, flags, bytes) }
+        let mut page_idx = self.page_idx(addr);
+        let mut offset = self.page_offset(addr);
 
         while !bytes.is_empty() {
-            let page_remaining = 0x400 - offset;
+            let page_remaining = self.page_size() as usize - offset;
             let read = page_remaining.min(bytes.len());
-            let page = self.pages[page_idx].lock().unwrap();
-            assert!(page.flags.contains(flags), "arm::Memory::read_unaligned: page 0x{:08x} not mapped for read", page_idx << 10);
-            bytes[..read].copy_from_slice(&page.bytes()[offset..][..read]);
+            let page = self.page(page_idx).unwrap_or(&UNMAPPED).lock().unwrap();
+            page.check(((page_idx << self.page_shift) + offset) as u32, flags)?;
+            match page.mmio.clone() {
+                Some(mmio)  => { drop(page); mmio.read(((page_idx << self.page_shift) + offset) as u32, &mut bytes[..read]) },
+                None        => bytes[..read].copy_from_slice(&page.bytes()[offset..][..read]),
+            }
 
             bytes = &mut bytes[read..];
-            page_idx += 1;
+            page_idx = (page_idx + 1) % self.pages(); // wrap around the end of the address space, rather than indexing past it
             offset = 0;
         }
+        Ok(())
+    }
+
+    fn write_aligned(&self, addr: u32, flags: MemoryFlags, bytes: &[u8]) -> Result<(), MemFault> {
+        if let Some(flat) = &self.flat { return self.flat_write(flat, addr, flags, bytes) }
+        let page_idx = self.page_idx(addr);
+        let offset = self.page_offset(addr);
+        let mut page = self.page_for_write(page_idx, addr, flags)?;
+        if let Some(mmio) = page.mmio.clone() { drop(page); mmio.write(addr, bytes); return Ok(()) }
+        page.write_bytes(offset, bytes);
+        self.wrote(&page, page_idx, addr, bytes.len());
+        Ok(())
+    }
+
+    fn swap_aligned(&self, addr: u32, flags: MemoryFlags, bytes: &mut [u8]) -> Result<(), MemFault> {
+        if let Some(flat) = &self.flat { return self.flat_swap(flat, addr, flags, bytes) }
+        let page_idx = self.page_idx(addr);
+        let offset = self.page_offset(addr);
+        let mut page = self.page_for_write(page_idx, addr, flags)?; // held across both the read and the write
+        if let Some(mmio) = page.mmio.clone() {
+            drop(page);
+            let new = bytes.to_vec();
+            mmio.read(addr, bytes);
+            mmio.write(addr, &new);
+            return Ok(());
+        }
+        page.alloc_bytes_mut()[offset..][..bytes.len()].swap_with_slice(bytes);
+        self.wrote(&page, page_idx, addr, bytes.len());
+        Ok(())
+    }
+
+    /// On failure, `current` is overwritten with the value actually in memory.
+    fn compare_exchange_aligned(&self, addr: u32, flags: MemoryFlags, current: &mut [u8], new: &[u8]) -> Result<bool, MemFault> {
+        if let Some(flat) = &self.flat { return self.flat_compare_exchange(flat, addr, flags, current, new) }
+        let page_idx = self.page_idx(addr);
+        let offset = self.page_offset(addr);
+        let mut page = self.page_for_write(page_idx, addr, flags)?; // held across both the compare and the write
+        if let Some(mmio) = page.mmio.clone() {
+            drop(page);
+            let mut actual = current.to_vec();
+            mmio.read(addr, &mut actual);
+            if actual != current { current.copy_from_slice(&actual); return Ok(false) }
+            mmio.write(addr, new);
+            return Ok(true);
+        }
+        let bytes = &mut page.alloc_bytes_mut()[offset..][..new.len()];
+        if *bytes != *current {
+            current.copy_from_slice(bytes);
+            return Ok(false);
+        }
+        bytes.copy_from_slice(new);
+        self.wrote(&page, page_idx, addr, new.len());
+        Ok(true)
+    }
+
+    /// `f` gets & returns little endian values
+    fn fetch_update_aligned<T: bytemuck::Pod>(&self, addr: u32, flags: MemoryFlags, mut f: impl FnMut(T) -> Option<T>) -> Result<Result<T, T>, MemFault> {
+        let mut previous = T::zeroed();
+        let mut update = |bytes: &mut [u8]| f(bytemuck::pod_read_unaligned(bytes)).map(|new| bytes.copy_from_slice(bytes_of(&new))).is_some();
+        let updated = self.update_aligned(addr, flags, bytes_of_mut(&mut previous), &mut update)?;
+        Ok(if updated { Ok(previous) } else { Err(previous) })
+    }
+
+    /// Read the value at `addr` into `previous`, and write it back as modified by `update` unless that returns `false` - all under the page's lock.
+    /// `previous` must be aligned to its size, of at most 8 bytes.
+    fn update_aligned(&self, addr: u32, flags: MemoryFlags, previous: &mut [u8], update: &mut dyn FnMut(&mut [u8]) -> bool) -> Result<bool, MemFault> {
+        if let Some(flat) = &self.flat { return self.flat_update(flat, addr, flags, previous, update) }
+        let page_idx = self.page_idx(addr);
+        let offset = self.page_offset(addr);
+        let mut page = self.page_for_write(page_idx, addr, flags)?; // held across both the read and the write
+        let mut new = [0u8; 8];
+        let new = &mut new[..previous.len()];
+        if let Some(mmio) = page.mmio.clone() {
+            drop(page);
+            mmio.read(addr, previous);
+            new.copy_from_slice(previous);
+            let updated = update(new);
+            if updated { mmio.write(addr, new) }
+            return Ok(updated);
+        }
+        previous.copy_from_slice(&page.bytes()[offset..][..previous.len()]);
+        new.copy_from_slice(previous);
+        if !update(new) { return Ok(false) }
+        page.alloc_bytes_mut()[offset..][..new.len()].copy_from_slice(new);
+        self.wrote(&page, page_idx, addr, new.len());
+        Ok(true)
+    }
+
+    fn write_unaligned(&self, addr: u32, flags: MemoryFlags, mut bytes: &[u8]) -> Result<(), MemFault> {
+        if let Some(flat) = &self.flat { return self.flat_write(flat, addr, flags, bytes) }
+        let mut page_idx = self.page_idx(addr);
+        let mut offset = self.page_offset(addr);
+
+        while !bytes.is_empty() {
+            let page_remaining = self.page_size() as usize - offset;
+            let write = page_remaining.min(bytes.len());
+            let mut page = self.page_for_write(page_idx, ((page_idx << self.page_shift) + offset) as u32, flags)?;
+            match page.mmio.clone() {
+                Some(mmio)  => { drop(page); mmio.write(((page_idx << self.page_shift) + offset) as u32, &bytes[..write]) },
+                None        => { page.write_bytes(offset, &bytes[..write]); self.wrote(&page, page_idx, ((page_idx << self.page_shift) + offset) as u32, write); },
+            }
+
+            bytes = &bytes[write..];
+            page_idx = (page_idx + 1) % self.pages(); // wrap around the end of the address space, rather than indexing past it
+            offset = 0;
+        }
+        Ok(())
     }
 }
 
 impl Memory {
-    fn init_pages(&mut self, base: u32, flags: MemoryFlags, mut bytes: u32, mut on_page: impl FnMut(&mut Page, Range<u32>) -> io::Result<()>) -> io::Result<()> {
-        let mut page_idx = base >> 10;
+    fn init_pages(&self, base: u32, flags: MemoryFlags, mut bytes: u32, mut on_page: impl FnMut(&mut Page, Range<u32>) -> io::Result<()>) -> io::Result<()> {
+        if let Some(flat) = &self.flat { return self.flat_init_pages(flat, base, flags, bytes, on_page) }
+        self.define(base, bytes as usize); // whether copied or zeroed, as ELF loaders & linux would
+        let mut page_idx = base >> self.page_shift;
+        let page_size = self.page_size();
 
         // special case first page
         if bytes > 0 {
-            let offset = base & 0x3FF;
-            let size = (0x400 - offset).min(bytes);
+            let offset = base & (page_size - 1);
+            let size = (page_size - offset).min(bytes);
             let mut page = self.init_page(page_idx, flags)?;
             on_page(&mut *page, offset .. offset + size)?;
             page_idx += 1;
@@ -117,35 +597,154 @@ impl Memory {
         }
 
         while bytes > 0 {
-            let size = bytes.min(0x400);
+            let size = bytes.min(page_size);
             let mut page = self.init_page(page_idx, flags)?;
             on_page(&mut *page, 0 .. size)?;
             page_idx += 1;
             bytes -= size;
         }
 
+        self.invalidate_tlbs(); // pages may have been read only
         Ok(())
     }
 
-    fn init_page<'a>(&'a mut self, page_idx: u32, flags: MemoryFlags) -> io::Result<impl DerefMut<Target = Page> + 'a> {
+    fn init_page<'a>(&'a self, page_idx: u32, flags: MemoryFlags) -> io::Result<impl DerefMut<Target = Page> + 'a> {
         let page_idx = usize::try_from(page_idx).map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "arm::Memory: tried to initialize beyond address space"))?;
-        let page = self.pages.get(page_idx).ok_or_else(|| io::Error::new(io::ErrorKind::OutOfMemory, "arm::Memory: tried to initialize beyond address space"))?;
-        let mut page = page.lock().unwrap(); // panic on poisoned lock
+        if page_idx >= self.pages() { return Err(io::Error::new(io::ErrorKind::OutOfMemory, "arm::Memory: tried to initialize beyond address space")) }
+        let mut page = self.page_populated(page_idx).lock().unwrap(); // panic on poisoned lock
         page.flags |= flags;
+        page.mapped = true;
+        self.mark_dirty(page_idx as u32);
         Ok(page)
     }
+
+    /// Indices of the pages overlapping `range`
+    pub(super) fn pages_overlapping(&self, range: Range<u32>) -> Range<usize> {
+        if range.is_empty() { return 0 .. 0 }
+        let shift = self.page_shift;
+        (range.start >> shift) as usize .. ((u64::from(range.end) + (1 << shift) - 1) >> shift) as usize
+    }
+
+    /// How many pages the address space is divided into
+    pub(super) fn pages(&self) -> usize { 1 << (32 - self.page_shift) }
+
+    /// The index of the page `addr` is on
+    pub(super) fn page_idx(&self, addr: u32) -> usize { (addr >> self.page_shift) as usize }
+
+    /// The offset of `addr` into its page
+    pub(super) fn page_offset(&self, addr: u32) -> usize { (addr & ((1 << self.page_shift) - 1)) as usize }
+
+    /// Call `f` with the index & state of every mapped page (or with data, or a device), in ascending order
+    pub(super) fn for_each_mapped_page(&self, mut f: impl FnMut(usize, &Page)) {
+        if let Some(flat) = &self.flat {
+            for page_idx in 0 .. self.pages() {
+                if let Some(page) = self.flat_page(flat, page_idx) { f(page_idx, &page) }
+            }
+            return;
+        }
+        for (table_idx, table) in self.directory.iter().enumerate() {
+            let Some(table) = table.get() else { continue };
+            for (i, page) in table.iter().enumerate() {
+                let page = page.lock().unwrap();
+                if !page.is_unmapped() { f(table_idx * TABLE_PAGES + i, &page) }
+            }
+        }
+    }
+
+    /// Whether page `page_idx` can be read by the host without side effects: mapped, and not a device
+    pub(super) fn page_inspectable(&self, page_idx: usize) -> bool {
+        match &self.flat {
+            Some(flat)  => self.flat_page_inspectable(flat, page_idx),
+            None        => self.page(page_idx).is_some_and(|page| { let page = page.lock().unwrap(); !page.is_unmapped() && page.mmio.is_none() }),
+        }
+    }
+
+    /// How many page tables have been populated (see [`Memory::page_populated`])
+    pub(super) fn populated_tables(&self) -> usize { self.directory.iter().filter(|table| table.get().is_some()).count() }
+
+    /// Whether page `page_idx` is mapped (or has data, or a device)
+    fn page_mapped(&self, page_idx: usize) -> bool {
+        match &self.flat {
+            Some(flat)  => self.flat_mapped(flat, page_idx),
+            None        => self.page(page_idx).is_some_and(|page| !page.lock().unwrap().is_unmapped()),
+        }
+    }
+
+    /// Page `page_idx`, or [`None`] if its table was never populated (in which case it's unmapped)
+    pub(super) fn page(&self, page_idx: usize) -> Option<&Mutex<Page>> {
+        Some(&self.directory[page_idx / TABLE_PAGES].get()?[page_idx % TABLE_PAGES])
+    }
+
+    /// Page `page_idx`, populating its table if this is the first time it's been written to or mapped
+    pub(super) fn page_populated(&self, page_idx: usize) -> &Mutex<Page> {
+        let table = self.directory[page_idx / TABLE_PAGES].get_or_init(|| (0 .. TABLE_PAGES).map(|_| Mutex::new(Page::new())).collect());
+        &table[page_idx % TABLE_PAGES]
+    }
+
+    /// Page `page_idx`, locked for an access of `flags` at `addr` that might write to it.  Its table is only populated once the access is known to be allowed,
+    /// so faulting stores to never mapped memory don't allocate page tables.
+    fn page_for_write(&self, page_idx: usize, addr: u32, flags: MemoryFlags) -> Result<MutexGuard<'_, Page>, MemFault> {
+        if self.page(page_idx).is_none() { UNMAPPED_PAGE.check(addr, flags)?; }
+        let page = self.page_populated(page_idx).lock().unwrap();
+        page.check(addr, flags)?;
+        Ok(page)
+    }
+
+    /// Page `page_idx` (locked as `page`) had its data written
+    fn wrote(&self, page: &Page, page_idx: usize, addr: u32, len: usize) {
+        self.mark_dirty(page_idx as u32);
+        self.define(addr, len);
+        if !page.flags.contains(MemoryFlags::WRITE) { self.invalidate_tlbs() } // the host wrote to a page Tlbs might be caching
+    }
+
+    pub(super) fn mark_dirty(&self, page_idx: u32) {
+        if self.track_dirty.load(Ordering::Relaxed) { self.dirty.lock().unwrap().insert(page_idx); }
+    }
 }
 
 impl Page {
     pub fn new() -> Self { Default::default() }
 
+    fn check(&self, addr: u32, access: MemoryFlags) -> Result<(), MemFault> {
+        if self.flags.contains(access) { Ok(()) } else { Err(MemFault { addr, access, flags: self.flags, kind: MemFaultKind::Permission }) }
+    }
+
     pub fn alloc_bytes_mut(&mut self) -> &mut [u8] {
-        bytes_of_mut(&mut **self.data.get_or_insert_with(|| Box::new([0u64; 512])))
+        bytes_of_mut(Arc::make_mut(self.data.get_or_insert_with(|| Arc::new([0u64; 512])))) // copies the data if still shared with a fork
+    }
+
+    /// Write `bytes` at `offset`, without allocating data if the page still shares the zero page and `bytes` are all zero (e.g. a guest clearing its own BSS)
+    fn write_bytes(&mut self, offset: usize, bytes: &[u8]) {
+        if self.data.is_none() && bytes.iter().all(|b| *b == 0) { return }
+        self.alloc_bytes_mut()[offset..][..bytes.len()].copy_from_slice(bytes);
     }
 
     pub fn bytes(&self) -> &[u8] {
         bytes_of(self.data.as_ref().map_or(&ZEROS, |data| &**data))
     }
+
+    pub fn is_unmapped(&self) -> bool { !self.mapped }
 }
 
 const ZEROS : [u64; 512] = [0; 512];
+pub(super) const TABLE_PAGES : usize = 1 << 10; // pages per directory entry (1 MiB - 4 MiB of address space)
+
+/// Stands in for the pages of tables that were never populated, for reads (`Memory::page_populated` for writes)
+static UNMAPPED : Mutex<Page> = Mutex::new(UNMAPPED_PAGE);
+const UNMAPPED_PAGE : Page = Page { data: None, flags: MemoryFlags::NONE, mmio: None, mapped: false };
+
+impl MemFault {
+    /// The panicking accessors' failure, raised only after the page's lock was released (so it isn't poisoned)
+    #[cold] pub(super) fn panic(self) -> ! { panic!("arm::Memory: {}", self) }
+}
+
+impl std::fmt::Display for MemFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.kind {
+            MemFaultKind::Permission    => write!(f, "0x{:08x} not mapped for {:?} (its page is mapped for {:?})", self.addr, self.access, self.flags),
+            MemFaultKind::Alignment     => write!(f, "0x{:08x} misaligned for {:?}", self.addr, self.access),
+        }
+    }
+}
+
+impl std::error::Error for MemFault {}
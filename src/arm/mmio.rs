@@ -0,0 +1,51 @@
+use super::*;
+
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (A3.5 Memory types and attributes and the memory order model: Device memory)
+//
+// Limitations:
+// - Devices are only consulted at page (Memory::page_size) granularity: a range sharing a page with RAM takes over the whole page
+// - SWP & exclusives on device memory are a separate read & write, not atomic
+// - Devices can't fault accesses - return something (e.g. zeros), like a bus that ignores unknown registers
+
+
+
+/// Host code standing in for memory: e.g. a UART, timer, or interrupt controller, mapped with [`Memory::map_mmio`].
+///
+/// `offset` is relative to the start of the mapped range, and `bytes` is the size of the access (1, 2, 4, or 8 bytes for CPU loads & stores),
+/// in guest memory order.  Called from whichever thread is accessing memory - use interior mutability for device state.
+pub trait MmioDevice : Debug + Send + Sync {
+    fn read(&self, offset: u32, bytes: &mut [u8]);
+    fn write(&self, offset: u32, bytes: &[u8]);
+}
+
+/// A page's share of an [`MmioDevice`] mapping
+#[derive(Clone, Debug)]
+pub(super) struct Mmio {
+    base:   u32,
+    device: Arc<dyn MmioDevice>,
+}
+
+impl Mmio {
+    pub(super) fn read(&self, addr: u32, bytes: &mut [u8]) { self.device.read(addr.wrapping_sub(self.base), bytes) }
+    pub(super) fn write(&self, addr: u32, bytes: &[u8]) { self.device.write(addr.wrapping_sub(self.base), bytes) }
+}
+
+impl Memory {
+    /// Route accesses to every page overlapping `range` (mapped with `flags`) to `device` instead, replacing whatever was mapped there.
+    /// `device` sees offsets relative to `range.start`.  [`Memory::unmap`] the range to remove it again.
+    pub fn map_mmio(&self, range: Range<u32>, flags: MemoryFlags, device: Arc<dyn MmioDevice>) {
+        let mmio = Mmio { base: range.start, device };
+        self.forget_undefined(self.pages_overlapping(range.clone()));
+        for page_idx in self.pages_overlapping(range) {
+            *self.page_populated(page_idx).lock().unwrap() = Page { flags, mmio: Some(mmio.clone()), mapped: true, ..Page::new() };
+            if let Some(flat) = &self.flat { self.flat_map_mmio(flat, page_idx, flags); }
+            self.mark_dirty(page_idx as u32);
+        }
+        self.invalidate_tlbs();
+    }
+}
@@ -0,0 +1,524 @@
+#![allow(unused_parens)]
+
+use super::*;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (A7.4 Advanced SIMD data-processing instructions, A7.7 Advanced SIMD element or structure load/store instructions)
+//
+// Limitations:
+// - No polynomial, long/wide/narrow-saturating, by-scalar, table lookup, or conversion ops yet
+// - Float ops are F32 only, and ignore the "standard FPSCR value" flush-to-zero & default NaN behavior
+// - Alignment hints in load/store encodings are ignored rather than checked
+
+
+
+const FPSCR_QC : u32 = 1 << 27; // cumulative saturation
+
+/// Split a 64 or 128-bit vector into its `esize`-bit lanes, least significant first
+fn split(value: u128, esize: u32, bits: u32) -> Vec<u64> {
+    let mask = u64::MAX >> (64 - esize);
+    (0 .. bits / esize).map(|i| (value >> (i * esize)) as u64 & mask).collect()
+}
+
+/// Reassemble lanes produced by [`split`], truncating each to `esize` bits
+fn join(lanes: &[u64], esize: u32) -> u128 {
+    let mask = u64::MAX >> (64 - esize);
+    lanes.iter().enumerate().fold(0, |v, (i, &lane)| v | u128::from(lane & mask) << (i as u32 * esize))
+}
+
+/// Sign extend an `esize`-bit lane
+fn sx(lane: u64, esize: u32) -> i64 { ((lane << (64 - esize)) as i64) >> (64 - esize) }
+
+fn f(lane: u64) -> f32 { f32::from_bits(lane as u32) }
+fn bits(value: f32) -> u64 { u64::from(value.to_bits()) }
+
+impl Cpu {
+    /// Unconditional Advanced SIMD (NEON) instructions: data processing (`1111 001x`) and element/structure load/store (`1111 0100 xxx0`)
+    pub(super) fn impl_neon(&mut self, mem: &Memory, op: u32) {
+        match op >> 24 {
+            0xF2 | 0xF3 if op & 0x0080_0000 == 0                    => self.neon_three_same(op),
+            0xF2 | 0xF3 if op & 0x10 != 0 && op & 0x0038_0080 == 0  => self.neon_modified_immediate(op),
+            0xF2 | 0xF3 if op & 0x10 != 0                           => self.neon_shift(op),
+            0xF2        if op & 0x0030_0000 == 0x0030_0000          => self.neon_extract(op),
+            0xF3        if op & 0x0030_0800 == 0x0030_0000          => self.neon_two_misc(op),
+            0xF3        if op & 0x0030_0F80 == 0x0030_0C00          => self.neon_dup_scalar(op),
+            0xF4        if op & 0x0010_0000 == 0                    => self.neon_load_store(mem, op),
+            _ => self.undefined(op),
+        }
+    }
+
+    /// Decode a `X:Vx` D register number (Q registers are named by their even D register)
+    fn neon_reg(v: u32, x: u32) -> usize { ((x << 4) | v) as usize }
+
+    fn neon_read(&self, d: usize, q: bool) -> u128 { if q { u128::from(self.vfp.d[d]) | u128::from(self.vfp.d[d + 1]) << 64 } else { u128::from(self.vfp.d[d]) } }
+    fn neon_write(&mut self, d: usize, q: bool, value: u128) {
+        self.vfp.d[d] = value as u64;
+        if q { self.vfp.d[d + 1] = (value >> 64) as u64; }
+    }
+
+    /// Advanced SIMD three registers of the same length
+    fn neon_three_same(&mut self, op: u32) {
+        let u           = ((op >> 24) & 0b1) == 1;
+        let d           = ((op >> 22) & 0b1);
+        let size        = ((op >> 20) & 0b11);
+        let vn          = ((op >> 16) & 0b1111);
+        let vd          = ((op >> 12) & 0b1111);
+        let a           = ((op >>  8) & 0b1111);
+        let n           = ((op >>  7) & 0b1);
+        let q           = ((op >>  6) & 0b1) == 1;
+        let m           = ((op >>  5) & 0b1);
+        let b           = ((op >>  4) & 0b1) == 1;
+        let vm          = ((op >>  0) & 0b1111);
+
+        let (dd, dn, dm) = (Self::neon_reg(vd, d), Self::neon_reg(vn, n), Self::neon_reg(vm, m));
+        if q && (dd | dn | dm) & 1 != 0 { return self.undefined(op) }
+        let vbits       = if q { 128 } else { 64 };
+        let esize       = 8 << size;
+        let (x, y, acc) = (self.neon_read(dn, q), self.neon_read(dm, q), self.neon_read(dd, q));
+        let (xs, ys, accs) = (split(x, esize, vbits), split(y, esize, vbits), split(acc, esize, vbits));
+        let ones        = u64::MAX;
+
+        let mut saturated = false;
+        let mut saturate = |value: i128| -> u64 {
+            let (min, max) = if u { (0, (1i128 << esize) - 1) } else { (-(1i128 << (esize - 1)), (1i128 << (esize - 1)) - 1) };
+            if value < min || value > max { saturated = true; }
+            value.clamp(min, max) as u64
+        };
+        let lane = |v: u64| -> i128 { if u { i128::from(v) } else { i128::from(sx(v, esize)) } };
+        let pairwise = |g: &dyn Fn(u64, u64) -> u64| -> Vec<u64> { xs.chunks(2).chain(ys.chunks(2)).map(|p| g(p[0], p[1])).collect() };
+
+        let lanes: Vec<u64> = match (a, b, u) {
+            (0b0000, false, _) => xs.iter().zip(&ys).map(|(&x, &y)| ((lane(x) + lane(y)) >> 1) as u64).collect(),     // VHADD
+            (0b0000, true,  _) => xs.iter().zip(&ys).map(|(&x, &y)| saturate(lane(x) + lane(y))).collect(),           // VQADD
+            (0b0001, false, _) => xs.iter().zip(&ys).map(|(&x, &y)| ((lane(x) + lane(y) + 1) >> 1) as u64).collect(), // VRHADD
+            (0b0001, true,  _) => { // bitwise ops don't care about lanes
+                let result = match (u, size) {
+                    (false, 0b00) => x & y,                     // VAND
+                    (false, 0b01) => x & !y,                    // VBIC
+                    (false, 0b10) => x | y,                     // VORR (VMOV when Vn == Vm)
+                    (false, _   ) => x | !y,                    // VORN
+                    (true,  0b00) => x ^ y,                     // VEOR
+                    (true,  0b01) => (x & acc) | (y & !acc),    // VBSL
+                    (true,  0b10) => (x & y) | (acc & !y),      // VBIT
+                    (true,  _   ) => (acc & y) | (x & !y),      // VBIF
+                };
+                return self.neon_write(dd, q, result);
+            },
+            (0b0010, false, _) => xs.iter().zip(&ys).map(|(&x, &y)| ((lane(x) - lane(y)) >> 1) as u64).collect(),     // VHSUB
+            (0b0010, true,  _) => xs.iter().zip(&ys).map(|(&x, &y)| saturate(lane(x) - lane(y))).collect(),           // VQSUB
+            (0b0011, false, _) => xs.iter().zip(&ys).map(|(&x, &y)| if lane(x) >  lane(y) { ones } else { 0 }).collect(), // VCGT
+            (0b0011, true,  _) => xs.iter().zip(&ys).map(|(&x, &y)| if lane(x) >= lane(y) { ones } else { 0 }).collect(), // VCGE
+            (0b0100, false, _) => xs.iter().zip(&ys).map(|(&x, &y)| { // VSHL (register): Vm lanes shifted by the signed bottom byte of Vn lanes
+                match sx(x & 0xFF, 8) {
+                    s if s >= i64::from(esize)      => 0,
+                    s if s >= 0                     => y << s,
+                    s if u                          => y.checked_shr(-s as u32).unwrap_or(0),
+                    s                               => (sx(y, esize) >> (-s).min(63)) as u64,
+                }
+            }).collect(),
+            (0b0110, false, _) => xs.iter().zip(&ys).map(|(&x, &y)| if lane(x) >= lane(y) { x } else { y }).collect(),  // VMAX
+            (0b0110, true,  _) => xs.iter().zip(&ys).map(|(&x, &y)| if lane(x) <= lane(y) { x } else { y }).collect(),  // VMIN
+            (0b0111, false, _) => xs.iter().zip(&ys).map(|(&x, &y)| (lane(x) - lane(y)).unsigned_abs() as u64).collect(), // VABD
+            (0b0111, true,  _) => xs.iter().zip(&ys).zip(&accs).map(|((&x, &y), &acc)| acc.wrapping_add((lane(x) - lane(y)).unsigned_abs() as u64)).collect(), // VABA
+            (0b1000, false, false)  => xs.iter().zip(&ys).map(|(&x, &y)| x.wrapping_add(y)).collect(),                 // VADD
+            (0b1000, false, true )  => xs.iter().zip(&ys).map(|(&x, &y)| x.wrapping_sub(y)).collect(),                 // VSUB
+            (0b1000, true,  false)  => xs.iter().zip(&ys).map(|(&x, &y)| if x & y != 0 { ones } else { 0 }).collect(), // VTST
+            (0b1000, true,  true )  => xs.iter().zip(&ys).map(|(&x, &y)| if x == y { ones } else { 0 }).collect(),     // VCEQ
+            (0b1001, false, false)  => xs.iter().zip(&ys).zip(&accs).map(|((&x, &y), &acc)| acc.wrapping_add(x.wrapping_mul(y))).collect(), // VMLA
+            (0b1001, false, true )  => xs.iter().zip(&ys).zip(&accs).map(|((&x, &y), &acc)| acc.wrapping_sub(x.wrapping_mul(y))).collect(), // VMLS
+            (0b1001, true,  false)  => xs.iter().zip(&ys).map(|(&x, &y)| x.wrapping_mul(y)).collect(),                 // VMUL
+            (0b1010, false, _) if !q => pairwise(&|x, y| if lane(x) >= lane(y) { x } else { y }),                      // VPMAX
+            (0b1010, true,  _) if !q => pairwise(&|x, y| if lane(x) <= lane(y) { x } else { y }),                      // VPMIN
+            (0b1011, true,  false) if !q => pairwise(&|x, y| x.wrapping_add(y)),                                      // VPADD
+            (0b1101 ..= 0b1111, _, _) if size & 1 == 0 => return self.neon_three_same_float(op, dd, q, [x, y, acc]),
+            _ => return self.undefined(op),
+        };
+
+        if saturated { self.vfp.fpscr |= FPSCR_QC; }
+        self.neon_write(dd, q, join(&lanes, esize));
+    }
+
+    /// The F32 half of [`Self::neon_three_same`]
+    fn neon_three_same_float(&mut self, op: u32, dd: usize, q: bool, [x, y, acc]: [u128; 3]) {
+        let u           = ((op >> 24) & 0b1) == 1;
+        let high        = ((op >> 21) & 0b1) == 1; // size<1> selects between paired ops
+        let a           = ((op >>  8) & 0b1111);
+        let b           = ((op >>  4) & 0b1) == 1;
+
+        let vbits       = if q { 128 } else { 64 };
+        let (xs, ys, accs) = (split(x, 32, vbits), split(y, 32, vbits), split(acc, 32, vbits));
+        let (xs, ys, accs) = (&xs[..], &ys[..], &accs[..]);
+        let map         = |g: &dyn Fn(f32, f32) -> f32| -> Vec<u64> { xs.iter().zip(ys).map(|(&x, &y)| bits(g(f(x), f(y)))).collect() };
+        let compare     = |g: &dyn Fn(f32, f32) -> bool| -> Vec<u64> { xs.iter().zip(ys).map(|(&x, &y)| if g(f(x), f(y)) { u64::MAX } else { 0 }).collect() };
+        let pairwise    = |g: &dyn Fn(f32, f32) -> f32| -> Vec<u64> { xs.chunks(2).chain(ys.chunks(2)).map(|p| bits(g(f(p[0]), f(p[1])))).collect() };
+        let accumulate  = |sign: f32| -> Vec<u64> { xs.iter().zip(ys).zip(accs).map(|((&x, &y), &acc)| bits(f(acc) + sign * (f(x) * f(y)))).collect() };
+        let max         = |x: f32, y: f32| if x.is_nan() || y.is_nan() { f32::NAN } else { x.max(y) };
+        let min         = |x: f32, y: f32| if x.is_nan() || y.is_nan() { f32::NAN } else { x.min(y) };
+
+        let lanes = match (a, b, u, high) {
+            (0b1101, false, false, false) => map(&|x, y| x + y),                   // VADD.F32
+            (0b1101, false, false, true ) => map(&|x, y| x - y),                   // VSUB.F32
+            (0b1101, false, true,  false) if !q => pairwise(&|x, y| x + y),        // VPADD.F32
+            (0b1101, false, true,  true ) => map(&|x, y| (x - y).abs()),           // VABD.F32
+            (0b1101, true,  false, false) => accumulate( 1.0),                     // VMLA.F32
+            (0b1101, true,  false, true ) => accumulate(-1.0),                     // VMLS.F32
+            (0b1101, true,  true,  false) => map(&|x, y| x * y),                   // VMUL.F32
+            (0b1110, false, false, false) => compare(&|x, y| x == y),              // VCEQ.F32
+            (0b1110, false, true,  false) => compare(&|x, y| x >= y),              // VCGE.F32
+            (0b1110, false, true,  true ) => compare(&|x, y| x > y),               // VCGT.F32
+            (0b1110, true,  true,  false) => compare(&|x, y| x.abs() >= y.abs()),  // VACGE.F32
+            (0b1110, true,  true,  true ) => compare(&|x, y| x.abs() > y.abs()),   // VACGT.F32
+            (0b1111, false, false, false) => map(&max),                            // VMAX.F32
+            (0b1111, false, false, true ) => map(&min),                            // VMIN.F32
+            (0b1111, false, true,  false) if !q => pairwise(&max),                 // VPMAX.F32
+            (0b1111, false, true,  true ) if !q => pairwise(&min),                 // VPMIN.F32
+            (0b1111, true,  false, false) => map(&|x, y| 2.0 - x * y),             // VRECPS.F32
+            (0b1111, true,  false, true ) => map(&|x, y| (3.0 - x * y) / 2.0),     // VRSQRTS.F32
+            _ => return self.undefined(op),
+        };
+        self.neon_write(dd, q, join(&lanes, 32));
+    }
+
+    /// Advanced SIMD one register and a modified immediate (VMOV, VMVN, VORR, VBIC)
+    fn neon_modified_immediate(&mut self, op: u32) {
+        let i           = ((op >> 24) & 0b1);
+        let d           = ((op >> 22) & 0b1);
+        let imm3        = ((op >> 16) & 0b111);
+        let vd          = ((op >> 12) & 0b1111);
+        let cmode       = ((op >>  8) & 0b1111);
+        let q           = ((op >>  6) & 0b1) == 1;
+        let invert      = ((op >>  5) & 0b1) == 1;
+        let imm4        = ((op >>  0) & 0b1111);
+
+        let dd          = Self::neon_reg(vd, d);
+        if q && dd & 1 != 0 { return self.undefined(op) }
+        let imm8        = u64::from((i << 7) | (imm3 << 4) | imm4);
+        let replicate32 = |v: u64| v | v << 32;
+        let replicate16 = |v: u64| v | v << 16 | v << 32 | v << 48;
+        let imm64       = match cmode >> 1 {
+            0b000 ..= 0b011 => replicate32(imm8 << (8 * (cmode >> 1))),
+            0b100 | 0b101   => replicate16(imm8 << (8 * ((cmode >> 1) & 1))),
+            0b110           => replicate32(if cmode & 1 == 0 { imm8 << 8 | 0xFF } else { imm8 << 16 | 0xFFFF }),
+            _ => match (cmode & 1, invert) {
+                (0, false)  => imm8 * 0x0101_0101_0101_0101,                                                        // VMOV.I8
+                (0, true )  => (0 .. 8).filter(|b| imm8 & (1 << b) != 0).fold(0, |v, b| v | 0xFF << (8 * b)),      // VMOV.I64
+                (_, false)  => { // VMOV.F32
+                    let (a, b, cdefgh) = ((imm8 >> 7) & 1, (imm8 >> 6) & 1, imm8 & 0x3F);
+                    replicate32(a << 31 | (b ^ 1) << 30 | (if b == 1 { 0x1F } else { 0 }) << 25 | cdefgh << 19)
+                },
+                (_, true )  => return self.undefined(op),
+            },
+        };
+        let imm         = u128::from(imm64) | u128::from(imm64) << 64;
+
+        let orr_bic     = cmode & 1 == 1 && cmode < 0b1100;
+        let old         = self.neon_read(dd, q);
+        let result      = match (cmode, orr_bic, invert) {
+            (0b1110 | 0b1111, _, _) => imm, // VMOV.I8, VMOV.I64, VMOV.F32: op picked the expansion above rather than inverting
+            (_, false, false)   => imm,         // VMOV
+            (_, false, true )   => !imm,        // VMVN
+            (_, true,  false)   => old | imm,   // VORR
+            (_, true,  true )   => old & !imm,  // VBIC
+        };
+        self.neon_write(dd, q, result);
+    }
+
+    /// Advanced SIMD two registers and a shift amount
+    fn neon_shift(&mut self, op: u32) {
+        let u           = ((op >> 24) & 0b1) == 1;
+        let d           = ((op >> 22) & 0b1);
+        let imm6        = ((op >> 16) & 0b111111);
+        let vd          = ((op >> 12) & 0b1111);
+        let a           = ((op >>  8) & 0b1111);
+        let l           = ((op >>  7) & 0b1) == 1;
+        let q           = ((op >>  6) & 0b1) == 1;
+        let m           = ((op >>  5) & 0b1);
+        let vm          = ((op >>  0) & 0b1111);
+
+        let (dd, dm)    = (Self::neon_reg(vd, d), Self::neon_reg(vm, m));
+        if q && (dd | dm) & 1 != 0 { return self.undefined(op) }
+        let esize       = if l { 64 } else { 8 << (31 - imm6.leading_zeros() - 3) }; // 001xxx = 8, 01xxxx = 16, 1xxxxx = 32
+        let right       = if l { 64 - imm6 } else { 2 * esize - imm6 };
+        let left        = if l { imm6 } else { imm6 - esize };
+        let vbits       = if q { 128 } else { 64 };
+        let shr         = |v: u64, shift: u32| -> u64 { if u { v.checked_shr(shift).unwrap_or(0) } else { (sx(v, esize) >> shift.min(63)) as u64 } };
+        let rshr        = |v: u64, shift: u32| -> u64 { shr(v, shift).wrapping_add((v >> (shift - 1)) & 1) }; // add the last bit shifted out
+        let src         = split(self.neon_read(dm, q), esize, vbits);
+        let old         = split(self.neon_read(dd, q), esize, vbits);
+        let field       = |shift: u32| -> u64 { (u64::MAX >> (64 - esize)).checked_shr(shift).unwrap_or(0) };
+
+        let (lanes, esize, q): (Vec<u64>, u32, bool) = match (a, u, op & 0x40 != 0) {
+            (0b0000, _, _)      => (src.iter().map(|&v| shr(v, right)).collect(), esize, q),                                                   // VSHR
+            (0b0001, _, _)      => (src.iter().zip(&old).map(|(&v, &acc)| acc.wrapping_add(shr(v, right))).collect(), esize, q),               // VSRA
+            (0b0010, _, _)      => (src.iter().map(|&v| rshr(v, right)).collect(), esize, q),                                                  // VRSHR
+            (0b0011, _, _)      => (src.iter().zip(&old).map(|(&v, &acc)| acc.wrapping_add(rshr(v, right))).collect(), esize, q),              // VRSRA
+            (0b0100, true, _)   => (src.iter().zip(&old).map(|(&v, &acc)| (acc & !field(right)) | v.checked_shr(right).unwrap_or(0)).collect(), esize, q), // VSRI
+            (0b0101, false, _)  => (src.iter().map(|&v| v << left).collect(), esize, q),                                                       // VSHL
+            (0b0101, true, _)   => (src.iter().zip(&old).map(|(&v, &acc)| (acc & !(u64::MAX << left)) | v << left).collect(), esize, q),      // VSLI
+            (0b1000, false, false) if !l && dm & 1 == 0 => { // VSHRN: Qm lanes of 2 * esize narrowed into Dd
+                let src = split(self.neon_read(dm, true), 2 * esize, 128);
+                (src.iter().map(|&v| v >> right).collect(), esize, false)
+            },
+            (0b1010, _, false) if !l && dd & 1 == 0 => { // VSHLL, VMOVL: Dm lanes widened into Qd
+                let src = split(self.neon_read(dm, false), esize, 64);
+                (src.iter().map(|&v| (if u { v } else { sx(v, esize) as u64 }) << left).collect(), 2 * esize, true)
+            },
+            _ => return self.undefined(op),
+        };
+        self.neon_write(dd, q, join(&lanes, esize));
+    }
+
+    /// VEXT: extract a vector from the byte-wise concatenation `Vm:Vn`
+    fn neon_extract(&mut self, op: u32) {
+        let d           = ((op >> 22) & 0b1);
+        let vn          = ((op >> 16) & 0b1111);
+        let vd          = ((op >> 12) & 0b1111);
+        let imm4        = ((op >>  8) & 0b1111) as usize;
+        let n           = ((op >>  7) & 0b1);
+        let q           = ((op >>  6) & 0b1) == 1;
+        let m           = ((op >>  5) & 0b1);
+        let vm          = ((op >>  0) & 0b1111);
+
+        let (dd, dn, dm) = (Self::neon_reg(vd, d), Self::neon_reg(vn, n), Self::neon_reg(vm, m));
+        if !q && imm4 > 7 { return self.undefined(op) }
+        if q && (dd | dn | dm) & 1 != 0 { return self.undefined(op) }
+        let len         = if q { 16 } else { 8 };
+        let mut bytes   = self.neon_read(dn, q).to_le_bytes()[..len].to_vec();
+        bytes.extend_from_slice(&self.neon_read(dm, q).to_le_bytes()[..len]);
+        let mut result  = [0u8; 16];
+        result[..len].copy_from_slice(&bytes[imm4 ..][..len]);
+        self.neon_write(dd, q, u128::from_le_bytes(result));
+    }
+
+    /// Advanced SIMD two registers, miscellaneous
+    fn neon_two_misc(&mut self, op: u32) {
+        let d           = ((op >> 22) & 0b1);
+        let size        = ((op >> 18) & 0b11);
+        let a           = ((op >> 16) & 0b11);
+        let vd          = ((op >> 12) & 0b1111);
+        let b           = ((op >>  7) & 0b1111);
+        let q           = ((op >>  6) & 0b1) == 1;
+        let m           = ((op >>  5) & 0b1);
+        let vm          = ((op >>  0) & 0b1111);
+
+        let (dd, dm)    = (Self::neon_reg(vd, d), Self::neon_reg(vm, m));
+        if size == 0b11 || (q && (dd | dm) & 1 != 0) { return self.undefined(op) } // no two register miscellaneous op has 64-bit lanes
+        let esize       = 8 << size;
+        let vbits       = if q { 128 } else { 64 };
+        let value       = self.neon_read(dm, q);
+        let src         = split(value, esize, vbits);
+        let ones        = u64::MAX;
+        let float       = b & 0b1000 != 0;
+
+        let lanes: Vec<u64> = match (a, b) {
+            (0b00, 0b0000 ..= 0b0010) if esize < 64 >> b => { // VREV64, VREV32, VREV16: reverse esize lanes within each region
+                let region = 64 >> b;
+                let per = (region / esize) as usize;
+                src.chunks(per).flat_map(|c| c.iter().rev().copied()).collect()
+            },
+            (0b00, 0b0100 | 0b0101) => { // VPADDL: pairwise add into lanes twice as wide
+                let unsigned = b & 1 == 1;
+                let lanes: Vec<u64> = src.chunks(2).map(|p| if unsigned { p[0] + p[1] } else { (sx(p[0], esize) + sx(p[1], esize)) as u64 }).collect();
+                return self.neon_write(dd, q, join(&lanes, 2 * esize));
+            },
+            (0b00, 0b1000) => src.iter().map(|&v| u64::from((sx(v, esize) ^ (sx(v, esize) >> 63)).leading_zeros() - (64 - esize) - 1)).collect(), // VCLS
+            (0b00, 0b1001) => src.iter().map(|&v| u64::from(v.leading_zeros() - (64 - esize))).collect(), // VCLZ
+            (0b00, 0b1010) if size == 0 => src.iter().map(|&v| u64::from(v.count_ones())).collect(),     // VCNT
+            (0b00, 0b1011) if size == 0 => return self.neon_write(dd, q, !value),                           // VMVN
+            (0b01, 0b0101 | 0b1101) => return self.undefined(op),
+            (0b01, _) if float && size == 0b10 => src.iter().map(|&v| match b & 0b111 {
+                0b000 => if f(v) >  0.0 { ones } else { 0 },    // VCGT #0
+                0b001 => if f(v) >= 0.0 { ones } else { 0 },    // VCGE #0
+                0b010 => if f(v) == 0.0 { ones } else { 0 },    // VCEQ #0
+                0b011 => if f(v) <= 0.0 { ones } else { 0 },    // VCLE #0
+                0b100 => if f(v) <  0.0 { ones } else { 0 },    // VCLT #0
+                0b110 => v & 0x7FFF_FFFF,                       // VABS.F32
+                0b111 => v ^ 0x8000_0000,                       // VNEG.F32
+                _ => unreachable!(),
+            }).collect(),
+            (0b01, _) if !float => src.iter().map(|&v| { let s = sx(v, esize); match b {
+                0b000 => if s >  0 { ones } else { 0 },
+                0b001 => if s >= 0 { ones } else { 0 },
+                0b010 => if s == 0 { ones } else { 0 },
+                0b011 => if s <= 0 { ones } else { 0 },
+                0b100 => if s <  0 { ones } else { 0 },
+                0b110 => s.unsigned_abs(),                      // VABS
+                0b111 => s.wrapping_neg() as u64,               // VNEG
+                _ => unreachable!(),
+            }}).collect(),
+            (0b10, 0b0000) if size == 0 => { // VSWP
+                let old = self.neon_read(dd, q);
+                self.neon_write(dm, q, old);
+                return self.neon_write(dd, q, value);
+            },
+            (0b10, 0b0001 ..= 0b0011) if q || b == 0b0001 || size != 0b10 => { // VTRN, VUZP, VZIP (VUZP.32 & VZIP.32 of D registers would be VTRN.32)
+                let other = split(self.neon_read(dd, q), esize, vbits);
+                let len = src.len();
+                let (new_d, new_m): (Vec<u64>, Vec<u64>) = match b {
+                    0b0001 => { // VTRN: swap the odd lanes of Vd with the even lanes of Vm
+                        let (mut d, mut m) = (other.clone(), src.clone());
+                        for i in (0 .. len).step_by(2) { d[i + 1] = src[i]; m[i] = other[i + 1]; }
+                        (d, m)
+                    },
+                    0b0010 => { // VUZP: even lanes of Vd:Vm to Vd, odd lanes to Vm
+                        let both: Vec<u64> = other.iter().chain(&src).copied().collect();
+                        (both.iter().step_by(2).copied().collect(), both.iter().skip(1).step_by(2).copied().collect())
+                    },
+                    _ => { // VZIP: interleave Vd and Vm, low half to Vd, high half to Vm
+                        let zipped: Vec<u64> = other.iter().zip(&src).flat_map(|(&x, &y)| [x, y]).collect();
+                        (zipped[..len].to_vec(), zipped[len..].to_vec())
+                    },
+                };
+                self.neon_write(dm, q, join(&new_m, esize));
+                return self.neon_write(dd, q, join(&new_d, esize));
+            },
+            (0b10, 0b0100) if !q && dm & 1 == 0 => { // VMOVN: Qm lanes narrowed into Dd
+                let src = split(self.neon_read(dm, true), 2 * esize, 128);
+                return self.neon_write(dd, false, join(&src, esize));
+            },
+            _ => return self.undefined(op),
+        };
+        self.neon_write(dd, q, join(&lanes, esize));
+    }
+
+    /// VDUP (scalar)
+    fn neon_dup_scalar(&mut self, op: u32) {
+        let d           = ((op >> 22) & 0b1);
+        let imm4        = ((op >> 16) & 0b1111);
+        let vd          = ((op >> 12) & 0b1111);
+        let q           = ((op >>  6) & 0b1) == 1;
+        let m           = ((op >>  5) & 0b1);
+        let vm          = ((op >>  0) & 0b1111);
+
+        if imm4 & 0b111 == 0 || (q && vd & 1 != 0) { return self.undefined(op) } // Vd<0>
+        let esize       = 8 << imm4.trailing_zeros();
+        let index       = imm4 >> (imm4.trailing_zeros() + 1);
+        let value       = split(self.neon_read(Self::neon_reg(vm, m), false), esize, 64)[index as usize];
+        let lanes       = vec![value; (if q { 128 } else { 64 } / esize) as usize];
+        self.neon_write(Self::neon_reg(vd, d), q, join(&lanes, esize));
+    }
+
+    /// VMOV between an ARM core register and an 8 or 16-bit scalar, VDUP (ARM core register)
+    pub(super) fn neon_core_transfer(&mut self, op: u32) {
+        let opc1        = ((op >> 21) & 0b111); // U:opc1, or B:Q:0 for VDUP
+        let to_core     = ((op >> 20) & 0b1) == 1;
+        let vn          = ((op >> 16) & 0b1111);
+        let rt          = ((op >> 12) & 0b1111) as usize;
+        let n           = ((op >>  7) & 0b1);
+        let opc2        = ((op >>  5) & 0b11); // 0:E for VDUP
+
+        let dn          = Self::neon_reg(vn, n);
+        if opc1 & 0b100 != 0 && !to_core { // VDUP
+            let q = opc1 & 0b001 != 0;
+            if q && vn & 1 != 0 { return self.undefined(op) } // Vd<0>
+            let esize = match (opc1 & 0b010 != 0, opc2 & 0b01 != 0) { (false, false) => 32, (false, true) => 16, (true, _) => 8 };
+            let lanes = vec![u64::from(self.registers[rt]); (if q { 128 } else { 64 } / esize) as usize];
+            return self.neon_write(dn, q, join(&lanes, esize));
+        }
+
+        let (esize, index) = match (opc1 & 0b011, opc2) {
+            (0b10 | 0b11, _) => (8,  ((opc1 & 1) << 2) | opc2),
+            (_, 0b01 | 0b11) => (16, ((opc1 & 1) << 1) | (opc2 >> 1)),
+            _ => return self.undefined(op),
+        };
+        let mut lanes = split(u128::from(self.vfp.d[dn]), esize, 64);
+        match to_core {
+            true  => self.registers[rt] = if opc1 & 0b100 != 0 { lanes[index as usize] as u32 } else { sx(lanes[index as usize], esize) as u32 },
+            false => { lanes[index as usize] = u64::from(self.registers[rt]); self.vfp.d[dn] = join(&lanes, esize) as u64; },
+        }
+    }
+
+    /// Advanced SIMD element or structure load/store (VLDn, VSTn)
+    fn neon_load_store(&mut self, mem: &Memory, op: u32) {
+        let single      = ((op >> 23) & 0b1) == 1;
+        let d           = ((op >> 22) & 0b1);
+        let load        = ((op >> 21) & 0b1) == 1;
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let vd          = ((op >> 12) & 0b1111);
+        let kind        = ((op >>  8) & 0b1111);
+        let rm          = ((op >>  0) & 0b1111) as usize;
+
+        let dd          = Self::neon_reg(vd, d);
+        let base        = self.registers[rn];
+        let lane        = |cpu: &mut Self, reg: usize, index: u32, esize: u32, addr: u32| {
+            let bytes = (esize / 8) as usize;
+            let shift = index * esize;
+            let be = cpu.cpsr.e(); // elements are byte swapped in big endian data state
+            match load {
+                true => {
+                    let mut buf = [0u8; 8];
+                    cpu.watch(mem, addr, bytes as u32, MemoryFlags::READ, 0);
+                    cpu.data(mem.try_read_bytes(addr, MemoryFlags::READ, &mut buf[..bytes]));
+                    if be { buf[..bytes].reverse(); }
+                    let mask = (u64::MAX >> (64 - esize)) << shift;
+                    cpu.vfp.d[reg] = (cpu.vfp.d[reg] & !mask) | (u64::from_le_bytes(buf) << shift);
+                },
+                false => {
+                    let mut buf = (cpu.vfp.d[reg] >> shift).to_le_bytes();
+                    if be { buf[..bytes].reverse(); }
+                    let mut new = [0u8; 8];
+                    new[..bytes].copy_from_slice(&buf[..bytes]);
+                    cpu.watch(mem, addr, bytes as u32, MemoryFlags::WRITE, u64::from_le_bytes(new));
+                    cpu.data(mem.try_write_bytes(addr, MemoryFlags::WRITE, &buf[..bytes]));
+                },
+            }
+        };
+
+        let transferred = match (single, (op >> 10) & 0b11) {
+            (false, _) => { // multiple n-element structures
+                let esize = 8 << ((op >> 6) & 0b11);
+                let (n, regs, inc) = match kind {
+                    0b0111 => (1, 1, 1), 0b1010 => (1, 2, 1), 0b0110 => (1, 3, 1), 0b0010 => (1, 4, 1),
+                    0b1000 => (2, 1, 1), 0b1001 => (2, 1, 2), 0b0011 => (2, 2, 2),
+                    0b0100 => (3, 1, 1), 0b0101 => (3, 1, 2),
+                    0b0000 => (4, 1, 1), 0b0001 => (4, 1, 2),
+                    _ => return self.undefined(op),
+                };
+                let mut addr = base;
+                for r in 0 .. regs {
+                    for e in 0 .. 64 / esize {
+                        for k in 0 .. n {
+                            lane(self, (dd + r + k * inc) % 32, e, esize, addr);
+                            addr = addr.wrapping_add(esize / 8);
+                        }
+                    }
+                }
+                addr.wrapping_sub(base)
+            },
+            (true, 0b11) if load => { // single n-element structure to all lanes
+                let size = (op >> 6) & 0b11;
+                let esize = 8 << size;
+                let n = ((op >> 8) & 0b11) as usize + 1;
+                let t = ((op >> 5) & 0b1) as usize;
+                let (regs, inc) = if n == 1 { (1 + t, 1) } else { (1, 1 + t) };
+                for k in 0 .. n {
+                    let addr = base.wrapping_add((k as u32) * (esize / 8));
+                    for r in 0 .. regs {
+                        let reg = (dd + k * inc + r) % 32;
+                        lane(self, reg, 0, esize, addr);
+                        let value = self.vfp.d[reg] & (u64::MAX >> (64 - esize));
+                        self.vfp.d[reg] = join(&vec![value; (64 / esize) as usize], esize) as u64;
+                    }
+                }
+                (n as u32) * (esize / 8)
+            },
+            (true, size) => { // single n-element structure to one lane
+                let esize = 8 << size;
+                let n = ((op >> 8) & 0b11) as usize + 1;
+                let index_align = (op >> 4) & 0b1111;
+                let index = index_align >> (size + 1);
+                let inc = if (size == 1 && index_align & 0b10 != 0) || (size == 2 && index_align & 0b100 != 0) { 2 } else { 1 };
+                for k in 0 .. n {
+                    lane(self, (dd + k * inc) % 32, index, esize, base.wrapping_add((k as u32) * (esize / 8)));
+                }
+                (n as u32) * (esize / 8)
+            },
+        };
+
+        match rm {
+            15 => {},
+            13 => self.registers[rn] = base.wrapping_add(transferred),
+            rm => self.registers[rn] = base.wrapping_add(self.registers[rm]),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// References:
+// ARMv4? https://developer.arm.com/documentation/ddi0210/c/Programmer-s-Model/The-program-status-registers
+
+
+
+bitflags::bitflags! {
+    /// **P**rogram **S**tatus **R**egister (CPSR / SPSR)
+    #[repr(transparent)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Psr : u32 {
+        const N     = 1 << 31;      // negative / less than
+        const Z     = 1 << 30;      // zero
+        const C     = 1 << 29;      // carry / borrow / extend
+        const V     = 1 << 28;      // overflow
+        const Q     = 1 << 27;      // sticky overflow (ARMv5TE+)
+        const J     = 1 << 24;      // Jazelle state
+        const IT    = 0x0600_FC00;  // Thumb IT block state (ARMv6T2+): IT[1:0] in bits 26:25, IT[7:2] in bits 15:10
+        const GE    = 0xF << 16;    // SIMD greater than or equal (ARMv6+)
+        const E     = 1 << 9;       // big endian data (ARMv6+)
+        const A     = 1 << 8;       // asynchronous abort disable
+        const I     = 1 << 7;       // IRQ disable
+        const F     = 1 << 6;       // FIQ disable
+        const T     = 1 << 5;       // Thumb state
+        const MODE  = 0x1F;
+
+        /// N, Z, C, V, Q - the fields MSR can write from user mode
+        const FLAGS = 0xF8 << 24;
+    }
+}
+
+impl Default for Psr {
+    fn default() -> Self { Self::from_bits_truncate(0b10000) } // user mode, everything else clear
+}
+
+impl Psr {
+    pub fn n(self) -> bool { self.contains(Self::N) }
+    pub fn z(self) -> bool { self.contains(Self::Z) }
+    pub fn c(self) -> bool { self.contains(Self::C) }
+    pub fn v(self) -> bool { self.contains(Self::V) }
+    pub fn q(self) -> bool { self.contains(Self::Q) }
+    pub fn j(self) -> bool { self.contains(Self::J) }
+    pub fn e(self) -> bool { self.contains(Self::E) }
+    pub fn a(self) -> bool { self.contains(Self::A) }
+    pub fn i(self) -> bool { self.contains(Self::I) }
+    pub fn f(self) -> bool { self.contains(Self::F) }
+    pub fn t(self) -> bool { self.contains(Self::T) }
+
+    /// The processor mode, or [`None`] if the mode bits hold a reserved encoding
+    pub fn mode(self) -> Option<Mode> { Mode::from_bits((self & Self::MODE).bits()) }
+    pub fn set_mode(&mut self, mode: Mode) { *self = (*self - Self::MODE) | Self::from_bits_truncate(mode.bits()); }
+    pub fn ge(self) -> u32 { (self & Self::GE).bits() >> 16 }
+    pub fn set_ge(&mut self, ge: u32) { *self = (*self - Self::GE) | Self::from_bits_truncate((ge & 0xF) << 16); }
+    pub fn it(self) -> u8 { (((self.bits() >> 25) & 0b11) | ((self.bits() >> 8) & 0xFC)) as u8 }
+    pub fn set_it(&mut self, it: u8) { let it = u32::from(it); *self = (*self - Self::IT) | Self::from_bits_truncate(((it & 0b11) << 25) | ((it & 0xFC) << 8)); }
+}
+
+/// Processor modes, as encoded by [`Psr::MODE`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    User        = 0b10000,
+    Fiq         = 0b10001,
+    Irq         = 0b10010,
+    Supervisor  = 0b10011,
+    Abort       = 0b10111,
+    Undefined   = 0b11011,
+    System      = 0b11111, // ARMv4+: privileged, but sharing User's registers
+}
+
+impl Mode {
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            0b10000 => Some(Mode::User),
+            0b10001 => Some(Mode::Fiq),
+            0b10010 => Some(Mode::Irq),
+            0b10011 => Some(Mode::Supervisor),
+            0b10111 => Some(Mode::Abort),
+            0b11011 => Some(Mode::Undefined),
+            0b11111 => Some(Mode::System),
+            _       => None,
+        }
+    }
+
+    pub fn bits(self) -> u32 { self as u32 }
+    pub fn is_privileged(self) -> bool { self != Mode::User }
+
+    /// Index into the banked SPSRs / registers of the exception modes, or [`None`] for User and System (which have no SPSR)
+    pub(super) fn bank(self) -> Option<usize> {
+        match self {
+            Mode::User | Mode::System => None,
+            Mode::Fiq           => Some(0),
+            Mode::Irq           => Some(1),
+            Mode::Supervisor    => Some(2),
+            Mode::Abort         => Some(3),
+            Mode::Undefined     => Some(4),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+use std::fmt::{self, Display, Formatter};
+
+
+
+/// A register of the current mode, for [`Cpu::get`] & [`Cpu::set`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Reg {
+    R0, R1, R2, R3, R4, R5, R6, R7, R8, R9, R10, R11, R12,
+    /// r13: the stack pointer
+    SP,
+    /// r14: the link register
+    LR,
+    /// r15: the program counter - as the address of the next instruction to execute, without any pipeline offset
+    PC,
+    /// The current program status register: setting it switches register banks, like [`Cpu::set_cpsr`]
+    Cpsr,
+}
+
+impl Reg {
+    pub const R13 : Reg = Reg::SP;
+    pub const R14 : Reg = Reg::LR;
+    pub const R15 : Reg = Reg::PC;
+
+    /// r0 ..= r15, in order (see [`Cpu::regs`])
+    pub const ALL : [Reg; 16] = [
+        Reg::R0, Reg::R1, Reg::R2,  Reg::R3,  Reg::R4,  Reg::R5, Reg::R6, Reg::R7,
+        Reg::R8, Reg::R9, Reg::R10, Reg::R11, Reg::R12, Reg::SP, Reg::LR, Reg::PC,
+    ];
+
+    /// r`index`, or [`None`] if `index` isn't 0 ..= 15
+    pub fn from_index(index: usize) -> Option<Reg> { Self::ALL.get(index).copied() }
+
+    /// The register's number (0 ..= 15, the same as [`Cpu::registers`]' index), or [`None`] for [`Reg::Cpsr`]
+    pub fn index(self) -> Option<usize> { if self == Reg::Cpsr { None } else { Some(self as usize) } }
+}
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Reg::SP     => write!(f, "sp"),
+            Reg::LR     => write!(f, "lr"),
+            Reg::PC     => write!(f, "pc"),
+            Reg::Cpsr   => write!(f, "cpsr"),
+            r           => write!(f, "r{}", *r as usize),
+        }
+    }
+}
+
+impl Cpu {
+    /// Read `reg` of the current mode.  [`Reg::PC`] reads as [`Cpu::next_instruction_addr`], not [`Cpu::registers`]' pipelined `+ 8` / `+ 4`.
+    pub fn get(&self, reg: Reg) -> u32 {
+        match reg {
+            Reg::PC     => self.next_instruction_addr(),
+            Reg::Cpsr   => self.cpsr.bits(),
+            r           => self.registers[r as usize],
+        }
+    }
+
+    /// Write `reg` of the current mode.  [`Reg::PC`] writes [`Cpu::set_next_instruction_addr`], and [`Reg::Cpsr`] keeps executing from the same address,
+    /// even if it switches between ARM & Thumb state.
+    pub fn set(&mut self, reg: Reg, value: u32) {
+        match reg {
+            Reg::PC     => self.set_next_instruction_addr(value),
+            Reg::Cpsr   => {
+                let pc = self.next_instruction_addr();
+                self.set_cpsr(Psr::from_bits_truncate(value));
+                self.set_next_instruction_addr(pc);
+            },
+            r           => self.registers[r as usize] = value,
+        }
+    }
+
+    /// r0 ..= r15 of the current mode and their values, as read by [`Cpu::get`]
+    pub fn regs(&self) -> impl Iterator<Item = (Reg, u32)> + '_ { Reg::ALL.into_iter().map(move |reg| (reg, self.get(reg))) }
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+// References:
+// ARMv4? https://developer.arm.com/documentation/ddi0210/c/Instruction-Cycle-Timings (what Fuel::Cycles counts, see timing.rs)
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (B1.8 Exception handling: the exceptions a run stops at, as StepOutcomes, instead of taking them)
+//
+// Limitations:
+// - Fuel is only checked between instructions: a run can't stop partway through an LDM, STM, or other multi-cycle instruction
+
+
+
+/// How much [`Cpu::run`] may execute before returning
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fuel {
+    /// At most this many instructions (see [`Cpu::instructions`])
+    Instructions(u64),
+    /// At most this many (approximate) cycles (see [`Cpu::cycles`]) - the last instruction may overshoot
+    Cycles(u64),
+}
+
+/// What happened during a [`Cpu::run`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunOutcome {
+    /// How many instructions were stepped past (see [`Cpu::instructions`])
+    pub retired: u64,
+    /// Why the run stopped: [`StepOutcome::Continue`] if it ran out of fuel, otherwise the first step outcome that wasn't
+    pub stop: StepOutcome,
+}
+
+impl Cpu {
+    /// [`Cpu::step1`] until `fuel` runs out, or a step's outcome is anything but [`StepOutcome::Continue`] (e.g. an SVC for the host to handle.)
+    ///
+    /// Running out of fuel leaves the CPU between instructions, so another `run` picks up where this one left off -
+    /// handy for scheduling several VMs on one thread, or reining in a runaway guest.
+    pub fn run(&mut self, mem: &Memory, fuel: Fuel) -> RunOutcome {
+        let (start_instructions, start_cycles) = (self.instructions, self.cycles);
+        loop {
+            let fueled = match fuel {
+                Fuel::Instructions(n)   => self.instructions - start_instructions < n,
+                Fuel::Cycles(n)         => self.cycles - start_cycles < n,
+            };
+            let stop = if fueled { self.step1(mem) } else { StepOutcome::Continue };
+            if !fueled || stop != StepOutcome::Continue { return RunOutcome { retired: self.instructions - start_instructions, stop } }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use super::*;
+
+use bytemuck::allocation::zeroed_slice_box;
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Limitations:
+// - Windows are mapped as MMIO (see mmio.rs): whole pages, with guest accesses dispatched through MmioDevice
+// - Bytes past the end of the buffer (in the window's last page) read as zeros, and ignore writes
+
+
+
+/// Host owned memory for [`Memory::map_shared`]: the host and guest both access the same bytes, with no copying through guest pages.
+#[derive(Debug)]
+pub struct SharedBuffer(Bytes);
+
+#[derive(Debug)]
+enum Bytes {
+    ReadWrite(Box<[AtomicU8]>),
+    ReadOnly(Arc<[u8]>), // guest writes are ignored
+}
+
+impl SharedBuffer {
+    /// `len` zeroed bytes the guest can read & write
+    pub fn new(len: usize) -> Self { Self(Bytes::ReadWrite(zeroed_slice_box(len))) }
+
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Bytes::ReadWrite(bytes) => bytes.len(),
+            Bytes::ReadOnly(bytes)  => bytes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Read `bytes` at `offset` into the buffer.  Panics if out of bounds.
+    pub fn read_at(&self, offset: usize, bytes: &mut [u8]) {
+        let len = bytes.len();
+        match &self.0 {
+            Bytes::ReadWrite(src)   => for (dst, src) in bytes.iter_mut().zip(&src[offset..][..len]) { *dst = src.load(Ordering::Relaxed) },
+            Bytes::ReadOnly(src)    => bytes.copy_from_slice(&src[offset..][..len]),
+        }
+    }
+
+    /// Write `bytes` at `offset` into the buffer.  Panics if out of bounds, or if the buffer was created from read only bytes.
+    pub fn write_at(&self, offset: usize, bytes: &[u8]) {
+        match &self.0 {
+            Bytes::ReadWrite(dst)   => for (dst, src) in dst[offset..][..bytes.len()].iter().zip(bytes) { dst.store(*src, Ordering::Relaxed) },
+            Bytes::ReadOnly(_)      => panic!("arm::SharedBuffer::write_at: buffer is read only"),
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = vec![0; self.len()];
+        self.read_at(0, &mut bytes);
+        bytes
+    }
+}
+
+impl From<&[u8]> for SharedBuffer {
+    /// A copy of `bytes` the guest can read & write
+    fn from(bytes: &[u8]) -> Self {
+        let buffer = Self::new(bytes.len());
+        buffer.write_at(0, bytes);
+        buffer
+    }
+}
+
+impl From<Arc<[u8]>> for SharedBuffer {
+    /// `bytes` without copying them - read only, since they're shared
+    fn from(bytes: Arc<[u8]>) -> Self { Self(Bytes::ReadOnly(bytes)) }
+}
+
+impl MmioDevice for SharedBuffer {
+    fn read(&self, offset: u32, bytes: &mut [u8]) {
+        let offset = offset as usize;
+        let n = self.len().saturating_sub(offset).min(bytes.len());
+        if n > 0 { self.read_at(offset, &mut bytes[..n]); }
+        bytes[n..].fill(0);
+    }
+
+    fn write(&self, offset: u32, bytes: &[u8]) {
+        let offset = offset as usize;
+        let n = self.len().saturating_sub(offset).min(bytes.len());
+        if n > 0 && matches!(self.0, Bytes::ReadWrite(_)) { self.write_at(offset, &bytes[..n]); }
+    }
+}
+
+impl Memory {
+    /// Map `buffer` at `range.start` with `flags`, over every page overlapping `range` - replacing whatever was mapped there.
+    /// The host keeps using `buffer` directly (e.g. to fill in inputs or collect outputs) while the guest accesses it as memory.
+    pub fn map_shared(&self, range: Range<u32>, flags: MemoryFlags, buffer: Arc<SharedBuffer>) { self.map_mmio(range, flags, buffer) }
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+use std::ops::ControlFlow;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (the MPIDR, Multiprocessor Affinity Register, description)
+//
+// Limitations:
+// - Every CPU is a separate Cpu: IRQs, FIQs & events are only raised on the thread that owns it (e.g. from run_threads' handler)
+// - SEV only signals the executing CPU, so other CPUs' WFEs rely on waking spuriously (which run_threads' handler can do by resuming)
+// - Exclusive monitors are per CPU, and only notice other CPUs' stores that change the tagged value (see Cpu::impl_exclusive_transfer)
+// - No SCU, GIC, or cache coherency to model: Memory is always coherent, and accesses are sequentially consistent
+
+
+
+impl Cpu {
+    /// `count` CPUs to share one [`Memory`], with MPIDRs (see [`CpuId::mpidr`]) numbering them 0, 1, 2 ... in affinity level 0.
+    /// ARMv6K+ guests typically read MPIDR to pick the boot CPU and park the rest.
+    pub fn cluster(config: CpuConfig, count: usize) -> Vec<Cpu> {
+        assert!(count <= 256, "Cpu::cluster: MPIDR.Aff0 can only number 256 CPUs");
+        (0 .. count).map(|index| {
+            let mut cpu = Cpu::with_config(config);
+            cpu.cp15.id.mpidr = 0x8000_0000 | index as u32; // multiprocessing extensions format, part of a multiprocessor system
+            cpu
+        }).collect()
+    }
+
+    /// The CPU's number within a [`Cpu::cluster`] (MPIDR.Aff0)
+    pub fn cpu_index(&self) -> usize { (self.cp15.id.mpidr & 0xFF) as usize }
+}
+
+/// Run each of `cpus` on its own thread, all sharing `mem`, until every one of them has stopped.
+///
+/// Each thread repeatedly [`Cpu::run`]s its CPU for `fuel`, then hands the [`RunOutcome::stop`] to `handle` -
+/// [`StepOutcome::Continue`] when the fuel ran out, giving `handle` a chance to raise interrupts or notice a shutdown.
+/// Each CPU stops once `handle` returns [`ControlFlow::Break`] for it, and the `Break` values are returned in `cpus` order.
+pub fn run_threads<B, H>(cpus: &mut [Cpu], mem: &Memory, fuel: Fuel, handle: H) -> Vec<B>
+where
+    B: Send,
+    H: Fn(&mut Cpu, &Memory, StepOutcome) -> ControlFlow<B> + Sync,
+{
+    let handle = &handle;
+    std::thread::scope(|scope| {
+        let threads = cpus.iter_mut().map(|cpu| scope.spawn(move || loop {
+            let outcome = cpu.run(mem, fuel);
+            if let ControlFlow::Break(b) = handle(cpu, mem, outcome.stop) { return b }
+        })).collect::<Vec<_>>();
+        threads.into_iter().map(|t| t.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect()
+    })
+}
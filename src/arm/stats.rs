@@ -0,0 +1,90 @@
+use super::*;
+
+use std::fmt::{self, Display, Formatter};
+use std::mem::size_of;
+use std::sync::{Arc, Mutex};
+
+
+
+/// How much of a [`Memory`] is in use, from [`Memory::stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryStats {
+    /// Pages (of [`Memory::page_size`]) mapped, with data, or routed to a device
+    pub mapped_pages:       usize,
+    /// Pages with data of their own, rather than reading as zeros
+    pub resident_pages:     usize,
+    /// Approximate host memory allocated for page data & page tables.  Data shared with forks & snapshots counts towards each.
+    pub allocated_bytes:    usize,
+}
+
+/// A run of contiguous pages with the same flags & label, from [`Memory::regions`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryRegion {
+    pub start:  u32,
+    /// One past the last byte (`1 << 32` if the region reaches the end of the address space)
+    pub end:    u64,
+    pub flags:  MemoryFlags,
+    /// The region is routed to an [`MmioDevice`]
+    pub mmio:   bool,
+    /// See [`Memory::label`]
+    pub label:  Option<Arc<str>>,
+}
+
+impl Memory {
+    pub fn stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats::default();
+        self.for_each_mapped_page(|_, page| {
+            stats.mapped_pages += 1;
+            if page.data.is_some() { stats.resident_pages += 1 }
+        });
+        stats.allocated_bytes = match self.flat {
+            Some(_) => self.pages() + stats.resident_pages * self.page_size() as usize, // page states + (roughly) the host pages committed by touching them
+            None    => self.populated_tables() * TABLE_PAGES * size_of::<Mutex<Page>>() + stats.resident_pages * size_of::<[u64; 512]>(),
+        };
+        stats
+    }
+
+    /// Mapped memory as contiguous regions of the same flags & label, in ascending order (e.g. for a `/proc/self/maps` style summary - see [`MemoryRegion`]'s [`Display`])
+    pub fn regions(&self) -> Vec<MemoryRegion> {
+        let mut regions = Vec::<MemoryRegion>::new();
+        self.for_each_mapped_page(|page_idx, page| {
+            let start = (page_idx as u64) << self.page_size().trailing_zeros();
+            let end = start + u64::from(self.page_size());
+            let mmio = page.mmio.is_some();
+            match regions.last_mut() {
+                Some(last) if last.end == start && last.flags == page.flags && last.mmio == mmio => last.end = end,
+                _ => regions.push(MemoryRegion { start: start as u32, end, flags: page.flags, mmio, label: None }),
+            }
+        });
+
+        let labels = self.labels();
+        if labels.is_empty() { return regions }
+        let mut labeled = Vec::new();
+        for region in regions {
+            let mut start = u64::from(region.start);
+            while start < region.end {
+                let label = labels.iter().find(|(range, _)| u64::from(range.start) <= start && start < u64::from(range.end));
+                let end = match label {
+                    Some((range, _))    => u64::from(range.end),
+                    None                => labels.iter().map(|(range, _)| u64::from(range.start)).find(|s| *s > start).unwrap_or(region.end),
+                }.min(region.end);
+                labeled.push(MemoryRegion { start: start as u32, end, label: label.map(|(_, label)| label.clone()), ..region.clone() });
+                start = end;
+            }
+        }
+        labeled
+    }
+}
+
+impl Display for MemoryRegion {
+    /// e.g. `00008000-0000a000 r-x`, followed by ` mmio` for devices, and the label if any
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let flag = |flag, c| if self.flags.contains(flag) { c } else { '-' };
+        write!(f, "{:08x}-{:08x} {}{}{}", self.start, self.end, flag(MemoryFlags::READ, 'r'), flag(MemoryFlags::WRITE, 'w'), flag(MemoryFlags::EXECUTE, 'x'))?;
+        if self.mmio { write!(f, " mmio")?; }
+        if let Some(label) = &self.label { write!(f, " {}", label)?; }
+        Ok(())
+    }
+}
@@ -0,0 +1,405 @@
+#![allow(unused_parens)]
+
+use super::*;
+
+// References:
+// ARMv4T: the ARM7TDMI documentation's "THUMB Instruction Set" chapter, whose numbered formats the "Format N" comments refer to
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (A6.2 16-bit Thumb instruction encoding) for the newer encodings
+
+
+
+impl Cpu {
+    pub(super) fn step1_thumb(&mut self, mem: &Memory) -> StepOutcome {
+        let pc = self.next_instruction_addr();
+        let op = match self.tlb.get_mut().fetch_u16(mem, pc) {
+            Ok(op)      => u32::from(op),
+            Err(fault)  => return StepOutcome::PrefetchAbort { pc, fault },
+        };
+
+        // IT blocks: the condition comes from ITSTATE instead of the instruction, and most flag setting is suppressed
+        let in_it_block = (self.itstate & 0xF) != 0;
+        let cond = if in_it_block { u32::from(self.itstate >> 4) } else { 0b1110 };
+        if in_it_block { self.itstate = if (self.itstate & 0b111) == 0 { 0 } else { (self.itstate & 0xE0) | ((self.itstate << 1) & 0x1F) }; }
+        let setcc = !in_it_block;
+
+        let mut outcome = StepOutcome::Continue;
+        let size = match op >> 11 { 0b11101 ..= 0b11111 => 4, _ => 2 }; // skipped 32-bit encodings skip both halfwords
+        if self.condition_passed(cond) {
+            self.cycles += u64::from(self.thumb_cycles(op));
+            match op >> 11 {
+                0b00000 ..= 0b00010 => self.thumb_move_shifted_register(op, setcc),
+                0b00011             => self.thumb_add_subtract(op, setcc),
+                0b00100 ..= 0b00111 => self.thumb_immediate(op, setcc),
+                0b01000 if (op >> 10) & 1 == 0 => self.thumb_alu(op, setcc),
+                0b01000             => self.thumb_hi_register(op),
+                0b01001             => self.thumb_pc_relative_load(mem, op),
+                0b01010 ..= 0b01011 => self.thumb_register_offset(mem, op),
+                0b01100 ..= 0b01111 => self.thumb_immediate_offset(mem, op),
+                0b10000 ..= 0b10001 => self.thumb_halfword_immediate_offset(mem, op),
+                0b10010 ..= 0b10011 => self.thumb_sp_relative(mem, op),
+                0b10100 ..= 0b10101 => self.thumb_load_address(op),
+                0b10110 ..= 0b10111 => outcome = self.thumb_misc(mem, op),
+                0b11000 ..= 0b11001 => self.thumb_multiple(mem, op),
+                0b11010 ..= 0b11011 => outcome = self.thumb_conditional_branch(op),
+                0b11100             => self.thumb_branch(op),
+                0b11110             => {
+                    let suffix = match self.tlb.get_mut().fetch_u16(mem, pc.wrapping_add(2)) {
+                        Ok(suffix)  => u32::from(suffix),
+                        Err(fault)  => return StepOutcome::PrefetchAbort { pc, fault },
+                    };
+                    self.thumb_long_branch_with_link(pc, op, suffix);
+                },
+                _b111x1             => self.undefined(op << 16), // 32-bit Thumb-2 instructions: not yet implemented
+            }
+        }
+
+        self.end_step(pc, size, outcome)
+    }
+
+    fn set_nz(&mut self, value: u32) {
+        self.cpsr.set(Psr::N, (value >> 31) == 1);
+        self.cpsr.set(Psr::Z, value == 0);
+    }
+
+    fn set_nzcv(&mut self, (value, carry, overflow): (u32, bool, bool)) -> u32 {
+        self.set_nz(value);
+        self.cpsr.set(Psr::C, carry);
+        self.cpsr.set(Psr::V, overflow);
+        value
+    }
+
+    /// Write `value` to `rd`, treating R15 as a (non-interworking) branch
+    fn thumb_write(&mut self, rd: usize, value: u32) {
+        match rd {
+            15 => self.branch_to(value & !1),
+            rd => self.registers[rd] = value,
+        }
+    }
+
+    fn load_word(&self, mem: &Memory, addr: u32) -> u32 {
+        self.read_data_u32(mem, addr & !3).rotate_right(8 * (addr & 3)) // rotates like ARM LDR
+    }
+
+    /// Format 1: move shifted register (LSL, LSR, ASR)
+    fn thumb_move_shifted_register(&mut self, op: u32, setcc: bool) {
+        let shift_type  = ((op >> 11) & 0b11);
+        let offset      = ((op >>  6) & 0b11111);
+        let rs          = ((op >>  3) & 0b111) as usize;
+        let rd          = ((op >>  0) & 0b111) as usize;
+
+        let amount      = if offset == 0 && shift_type != 0b00 { 32 } else { offset }; // LSR/ASR #0 encode #32
+        let (value, carry) = self.shift(self.registers[rs], shift_type, amount);
+        self.registers[rd] = value;
+        if setcc { self.set_nz(value); self.cpsr.set(Psr::C, carry); }
+    }
+
+    /// Format 2: add/subtract
+    fn thumb_add_subtract(&mut self, op: u32, setcc: bool) {
+        let immediate   = ((op >> 10) & 0b1) == 1;
+        let subtract    = ((op >>  9) & 0b1) == 1;
+        let rn          = ((op >>  6) & 0b111);
+        let rs          = ((op >>  3) & 0b111) as usize;
+        let rd          = ((op >>  0) & 0b111) as usize;
+
+        let op1         = self.registers[rs];
+        let op2         = if immediate { rn } else { self.registers[rn as usize] };
+        let result      = match subtract {
+            false => Self::add_with_carry(op1, op2, false),
+            true  => Self::add_with_carry(op1, !op2, true),
+        };
+        self.registers[rd] = if setcc { self.set_nzcv(result) } else { result.0 };
+    }
+
+    /// Format 3: move/compare/add/subtract immediate
+    fn thumb_immediate(&mut self, op: u32, setcc: bool) {
+        let opcode      = ((op >> 11) & 0b11);
+        let rd          = ((op >>  8) & 0b111) as usize;
+        let imm         = ((op >>  0) & 0xFF);
+
+        let rdv         = self.registers[rd];
+        match opcode {
+            0b00 => { self.registers[rd] = imm; if setcc { self.set_nz(imm); } }, // MOV
+            0b01 => { self.set_nzcv(Self::add_with_carry(rdv, !imm, true)); }, // CMP
+            0b10 => { let r = Self::add_with_carry(rdv, imm, false); self.registers[rd] = if setcc { self.set_nzcv(r) } else { r.0 }; }, // ADD
+            _b11 => { let r = Self::add_with_carry(rdv, !imm, true); self.registers[rd] = if setcc { self.set_nzcv(r) } else { r.0 }; }, // SUB
+        }
+    }
+
+    /// Format 4: ALU operations
+    fn thumb_alu(&mut self, op: u32, setcc: bool) {
+        let opcode      = ((op >> 6) & 0b1111);
+        let rs          = ((op >> 3) & 0b111) as usize;
+        let rd          = ((op >> 0) & 0b111) as usize;
+        let (d, s)      = (self.registers[rd], self.registers[rs]);
+        let c           = self.cpsr.c();
+
+        enum R { Logical(u32), Shift((u32, bool)), Arith((u32, bool, bool)) }
+        let (result, write) = match opcode {
+            0b0000 => (R::Logical(d & s),                               true),  // AND
+            0b0001 => (R::Logical(d ^ s),                               true),  // EOR
+            0b0010 => (R::Shift(self.shift(d, 0b00, s & 0xFF)),         true),  // LSL
+            0b0011 => (R::Shift(self.shift(d, 0b01, s & 0xFF)),         true),  // LSR
+            0b0100 => (R::Shift(self.shift(d, 0b10, s & 0xFF)),         true),  // ASR
+            0b0101 => (R::Arith(Self::add_with_carry(d, s, c)),         true),  // ADC
+            0b0110 => (R::Arith(Self::add_with_carry(d, !s, c)),        true),  // SBC
+            0b0111 => (R::Shift(self.shift(d, 0b11, s & 0xFF)),         true),  // ROR
+            0b1000 => (R::Logical(d & s),                               false), // TST
+            0b1001 => (R::Arith(Self::add_with_carry(0, !s, true)),     true),  // NEG
+            0b1010 => (R::Arith(Self::add_with_carry(d, !s, true)),     false), // CMP
+            0b1011 => (R::Arith(Self::add_with_carry(d, s, false)),     false), // CMN
+            0b1100 => (R::Logical(d | s),                               true),  // ORR
+            0b1101 => (R::Logical(d.wrapping_mul(s)),                   true),  // MUL (C unaffected on ARMv5+, "meaningless" on ARMv4T)
+            0b1110 => (R::Logical(d & !s),                              true),  // BIC
+            _b1111 => (R::Logical(!s),                                  true),  // MVN
+        };
+
+        let setcc = setcc || !write; // TST, CMP, CMN always set flags, even in IT blocks
+        let value = match result {
+            R::Logical(value)           => { if setcc { self.set_nz(value); } value },
+            R::Shift((value, carry))    => { if setcc { self.set_nz(value); self.cpsr.set(Psr::C, carry); } value },
+            R::Arith(result)            => if setcc { self.set_nzcv(result) } else { result.0 },
+        };
+        if write { self.registers[rd] = value; }
+    }
+
+    /// Format 5: Hi register operations/branch exchange (ADD, CMP, MOV, BX, BLX)
+    fn thumb_hi_register(&mut self, op: u32) {
+        let opcode      = ((op >> 8) & 0b11);
+        let h1          = ((op >> 7) & 0b1) as usize;
+        let rs          = ((op >> 3) & 0b1111) as usize; // includes H2
+        let rd          = ((op >> 0) & 0b111) as usize | (h1 << 3);
+        let s           = self.registers[rs];
+
+        match opcode {
+            0b00 => { let value = self.registers[rd].wrapping_add(s); self.thumb_write(rd, value); }, // ADD
+            0b01 => { self.set_nzcv(Self::add_with_carry(self.registers[rd], !s, true)); }, // CMP
+            0b10 => self.thumb_write(rd, s), // MOV
+            _b11 => {
+                if h1 == 1 && !self.require(Arch::V5TE, op) { return }
+                if h1 == 1 { self.registers[14] = self.registers[15].wrapping_sub(2) | 1; } // BLX (ARMv5T+): return to the next instruction, in Thumb state
+                self.branch_exchange(s); // BX
+            },
+        }
+    }
+
+    /// Format 6: PC-relative load
+    fn thumb_pc_relative_load(&mut self, mem: &Memory, op: u32) {
+        let rd          = ((op >> 8) & 0b111) as usize;
+        let imm         = ((op >> 0) & 0xFF) << 2;
+        self.registers[rd] = self.load_word(mem, (self.registers[15] & !3).wrapping_add(imm));
+    }
+
+    /// Format 7: load/store with register offset, and Format 8: load/store sign-extended byte/halfword
+    fn thumb_register_offset(&mut self, mem: &Memory, op: u32) {
+        let opcode      = ((op >> 9) & 0b111);
+        let ro          = ((op >> 6) & 0b111) as usize;
+        let rb          = ((op >> 3) & 0b111) as usize;
+        let rd          = ((op >> 0) & 0b111) as usize;
+        let addr        = self.registers[rb].wrapping_add(self.registers[ro]);
+
+        match opcode {
+            0b000 => self.store_u32(mem, addr, self.registers[rd]),                           // STR
+            0b001 => self.store_u16(mem, addr, self.registers[rd] as u16),                    // STRH
+            0b010 => self.write_data_u8(mem, addr, self.registers[rd] as u8),                 // STRB
+            0b011 => self.registers[rd] = self.read_data_u8(mem, addr) as i8 as u32,          // LDRSB
+            0b100 => self.registers[rd] = self.load_u32(mem, addr),                           // LDR
+            0b101 => self.registers[rd] = self.load_u16(mem, addr).into(),                    // LDRH
+            0b110 => self.registers[rd] = self.read_data_u8(mem, addr).into(),                // LDRB
+            _b111 => self.registers[rd] = self.load_u16(mem, addr) as i16 as u32,             // LDRSH
+        }
+    }
+
+    /// Format 9: load/store with immediate offset
+    fn thumb_immediate_offset(&mut self, mem: &Memory, op: u32) {
+        let byte        = ((op >> 12) & 0b1) == 1;
+        let load        = ((op >> 11) & 0b1) == 1;
+        let offset      = ((op >>  6) & 0b11111);
+        let rb          = ((op >>  3) & 0b111) as usize;
+        let rd          = ((op >>  0) & 0b111) as usize;
+        let base        = self.registers[rb];
+
+        match (load, byte) {
+            (false, false)  => self.store_u32(mem, base.wrapping_add(offset << 2), self.registers[rd]),
+            (false, true)   => self.write_data_u8(mem, base.wrapping_add(offset), self.registers[rd] as u8),
+            (true,  false)  => self.registers[rd] = self.load_u32(mem, base.wrapping_add(offset << 2)),
+            (true,  true)   => self.registers[rd] = self.read_data_u8(mem, base.wrapping_add(offset)).into(),
+        }
+    }
+
+    /// Format 10: load/store halfword
+    fn thumb_halfword_immediate_offset(&mut self, mem: &Memory, op: u32) {
+        let load        = ((op >> 11) & 0b1) == 1;
+        let offset      = ((op >>  6) & 0b11111) << 1;
+        let rb          = ((op >>  3) & 0b111) as usize;
+        let rd          = ((op >>  0) & 0b111) as usize;
+        let addr        = self.registers[rb].wrapping_add(offset);
+
+        match load {
+            false => self.store_u16(mem, addr, self.registers[rd] as u16),
+            true  => self.registers[rd] = self.load_u16(mem, addr).into(),
+        }
+    }
+
+    /// Format 11: SP-relative load/store
+    fn thumb_sp_relative(&mut self, mem: &Memory, op: u32) {
+        let load        = ((op >> 11) & 0b1) == 1;
+        let rd          = ((op >>  8) & 0b111) as usize;
+        let addr        = self.registers[13].wrapping_add((op & 0xFF) << 2);
+
+        match load {
+            false => self.store_u32(mem, addr, self.registers[rd]),
+            true  => self.registers[rd] = self.load_u32(mem, addr),
+        }
+    }
+
+    /// Format 12: load address
+    fn thumb_load_address(&mut self, op: u32) {
+        let sp          = ((op >> 11) & 0b1) == 1;
+        let rd          = ((op >>  8) & 0b111) as usize;
+        let base        = if sp { self.registers[13] } else { self.registers[15] & !3 };
+        self.registers[rd] = base.wrapping_add((op & 0xFF) << 2);
+    }
+
+    /// Format 13: add offset to stack pointer, Format 14: push/pop registers, and the ARMv5T+ miscellaneous 16-bit instructions
+    fn thumb_misc(&mut self, mem: &Memory, op: u32) -> StepOutcome {
+        match (op >> 8) & 0b1111 {
+            0b0010 | 0b0110 | 0b1010 if self.config.arch < Arch::V6 => self.undefined(op),
+            0b0001 | 0b0011 | 0b1001 | 0b1011 | 0b1111 if self.config.arch < Arch::V7A => self.undefined(op), // ARMv6T2
+            0b0000 => { // Format 13: ADD/SUB SP, #imm
+                let offset = (op & 0x7F) << 2;
+                self.registers[13] = match (op >> 7) & 1 == 1 {
+                    false => self.registers[13].wrapping_add(offset),
+                    true  => self.registers[13].wrapping_sub(offset),
+                };
+            },
+            0b0010 => { // SXTH, SXTB, UXTH, UXTB (ARMv6)
+                let rm = self.registers[((op >> 3) & 0b111) as usize];
+                self.registers[(op & 0b111) as usize] = match (op >> 6) & 0b11 {
+                    0b00 => rm as i16 as u32,
+                    0b01 => rm as i8 as u32,
+                    0b10 => rm as u16 as u32,
+                    _b11 => rm as u8 as u32,
+                };
+            },
+            0b0001 | 0b0011 | 0b1001 | 0b1011 => { // CBZ, CBNZ (ARMv7)
+                let nonzero = ((op >> 11) & 1) == 1;
+                let offset  = ((op >> 9) & 1) << 6 | ((op >> 3) & 0b11111) << 1;
+                if (self.registers[(op & 0b111) as usize] != 0) == nonzero {
+                    self.branch_to(self.registers[15].wrapping_add(offset));
+                }
+            },
+            0b0100 | 0b0101 => { // Format 14: PUSH {rlist, LR?}
+                let rlist   = (op & 0xFF) | ((op >> 8) & 1) << 14;
+                let mut addr = self.registers[13].wrapping_sub(4 * rlist.count_ones());
+                self.registers[13] = addr;
+                for r in (0 .. 16).filter(|r| rlist & (1 << r) != 0) {
+                    self.write_data_u32(mem, addr & !3, self.registers[r]);
+                    addr = addr.wrapping_add(4);
+                }
+            },
+            0b0110 => match (op >> 5) & 0b111 {
+                0b010 => { // SETEND (ARMv6)
+                    self.cpsr.set(Psr::E, ((op >> 3) & 1) == 1);
+                },
+                0b011 => self.impl_cps(0b10 | ((op >> 4) & 1), Psr::from_bits_truncate((op & 0b111) << 6), None), // CPS (ARMv6)
+                _ => self.undefined(op),
+            },
+            0b1010 => { // REV, REV16, REVSH (ARMv6)
+                let rm = self.registers[((op >> 3) & 0b111) as usize];
+                self.registers[(op & 0b111) as usize] = match (op >> 6) & 0b11 {
+                    0b00 => rm.swap_bytes(),
+                    0b01 => ((rm as u16).swap_bytes() as u32) | (((rm >> 16) as u16).swap_bytes() as u32) << 16,
+                    0b11 => (rm as u16).swap_bytes() as i16 as u32,
+                    _b10 => { self.undefined(op); return StepOutcome::Continue },
+                };
+            },
+            0b1100 | 0b1101 => { // Format 14: POP {rlist, PC?}
+                let rlist   = (op & 0xFF) | ((op >> 8) & 1) << 15;
+                let mut addr = self.registers[13];
+                if self.misaligned(addr, 4, MemoryFlags::READ) { return StepOutcome::Continue }
+                self.registers[13] = addr.wrapping_add(4 * rlist.count_ones());
+                for r in (0 .. 16).filter(|r| rlist & (1 << r) != 0) {
+                    let value = self.read_data_u32(mem, addr & !3); // ignores the low address bits, like ARM LDM
+                    match r {
+                        15 => self.load_write_pc(value),
+                        r  => self.registers[r] = value,
+                    }
+                    addr = addr.wrapping_add(4);
+                }
+            },
+            0b1110 if self.config.arch < Arch::V5TE => self.undefined(op),
+            0b1110 => return StepOutcome::Breakpoint { pc: self.next_instruction_addr(), imm: op & 0xFF }, // BKPT (ARMv5T)
+            0b1111 => match op & 0xF {
+                0 => return self.impl_hint((op >> 4) & 0xF), // NOP, YIELD, WFE, WFI, SEV (ARMv7)
+                _ => self.itstate = op as u8, // IT (ARMv7): firstcond, mask
+            },
+            _ => self.undefined(op),
+        }
+        StepOutcome::Continue
+    }
+
+    /// Format 15: multiple load/store (LDMIA, STMIA)
+    fn thumb_multiple(&mut self, mem: &Memory, op: u32) {
+        let load        = ((op >> 11) & 0b1) == 1;
+        let rb          = ((op >>  8) & 0b111) as usize;
+        let rlist       = ((op >>  0) & 0xFF);
+
+        let mut addr    = self.registers[rb];
+        let final_base  = addr.wrapping_add(4 * rlist.count_ones());
+        if load {
+            if self.misaligned(addr, 4, MemoryFlags::READ) { return }
+            if rlist & (1 << rb) == 0 { self.registers[rb] = final_base; } // no write-back if the base is loaded
+            for r in (0 .. 8).filter(|r| rlist & (1 << r) != 0) {
+                self.registers[r] = self.read_data_u32(mem, addr & !3); // ignores the low address bits, like ARM LDM
+                addr = addr.wrapping_add(4);
+            }
+        } else {
+            for r in (0 .. 8).filter(|r| rlist & (1 << r) != 0) {
+                self.write_data_u32(mem, addr & !3, self.registers[r]);
+                addr = addr.wrapping_add(4);
+            }
+            self.registers[rb] = final_base;
+        }
+    }
+
+    /// Format 16: conditional branch, and Format 17: software interrupt
+    fn thumb_conditional_branch(&mut self, op: u32) -> StepOutcome {
+        match (op >> 8) & 0b1111 {
+            0b1110 => self.undefined(op),
+            0b1111 => return self.impl_swi(0xEF00_0000 | (op & 0xFF)), // as the equivalent ARM encoding
+            cond => if self.condition_passed(cond) {
+                let offset = ((op & 0xFF) as i8 as i32 as u32) << 1;
+                self.branch_to(self.registers[15].wrapping_add(offset));
+            },
+        }
+        StepOutcome::Continue
+    }
+
+    /// Format 18: unconditional branch
+    fn thumb_branch(&mut self, op: u32) {
+        let offset = (((op & 0x7FF) << 21) as i32 >> 20) as u32; // sign extended, << 1
+        self.branch_to(self.registers[15].wrapping_add(offset));
+    }
+
+    /// Format 19: long branch with link (BL, and the ARMv5T+ BLX immediate)
+    ///
+    /// Decoded as a single 32-bit instruction (as Thumb-2 does) rather than two halves communicating via LR.
+    /// For the ARMv4T/v5T encodings, J1 and J2 are always set, making the two interpretations equivalent.
+    fn thumb_long_branch_with_link(&mut self, pc: u32, op: u32, suffix: u32) {
+        if (suffix >> 14) != 0b11 { return self.undefined(op << 16 | suffix) } // 32-bit Thumb-2 instructions: not yet implemented
+        let exchange    = ((suffix >> 12) & 0b1) == 0;
+        if exchange && !self.require(Arch::V5TE, op << 16 | suffix) { return }
+        let s           = ((op >> 10) & 0b1);
+        let i1          = !(((suffix >> 13) & 0b1) ^ s) & 1;
+        let i2          = !(((suffix >> 11) & 0b1) ^ s) & 1;
+        let offset      = s << 24 | i1 << 23 | i2 << 22 | (op & 0x3FF) << 12 | (suffix & 0x7FF) << 1;
+        let offset      = ((offset << 7) as i32 >> 7) as u32; // sign extend 25 bits
+
+        self.registers[14] = pc.wrapping_add(4) | 1;
+        let target = self.registers[15].wrapping_add(offset);
+        match exchange {
+            false   => self.branch_to(target),                  // BL
+            true    => self.branch_exchange(target & !3),       // BLX: to ARM state
+        }
+    }
+}
@@ -0,0 +1,75 @@
+#![allow(unused_parens)]
+
+use super::*;
+
+// References:
+// ARMv4? https://developer.arm.com/documentation/ddi0210/c/Instruction-Cycle-Timings
+//
+// Limitations:
+// - Cycle counts are ARM7TDMI-ish for every Arch: no caches, no wait states, no pipeline interlocks
+// - VFP, NEON, coprocessors & the ARMv5TE+ multiplies count as single cycle instructions
+
+
+
+impl Cpu {
+    /// Approximately how many cycles the CPU has spent: 1 per instruction (including those that failed their condition),
+    /// plus extra cycles for loads, stores, multiplies, register specified shifts, and refilling the pipeline after branches & exceptions.
+    pub fn cycles(&self) -> u64 { self.cycles }
+
+    /// How many instructions the CPU has stepped past, including those that failed their condition (but not undefined ones)
+    pub fn instructions(&self) -> u64 { self.instructions }
+
+    /// Extra cycles the ARM instruction `op` will take (beyond 1) if it passes its condition
+    pub(super) fn arm_cycles(&self, op: u32) -> u32 {
+        let rs          = self.registers[((op >> 8) & 0b1111) as usize];
+        let rlist       = ((op >> 0) & 0xFFFF);
+        let load        = ((op >> 20) & 0b1) == 1;
+
+        if op & 0x0F80_00F0 == 0x0000_0090 { // MUL, MLA, (UMAAL, MLS)
+            Self::multiply_cycles(rs, true) + ((op >> 21) & 0b1)
+        } else if op & 0x0F80_00F0 == 0x0080_0090 { // UMULL, UMLAL, SMULL, SMLAL
+            Self::multiply_cycles(rs, (op >> 22) & 0b1 == 1) + 1 + ((op >> 21) & 0b1)
+        } else if op & 0x0FB0_0FF0 == 0x0100_0090 { // SWP, SWPB
+            3
+        } else if op & 0x0E10_00D0 == 0x0000_00D0 { // LDRD, STRD
+            if op & 0x20 == 0 { 3 } else { 2 }
+        } else if (op & 0x0E00_0090 == 0x0000_0090 && op & 0x60 != 0) || op & 0x0F80_0FF0 == 0x0180_0F90 { // halfword & signed transfers, exclusives
+            if load { 2 } else { 1 }
+        } else if op & 0x0200_0090 == 0x0000_0010 && op & 0x0190_0000 != 0x0100_0000 && op & 0x0C00_0000 == 0 { // data processing with a register specified shift
+            1
+        } else if op & 0x0C00_0000 == 0x0400_0000 && op & 0x0200_0010 != 0x0200_0010 { // LDR, STR (not media instructions)
+            if load { 2 } else { 1 }
+        } else if op & 0x0E00_0000 == 0x0800_0000 { // LDM, STM
+            rlist.count_ones() + if load { 1 } else { 0 }
+        } else {
+            0
+        }
+    }
+
+    /// Extra cycles the Thumb instruction `op` will take (beyond 1) if it passes its condition
+    pub(super) fn thumb_cycles(&self, op: u32) -> u32 {
+        let load        = ((op >> 11) & 0b1) == 1;
+        let rlist       = ((op >>  0) & 0xFF);
+        match op >> 11 {
+            0b01000 if (op >> 6) == 0b0100_0011_01 => Self::multiply_cycles(self.registers[(op & 0b111) as usize], true), // MUL
+            0b01000 if (op >> 10) & 1 == 0 && matches!((op >> 6) & 0b1111, 0b0010 | 0b0011 | 0b0100 | 0b0111) => 1, // LSL, LSR, ASR, ROR (register)
+            0b01001             => 2, // LDR (PC-relative)
+            0b01010 ..= 0b01011 => if (op >> 9) & 0b111 < 0b011 { 1 } else { 2 }, // STR*, LDR* (register offset)
+            0b01100 ..= 0b10011 => if load { 2 } else { 1 }, // LDR*, STR* (immediate offset & SP-relative)
+            0b10110 ..= 0b10111 if (op >> 9) & 0b11 == 0b10 => rlist.count_ones() + ((op >> 8) & 1) + if load { 1 } else { 0 }, // PUSH, POP (+ LR / PC)
+            0b11000 ..= 0b11001 => rlist.count_ones() + if load { 1 } else { 0 }, // LDMIA, STMIA
+            _                   => 0,
+        }
+    }
+
+    /// ARM7TDMI's "m": multiplies terminate early once the rest of `rs` is all zeros (or all ones, if `signed`)
+    fn multiply_cycles(rs: u32, signed: bool) -> u32 {
+        let redundant = if signed { rs.leading_zeros().max(rs.leading_ones()) } else { rs.leading_zeros() };
+        match redundant {
+            24 ..= 32   => 1,
+            16 ..= 23   => 2,
+            8  ..= 15   => 3,
+            _           => 4,
+        }
+    }
+}
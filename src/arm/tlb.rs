@@ -0,0 +1,84 @@
+use super::*;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Limitations:
+// - Only pages without WRITE permission or MMIO are cached (e.g. code, literal pools & read only data), so cached data never goes stale underneath the CPU.
+//   Stores, and loads from writable pages (stack, heap, .data & co.), still lock their page every time: only accesses to read-only pages skip the lock.
+// - No unlocked single-threaded backend or `&mut Memory` fast path: paged Memory always locks, so it stays shareable between threads (see smp.rs).
+//   Memory::new_flat (see flat.rs) is the lock free alternative, for every page.
+// - Direct mapped, with TLB_ENTRIES entries
+
+
+
+/// A small per-[`Cpu`] cache of read-only pages, so instruction fetches & loads from them can skip locking (and finding) their page.
+/// Writable pages are never cached: accesses to them always go through [`Memory`] and its page locks.
+///
+/// Flushed whenever the [`Memory`] it's used with has a different generation than when it was filled (see [`Memory::invalidate_tlbs`].)
+#[derive(Clone, Debug, Default)]
+pub(super) struct Tlb {
+    generation: u64,
+    entries:    [Option<TlbEntry>; TLB_ENTRIES],
+}
+
+#[derive(Clone, Debug)]
+struct TlbEntry {
+    page_idx:   usize,
+    cached:     Option<(MemoryFlags, Option<Arc<[u64; 512]>>)>, // None if the page can't be cached, to go straight to Memory
+}
+
+impl Tlb {
+    pub(super) fn fetch_u16(&mut self, mem: &Memory, addr: u32) -> Result<u16, MemFault> { self.read(mem, addr, MemoryFlags::READ | MemoryFlags::EXECUTE).map(u16::from_le_bytes) }
+    pub(super) fn fetch_u32(&mut self, mem: &Memory, addr: u32) -> Result<u32, MemFault> { self.read(mem, addr, MemoryFlags::READ | MemoryFlags::EXECUTE).map(u32::from_le_bytes) }
+
+    /// `N` bytes at `addr`.  Accesses straddling the end of a page go through [`Memory`] instead, which reads across pages.
+    pub(super) fn read<const N: usize>(&mut self, mem: &Memory, addr: u32, flags: MemoryFlags) -> Result<[u8; N], MemFault> {
+        let offset = mem.page_offset(addr);
+        let cached = if offset + N <= mem.page_size() as usize { self.lookup(mem, addr, flags) } else { None };
+        match cached {
+            Some(bytes) => Ok(bytes[offset..][..N].try_into().unwrap()),
+            None        => { let mut bytes = [0u8; N]; mem.try_read_bytes(addr, flags, &mut bytes)?; Ok(bytes) },
+        }
+    }
+
+    /// The bytes of `addr`'s page, if it's cachable and mapped for `flags`
+    fn lookup(&mut self, mem: &Memory, addr: u32, flags: MemoryFlags) -> Option<&[u8]> {
+        let generation = mem.tlb_generation();
+        if generation != self.generation { *self = Self { generation, ..Default::default() } }
+
+        let page_idx = mem.page_idx(addr);
+        let entry = &mut self.entries[page_idx % TLB_ENTRIES];
+        if entry.as_ref().map(|e| e.page_idx) != Some(page_idx) {
+            *entry = Some(TlbEntry { page_idx, cached: mem.tlb_cachable(page_idx) });
+        }
+
+        let (page_flags, data) = entry.as_ref()?.cached.as_ref()?;
+        if !page_flags.contains(flags) { return None } // let Memory report the fault
+        Some(bytemuck::bytes_of(data.as_deref().unwrap_or(&[0; 512])))
+    }
+}
+
+impl Memory {
+    /// Flush every [`Cpu`]'s cache of this memory's read-only pages.  Done by anything that changes a page's mapping, flags, or read-only data.
+    pub(super) fn invalidate_tlbs(&self) { self.tlb_generation.store(next_tlb_generation(), Ordering::Release) }
+
+    pub(super) fn tlb_generation(&self) -> u64 { self.tlb_generation.load(Ordering::Acquire) }
+
+    /// Page `page_idx`'s flags & data, if it's safe to cache: without WRITE permission (only written by the host, which calls [`Memory::invalidate_tlbs`]), and not MMIO
+    fn tlb_cachable(&self, page_idx: usize) -> Option<(MemoryFlags, Option<Arc<[u64; 512]>>)> {
+        if self.flat.is_some() { return None } // already cheap to read
+        let Some(page) = self.page(page_idx) else { return Some((MemoryFlags::NONE, None)) };
+        let page = page.lock().unwrap();
+        if page.flags.contains(MemoryFlags::WRITE) || page.mmio.is_some() { return None }
+        Some((page.flags, page.data.clone()))
+    }
+}
+
+/// Generations are unique across every [`Memory`], so a [`Tlb`] used with several can't mistake one for another
+pub(super) fn next_tlb_generation() -> u64 {
+    static NEXT : AtomicU64 = AtomicU64::new(1); // 0 is the empty Tlb's
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+const TLB_ENTRIES : usize = 16;
@@ -0,0 +1,119 @@
+use super::*;
+
+use std::ops::Range;
+use std::sync::atomic::Ordering;
+
+// Limitations:
+// - Only memory mapped with Memory::map while tracking is enabled starts out undefined: init_* (file-backed or zeroed) memory, MMIO, and anything mapped before counts as defined
+// - Only the CPU's own data reads are checked (like watchpoints): not instruction fetches, or the host's Memory accesses
+// - Shadow state is per byte, but kept in a single lock: tracking slows down every write to memory
+// - snapshot / restore leave shadow state alone
+// - Only the first undefined read of an instruction is reported
+
+
+
+/// A read of bytes never written since being [`Memory::map`]ped, reported by [`StepOutcome::UninitializedRead`] after the instruction at `pc` completes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UninitializedRead {
+    pub pc:         u32,
+    /// The whole access, which includes at least one undefined byte
+    pub addr:       u32,
+    pub size:       u32,
+    /// The first undefined byte of the access
+    pub undefined:  u32,
+}
+
+pub(super) type Shadow = Box<[u64; 4096/64]>; // a bit per byte of a page: set if undefined
+
+impl Memory {
+    /// Enable (or disable and forget) tracking which bytes have been written: [`Cpu::step1`] reports guest reads of
+    /// memory [`Memory::map`]ped since enabling, but never written, as [`StepOutcome::UninitializedRead`] - like MSan for guests.
+    pub fn set_uninit_tracking(&self, enabled: bool) {
+        self.track_uninit.store(enabled, Ordering::Relaxed);
+        if !enabled { self.undefined.lock().unwrap().clear() }
+    }
+
+    pub fn uninit_tracking(&self) -> bool { self.track_uninit.load(Ordering::Relaxed) }
+
+    /// Mark `range` as written (e.g. after the host initializes guest memory some other way)
+    pub fn mark_defined(&self, range: Range<u32>) { self.set_undefined(range.start.into() .. range.end.into(), false) }
+
+    /// Mark `range` as never written (e.g. when an emulated `free` or `malloc` recycles memory.)  Does nothing unless tracking is enabled.
+    pub fn mark_undefined(&self, range: Range<u32>) { if self.uninit_tracking() { self.set_undefined(range.start.into() .. range.end.into(), true) } }
+
+    /// The first byte of `range` that's never been written, if tracked
+    pub fn first_undefined(&self, range: Range<u32>) -> Option<u32> {
+        if !self.uninit_tracking() || range.is_empty() { return None }
+        let undefined = self.undefined.lock().unwrap();
+        let page_size = u64::from(self.page_size());
+        let (mut addr, end) = (u64::from(range.start), u64::from(range.end));
+        while addr < end {
+            let page_end = (addr & !(page_size - 1)) + page_size;
+            if let Some(shadow) = undefined.get(&self.page_idx(addr as u32)) {
+                for byte in addr .. page_end.min(end) {
+                    let offset = self.page_offset(byte as u32);
+                    if shadow[offset / 64] & (1 << (offset % 64)) != 0 { return Some(byte as u32) }
+                }
+            }
+            addr = page_end;
+        }
+        None
+    }
+
+    /// Mark `len` bytes at `addr` (wrapping around the end of the address space) as written, if tracking
+    pub(super) fn define(&self, addr: u32, len: usize) {
+        if !self.uninit_tracking() || len == 0 { return }
+        let start = u64::from(addr);
+        let end = start + len as u64;
+        self.set_undefined(start .. end.min(1 << 32), false);
+        if end > 1 << 32 { self.set_undefined(0 .. end - (1 << 32), false) }
+    }
+
+    /// Forget shadow state for pages that are no longer plain memory (unmapped, or remapped to a device)
+    pub(super) fn forget_undefined(&self, pages: Range<usize>) {
+        if !self.uninit_tracking() { return }
+        self.undefined.lock().unwrap().retain(|page_idx, _| !pages.contains(page_idx));
+    }
+
+    pub(super) fn set_undefined(&self, range: Range<u64>, undefined: bool) {
+        let page_size = u64::from(self.page_size());
+        let mut shadows = self.undefined.lock().unwrap();
+        let mut addr = range.start;
+        while addr < range.end {
+            let page_idx = self.page_idx(addr as u32);
+            let page_start = addr & !(page_size - 1);
+            let chunk = addr .. (page_start + page_size).min(range.end);
+            addr = chunk.end;
+
+            if chunk.start == page_start && chunk.end == page_start + page_size {
+                if undefined {
+                    let mut shadow = Box::new([0; 4096/64]);
+                    shadow[.. page_size as usize / 64].fill(!0);
+                    shadows.insert(page_idx, shadow);
+                } else {
+                    shadows.remove(&page_idx);
+                }
+                continue;
+            }
+            if !undefined && !shadows.contains_key(&page_idx) { continue }
+            let shadow : &mut Shadow = shadows.entry(page_idx).or_insert_with(|| Box::new([0; 4096/64]));
+            for byte in chunk {
+                let offset = (byte - page_start) as usize;
+                if undefined { shadow[offset / 64] |= 1 << (offset % 64) } else { shadow[offset / 64] &= !(1 << (offset % 64)) }
+            }
+            if shadow.iter().all(|bits| *bits == 0) { shadows.remove(&page_idx); }
+        }
+    }
+}
+
+impl Cpu {
+    /// Remember the instruction's first read of undefined memory (see [`Memory::set_uninit_tracking`].)  Called by [`Cpu::watch`], before the access.
+    pub(super) fn check_defined(&self, mem: &Memory, addr: u32, size: u32, access: MemoryFlags) {
+        if !access.contains(MemoryFlags::READ) || !mem.uninit_tracking() || self.uninit_hit.get().is_some() { return }
+        let end = addr.saturating_add(size); // accesses don't wrap in practice
+        if let Some(undefined) = mem.first_undefined(addr .. end) {
+            self.uninit_hit.set(Some(UninitializedRead { pc: 0, addr, size, undefined })); // pc filled in by Cpu::step1
+        }
+    }
+}
@@ -0,0 +1,299 @@
+#![allow(unused_parens)]
+
+use super::*;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (A7 Floating-point Instructions, A7.8 Floating-point data-processing instructions)
+//
+// Limitations:
+// - Arithmetic rounds to nearest regardless of FPSCR.RMode (only VCVT{R} to integer honors it)
+// - No cumulative exception flags besides IOC (invalid) and DZC (divide by zero), no traps
+// - FPSCR.LEN/STRIDE short vectors are ignored, every operation is scalar
+// - Flush-to-zero & default NaN modes are ignored
+
+
+
+/// VFP register file and status, shared with NEON (D0 ..= D31 are the low & high halves of Q0 ..= Q15)
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vfp {
+    pub d:      [u64; 32], // S0 ..= S31 alias the low & high halves of D0 ..= D15
+    pub fpscr:  u32,
+}
+
+impl Vfp {
+    pub fn s(&self, index: usize) -> u32 { (self.d[index / 2] >> (32 * (index % 2))) as u32 }
+    pub fn set_s(&mut self, index: usize, value: u32) {
+        let shift = 32 * (index % 2);
+        self.d[index / 2] = (self.d[index / 2] & !(0xFFFF_FFFF << shift)) | (u64::from(value) << shift);
+    }
+
+    pub fn f32(&self, index: usize) -> f32 { f32::from_bits(self.s(index)) }
+    pub fn f64(&self, index: usize) -> f64 { f64::from_bits(self.d[index]) }
+    pub fn set_f32(&mut self, index: usize, value: f32) { self.set_s(index, value.to_bits()) }
+    pub fn set_f64(&mut self, index: usize, value: f64) { self.d[index] = value.to_bits() }
+
+    pub fn q(&self, index: usize) -> u128 { u128::from(self.d[2 * index]) | u128::from(self.d[2 * index + 1]) << 64 }
+    pub fn set_q(&mut self, index: usize, value: u128) { self.d[2 * index] = value as u64; self.d[2 * index + 1] = (value >> 64) as u64; }
+}
+
+const FPSCR_IOC     : u32 = 1 << 0; // invalid operation (cumulative)
+const FPSCR_DZC     : u32 = 1 << 1; // divide by zero (cumulative)
+
+const FPSID         : u32 = 0x4103_3000; // implementer ARM, subarchitecture 3 (VFPv3+), part/variant/revision 0
+const MVFR0         : u32 = 0x1011_0222; // 32 D registers, VFPv3 single & double, divide, square root, all rounding modes
+const MVFR1         : u32 = 0x0001_1100; // NEON load/store, integer, and single precision (see neon.rs)
+
+impl Cpu {
+    /// Coprocessor 10 & 11 instructions (VFP)
+    pub(super) fn impl_vfp(&mut self, mem: &Memory, op: u32) {
+        match (op >> 24) & 0b1111 {
+            0b1100 if op & 0x00E0_0000 == 0x0040_0000 => self.vfp_transfer_two(op),
+            0b1100 | 0b1101 => self.vfp_load_store(mem, op),
+            0b1110 if op & 0x10 == 0 => self.vfp_data_processing(op),
+            0b1110 => self.vfp_transfer(op),
+            _other => self.undefined(op),
+        }
+    }
+
+    /// Decode a register number: `Vx:X` for single precision, `X:Vx` for double
+    fn vfp_reg(double: bool, v: u32, x: u32) -> usize { (if double { (x << 4) | v } else { (v << 1) | x }) as usize }
+
+    /// VLDR, VSTR, VLDM, VSTM (and VPUSH, VPOP)
+    fn vfp_load_store(&mut self, mem: &Memory, op: u32) {
+        let pre         = ((op >> 24) & 0b1) == 1;
+        let up          = ((op >> 23) & 0b1) == 1;
+        let d           = ((op >> 22) & 0b1);
+        let writeback   = ((op >> 21) & 0b1) == 1;
+        let load        = ((op >> 20) & 0b1) == 1;
+        let rn          = ((op >> 16) & 0b1111) as usize;
+        let vd          = ((op >> 12) & 0b1111);
+        let double      = ((op >>  8) & 0b1) == 1;
+        let imm8        = ((op >>  0) & 0xFF);
+
+        let vd          = Self::vfp_reg(double, vd, d);
+        let bytes       = imm8 * 4;
+        let base        = if rn == 15 { self.registers[15] & !3 } else { self.registers[rn] };
+        let (mut addr, count) = match (pre, writeback) {
+            (true, false) => (if up { base.wrapping_add(bytes) } else { base.wrapping_sub(bytes) }, 1), // VLDR, VSTR
+            (true, true) if !up => (base.wrapping_sub(bytes), if double { imm8 / 2 } else { imm8 }),  // VLDMDB, VSTMDB
+            (false, _)   if up  => (base, if double { imm8 / 2 } else { imm8 }),                        // VLDMIA, VSTMIA
+            _ => return self.undefined(op),
+        };
+        if count == 0 || vd + count as usize > 32 || (double && count > 16) { return self.undefined(op) } // register lists running off the end of the bank are UNPREDICTABLE
+        if addr & 3 != 0 { // VFP loads & stores are word aligned, regardless of SCTLR.A
+            let access = if load { MemoryFlags::READ } else { MemoryFlags::WRITE };
+            return self.data::<()>(Err(MemFault { addr, access, flags: MemoryFlags::NONE, kind: MemFaultKind::Alignment }));
+        }
+        if writeback { self.registers[rn] = if up { base.wrapping_add(bytes) } else { base.wrapping_sub(bytes) }; }
+
+        for reg in vd .. vd + count as usize {
+            match (double, load) {
+                (false, true ) => self.vfp.set_s(reg, self.read_data_u32(mem, addr)),
+                (false, false) => self.write_data_u32(mem, addr, self.vfp.s(reg)),
+                (true,  true ) => self.vfp.d[reg] = self.read_data_u64(mem, addr),
+                (true,  false) => self.write_data_u64(mem, addr, self.vfp.d[reg]),
+            }
+            addr = addr.wrapping_add(if double { 8 } else { 4 });
+        }
+    }
+
+    /// VMOV between two ARM core registers and a D register or a pair of S registers
+    fn vfp_transfer_two(&mut self, op: u32) {
+        let to_core     = ((op >> 20) & 0b1) == 1;
+        let rt2         = ((op >> 16) & 0b1111) as usize;
+        let rt          = ((op >> 12) & 0b1111) as usize;
+        let double      = ((op >>  8) & 0b1) == 1;
+        let m           = ((op >>  5) & 0b1);
+        let vm          = ((op >>  0) & 0b1111);
+
+        let vm          = Self::vfp_reg(double, vm, m);
+        match (double, to_core) {
+            (true,  true ) => { self.registers[rt] = self.vfp.d[vm] as u32; self.registers[rt2] = (self.vfp.d[vm] >> 32) as u32; },
+            (true,  false) => self.vfp.d[vm] = u64::from(self.registers[rt]) | u64::from(self.registers[rt2]) << 32,
+            (false, true ) => { self.registers[rt] = self.vfp.s(vm); self.registers[rt2] = self.vfp.s(vm + 1); },
+            (false, false) => { self.vfp.set_s(vm, self.registers[rt]); self.vfp.set_s(vm + 1, self.registers[rt2]); },
+        }
+    }
+
+    /// VMOV between an ARM core register and an S register or D register half, VMRS, VMSR
+    fn vfp_transfer(&mut self, op: u32) {
+        let opc1        = ((op >> 21) & 0b111);
+        let to_core     = ((op >> 20) & 0b1) == 1;
+        let vn          = ((op >> 16) & 0b1111);
+        let rt          = ((op >> 12) & 0b1111) as usize;
+        let cp11        = ((op >>  8) & 0b1) == 1;
+        let n           = ((op >>  7) & 0b1);
+        let opc2        = ((op >>  5) & 0b11);
+
+        match (cp11, opc1) {
+            (false, 0b000) => { // VMOV Rt, Sn / VMOV Sn, Rt
+                let sn = Self::vfp_reg(false, vn, n);
+                if to_core { self.registers[rt] = self.vfp.s(sn) } else { self.vfp.set_s(sn, self.registers[rt]) }
+            },
+            (false, 0b111) => match (to_core, vn) { // VMRS, VMSR
+                (true,  0b0001) if rt == 15 => self.cpsr = (self.cpsr - Psr::N - Psr::Z - Psr::C - Psr::V) | Psr::from_bits_truncate(self.vfp.fpscr & 0xF000_0000), // VMRS APSR_nzcv, FPSCR
+                (true,  0b0001) => self.registers[rt] = self.vfp.fpscr,
+                (true,  0b0000) => self.registers[rt] = FPSID,
+                (true,  0b0110) => self.registers[rt] = MVFR1,
+                (true,  0b0111) => self.registers[rt] = MVFR0,
+                (false, 0b0001) => self.vfp.fpscr = self.registers[rt],
+                _ => self.undefined(op),
+            },
+            (true, 0b000 | 0b001) if opc2 == 0 => { // VMOV Rt, Dn[x] / VMOV Dn[x], Rt (32-bit lanes)
+                let dn = Self::vfp_reg(true, vn, n);
+                let sn = 2 * dn + (opc1 & 1) as usize; // as if S registers continued past S31
+                let shift = 32 * (sn % 2);
+                match to_core {
+                    true  => self.registers[rt] = (self.vfp.d[dn] >> shift) as u32,
+                    false => self.vfp.d[dn] = (self.vfp.d[dn] & !(0xFFFF_FFFF << shift)) | (u64::from(self.registers[rt]) << shift),
+                }
+            },
+            (true, _) => self.neon_core_transfer(op), // 8 & 16-bit scalars, VDUP
+            _ => self.undefined(op),
+        }
+    }
+
+    /// VMLA, VMLS, VNMLA, VNMLS, VMUL, VNMUL, VADD, VSUB, VDIV, and the "other" VMOV, VABS, VNEG, VSQRT, VCMP, VCVT group
+    fn vfp_data_processing(&mut self, op: u32) {
+        let opc1        = ((op >> 20) & 0b1011); // p:D:q:r with D masked out
+        let d           = ((op >> 22) & 0b1);
+        let vn          = ((op >> 16) & 0b1111);
+        let vd          = ((op >> 12) & 0b1111);
+        let double      = ((op >>  8) & 0b1) == 1;
+        let n           = ((op >>  7) & 0b1);
+        let opc3        = ((op >>  6) & 0b1);
+        let m           = ((op >>  5) & 0b1);
+        let vm          = ((op >>  0) & 0b1111);
+
+        if opc1 == 0b1011 { return self.vfp_other(op) }
+        let (dd, dn, dm) = (Self::vfp_reg(double, vd, d), Self::vfp_reg(double, vn, n), Self::vfp_reg(double, vm, m));
+
+        // compute in f64 - exact for every f32 operation here, rounding once on the way back
+        let (a, b, acc) = match double {
+            false => (f64::from(self.vfp.f32(dn)), f64::from(self.vfp.f32(dm)), f64::from(self.vfp.f32(dd))),
+            true  => (self.vfp.f64(dn), self.vfp.f64(dm), self.vfp.f64(dd)),
+        };
+        let product = || if double { a * b } else { f64::from((a * b) as f32) }; // VMLA & co. round the product before accumulating
+        let result = match (opc1, opc3) {
+            (0b0000, 0) => acc + product(),     // VMLA
+            (0b0000, _) => acc - product(),     // VMLS
+            (0b0001, 0) => -acc + product(),    // VNMLS
+            (0b0001, _) => -acc - product(),    // VNMLA
+            (0b0010, 0) => a * b,               // VMUL
+            (0b0010, _) => -(a * b),            // VNMUL
+            (0b0011, 0) => a + b,               // VADD
+            (0b0011, _) => a - b,               // VSUB
+            (0b1000, 0) => {                    // VDIV
+                if b == 0.0 && !a.is_nan() && a != 0.0 { self.vfp.fpscr |= FPSCR_DZC; }
+                a / b
+            },
+            _ => return self.undefined(op),
+        };
+        if double { self.vfp.set_f64(dd, result) } else { self.vfp.set_f32(dd, result as f32) }
+    }
+
+    /// VMOV (immediate & register), VABS, VNEG, VSQRT, VCMP{E}, VCVT{R}
+    fn vfp_other(&mut self, op: u32) {
+        let d           = ((op >> 22) & 0b1);
+        let opc2        = ((op >> 16) & 0b1111);
+        let vd          = ((op >> 12) & 0b1111);
+        let double      = ((op >>  8) & 0b1) == 1;
+        let opc2_bit7   = ((op >>  7) & 0b1) == 1;
+        let opc3        = ((op >>  6) & 0b1) == 1;
+        let m           = ((op >>  5) & 0b1);
+        let vm          = ((op >>  0) & 0b1111);
+
+        let (dd, dm)    = (Self::vfp_reg(double, vd, d), Self::vfp_reg(double, vm, m));
+        let read = |vfp: &Vfp, reg: usize| if double { vfp.f64(reg) } else { f64::from(vfp.f32(reg)) };
+        let write = |vfp: &mut Vfp, reg: usize, value: f64| if double { vfp.set_f64(reg, value) } else { vfp.set_f32(reg, value as f32) };
+
+        if !opc3 { // VMOV (immediate): VFPExpandImm
+            let imm8 = ((op >> 16) & 0xF) << 4 | (op & 0xF);
+            let (a, b, cdefgh) = ((imm8 >> 7) & 1, (imm8 >> 6) & 1, imm8 & 0x3F);
+            match double {
+                false => self.vfp.set_s(dd, a << 31 | (b ^ 1) << 30 | (if b == 1 { 0x1F } else { 0 }) << 25 | cdefgh << 19),
+                true  => self.vfp.d[dd] = u64::from(a) << 63 | u64::from(b ^ 1) << 62 | (if b == 1 { 0xFF } else { 0 }) << 54 | u64::from(cdefgh) << 48,
+            }
+            return;
+        }
+
+        // single precision operands of conversions use the S register numbering regardless of sz
+        let (sd, sm)    = (Self::vfp_reg(false, vd, d), Self::vfp_reg(false, vm, m));
+
+        match (opc2, opc2_bit7) {
+            (0b0000, false) => if double { self.vfp.d[dd] = self.vfp.d[dm] } else { self.vfp.set_s(dd, self.vfp.s(dm)) }, // VMOV (register)
+            (0b0000, true ) => { let v = read(&self.vfp, dm); write(&mut self.vfp, dd, v.abs()) },  // VABS
+            (0b0001, false) => { let v = read(&self.vfp, dm); write(&mut self.vfp, dd, -v) },       // VNEG
+            (0b0001, true ) => { // VSQRT
+                let v = read(&self.vfp, dm);
+                if v < 0.0 { self.vfp.fpscr |= FPSCR_IOC; }
+                write(&mut self.vfp, dd, v.sqrt())
+            },
+            (0b0100 | 0b0101, quiet_nan_raises) => { // VCMP{E}, with zero for 0b0101
+                let a = read(&self.vfp, dd);
+                let b = if opc2 == 0b0101 { 0.0 } else { read(&self.vfp, dm) };
+                let nzcv = match a.partial_cmp(&b) {
+                    Some(std::cmp::Ordering::Equal)     => 0b0110,
+                    Some(std::cmp::Ordering::Less)      => 0b1000,
+                    Some(std::cmp::Ordering::Greater)   => 0b0010,
+                    None                                => { if quiet_nan_raises { self.vfp.fpscr |= FPSCR_IOC; } 0b0011 },
+                };
+                self.vfp.fpscr = (self.vfp.fpscr & 0x0FFF_FFFF) | (nzcv << 28);
+            },
+            (0b0111, true) => match double { // VCVT between double and single precision
+                true  => { let v = self.vfp.f64(dm); self.vfp.set_f32(sd, v as f32) },
+                false => { let v = self.vfp.f32(sm); self.vfp.set_f64(Self::vfp_reg(true, vd, d), f64::from(v)) },
+            },
+            (0b1000, signed) => { // VCVT from integer
+                let v = self.vfp.s(sm);
+                let v = if signed { f64::from(v as i32) } else { f64::from(v) };
+                write(&mut self.vfp, dd, v);
+            },
+            (0b1100 | 0b1101, round_to_zero) => { // VCVT{R} to integer
+                let v = if double { self.vfp.f64(dm) } else { f64::from(self.vfp.f32(sm)) };
+                let rounded = match if round_to_zero { 0b11 } else { (self.vfp.fpscr >> 22) & 0b11 } {
+                    0b00 => v.round_ties_even(),
+                    0b01 => v.ceil(),
+                    0b10 => v.floor(),
+                    _    => v.trunc(),
+                };
+                let signed = opc2 == 0b1101;
+                let (min, max) = if signed { (f64::from(i32::MIN), f64::from(i32::MAX)) } else { (0.0, f64::from(u32::MAX)) };
+                if !(min ..= max).contains(&rounded) { self.vfp.fpscr |= FPSCR_IOC; } // includes NaN
+                self.vfp.set_s(sd, if signed { rounded as i32 as u32 } else { rounded as u32 }); // `as` saturates & maps NaN to 0, like VCVT
+            },
+            (0b1010 | 0b1011 | 0b1110 | 0b1111, sx) => { // VCVT between floating point and fixed point (operates in place)
+                let to_fixed    = (opc2 & 0b0100) != 0;
+                let unsigned    = (opc2 & 0b0001) != 0;
+                let size        = if sx { 32 } else { 16 };
+                let imm5        = ((op & 0xF) << 1) | ((op >> 5) & 1);
+                let frac_bits   = size - imm5 as i32;
+                let scale       = 2f64.powi(frac_bits);
+                match to_fixed {
+                    true => {
+                        let v = read(&self.vfp, dd) * scale;
+                        let fixed = match (unsigned, size) {
+                            (false, 16) => v as i16 as u32,
+                            (false, _ ) => v as i32 as u32,
+                            (true,  16) => v as u16 as u32,
+                            (true,  _ ) => v as u32,
+                        };
+                        if double { self.vfp.d[dd] = match unsigned { false => fixed as i32 as i64 as u64, true => u64::from(fixed) } } else { self.vfp.set_s(dd, fixed) }
+                    },
+                    false => {
+                        let raw = if double { self.vfp.d[dd] as u32 } else { self.vfp.s(dd) };
+                        let v = match (unsigned, size) {
+                            (false, 16) => f64::from(raw as i16),
+                            (false, _ ) => f64::from(raw as i32),
+                            (true,  16) => f64::from(raw as u16),
+                            (true,  _ ) => f64::from(raw),
+                        };
+                        write(&mut self.vfp, dd, v / scale);
+                    },
+                }
+            },
+            _ => self.undefined(op),
+        }
+    }
+}
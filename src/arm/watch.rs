@@ -0,0 +1,54 @@
+use super::*;
+
+use std::ops::Range;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (C3.4 Watchpoint debug events, which these stand in for)
+//
+// Limitations:
+// - Only the CPU's own data accesses are watched: not instruction fetches, other CPUs, DMA, or the host's Memory accesses
+// - `old` is read from memory before the access, so watching MMIO (see mmio.rs) reads the device an extra time
+// - Only the first hit of an instruction is reported
+
+
+
+/// Data accesses to watch for, in [`Cpu::watchpoints`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Watchpoint {
+    pub range:  Range<u32>,
+    /// [`MemoryFlags::READ`] and/or [`MemoryFlags::WRITE`]
+    pub access: MemoryFlags,
+}
+
+/// A watched access, reported by [`StepOutcome::Watchpoint`] after the instruction at `pc` completes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchpointHit {
+    pub pc:     u32,
+    /// The whole access, which overlaps the watchpoint (but might not be contained by it)
+    pub addr:   u32,
+    pub size:   u32,
+    pub access: MemoryFlags,
+    /// The accessed bytes before & after the access, as a little endian integer (the same, for reads)
+    pub old:    u64,
+    pub new:    u64,
+}
+
+impl Cpu {
+    /// Remember the instruction's first access that a watchpoint is watching - `new` being the bytes to be written, as a little endian integer.
+    /// Call before the access, so `old` can be read.
+    pub(super) fn watch(&self, mem: &Memory, addr: u32, size: u32, access: MemoryFlags, new: u64) {
+        self.check_defined(mem, addr, size, access);
+        if self.watchpoints.is_empty() || self.watch_hit.get().is_some() { return }
+        let end = u64::from(addr) + u64::from(size);
+        let watched = self.watchpoints.iter().any(|wp| wp.access.intersects(access) && u64::from(wp.range.start) < end && addr < wp.range.end);
+        if !watched { return }
+
+        let mut old = [0u8; 8];
+        let _ = mem.try_read_bytes(addr, MemoryFlags::NONE, &mut old[..size as usize]); // unmapped memory reads as zero - the access itself will fault
+        let old = u64::from_le_bytes(old);
+        let new = if access.contains(MemoryFlags::WRITE) { new } else { old };
+        self.watch_hit.set(Some(WatchpointHit { pc: 0, addr, size, access, old, new })); // pc filled in by Cpu::step1
+    }
+}
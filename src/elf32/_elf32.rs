@@ -0,0 +1,535 @@
+mod attributes; pub use attributes::*;
+mod coredump; pub use coredump::*;
+mod dwarf; pub use dwarf::*;
+mod file; pub use file::*;
+mod image; pub use image::*;
+mod link;
+pub mod linux;
+pub mod reloc;
+mod tls; use tls::TlsBlock;
+mod unwind; pub use unwind::*;
+
+use crate::{arm, Machine};
+
+use std::collections::BTreeMap;
+use std::io;
+use std::mem::size_of_val;
+use std::path::PathBuf;
+
+use bytemuck::{bytes_of_mut, Pod, Zeroable};
+use read_write_at::ReadAtMut;
+
+
+
+pub type Addr       = u32;
+pub type Off        = u32;
+pub type Section    = u16;
+pub type Versym     = u16;
+
+/// **E**lf **H**ea**d**e**r**
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Ehdr {
+    pub e_ident:        [u8; 16],
+    pub e_type:         u16,
+    pub e_machine:      u16,
+    pub e_version:      u32,
+    pub e_entry:        Addr,
+    pub e_phoff:        Off,
+    pub e_shoff:        Off,
+    pub e_flags:        u32,
+    pub e_ehsize:       u16,
+    pub e_phentsize:    u16,
+    pub e_phnum:        u16,
+    pub e_shentsize:    u16,
+    pub e_shnum:        u16,
+    pub e_shstrndx:     u16,
+}
+
+// **P**rogram **H**ea**d**e**r**
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Phdr {
+    pub p_type:     u32,
+    // p_flags goes here in 64-bit version
+    pub p_offset:   Off,
+    pub p_vaddr:    Addr,
+    pub p_paddr:    Addr,
+    pub p_filesz:   u32,
+    pub p_memsz:    u32,
+    pub p_flags:    u32,
+    pub p_align:    u32,
+}
+
+/// **S**ection **h**ea**d**e**r**
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Shdr {
+    pub sh_name:        u32,
+    pub sh_type:        u32,
+    pub sh_flags:       u32,
+    pub sh_addr:        Addr,
+    pub sh_offset:      Off,
+    pub sh_size:        u32,
+    pub sh_link:        u32,
+    pub sh_info:        u32,
+    pub sh_addralign:   u32,
+    pub sh_entsize:     u32,
+}
+
+/// **Dyn**amic section entry
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Dyn {
+    pub d_tag:      i32,
+    pub d_val:      u32, // or d_ptr
+}
+
+/// **Sym**bol table entry
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Sym {
+    pub st_name:    u32,
+    pub st_value:   Addr,
+    pub st_size:    u32,
+    pub st_info:    u8,
+    pub st_other:   u8,
+    pub st_shndx:   Section,
+}
+
+/// Byte swap every multi-byte field: ELF structures are stored in the file's byte order (EI_DATA), not the host's
+trait SwapBytes { fn swap_bytes(&mut self); }
+
+macro_rules! swap_bytes {
+    ( $ty:ty : $($field:ident),* ) => {
+        impl SwapBytes for $ty { fn swap_bytes(&mut self) { $( self.$field = self.$field.swap_bytes(); )* } }
+    };
+}
+
+swap_bytes!(Ehdr: e_type, e_machine, e_version, e_entry, e_phoff, e_shoff, e_flags, e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx);
+swap_bytes!(Phdr: p_type, p_offset, p_vaddr, p_paddr, p_filesz, p_memsz, p_flags, p_align);
+swap_bytes!(Shdr: sh_name, sh_type, sh_flags, sh_addr, sh_offset, sh_size, sh_link, sh_info, sh_addralign, sh_entsize);
+swap_bytes!(Dyn: d_tag, d_val);
+swap_bytes!(Sym: st_name, st_value, st_size, st_shndx);
+swap_bytes!(Rel: r_offset, r_info);
+swap_bytes!(Rela: r_offset, r_info, r_addend);
+
+impl Ehdr {
+    pub const EF_ARM_BE8 : u32 = 0x0080_0000;
+
+    /// `true` if the file is big-endian (EI_DATA == ELFDATA2MSB)
+    pub fn big_endian(&self) -> bool { self.e_ident[5] == 2 }
+
+    /// `true` for big-endian images with little-endian code (ARMv6+ BE8), as opposed to legacy word-invariant BE32 images
+    pub fn be8(&self) -> bool { self.big_endian() && self.e_flags & Self::EF_ARM_BE8 != 0 }
+}
+
+impl Sym {
+    /// ELF32_ST_BIND: STB_LOCAL (0), STB_GLOBAL (1), STB_WEAK (2), ...
+    pub fn st_bind(&self) -> u8 { self.st_info >> 4 }
+    /// ELF32_ST_TYPE: STT_NOTYPE (0), STT_OBJECT (1), STT_FUNC (2), STT_SECTION (3), STT_FILE (4), ...
+    pub fn st_type(&self) -> u8 { self.st_info & 0xF }
+}
+
+/// **Rel**ocation entry
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Rel {
+    pub r_offset:   Addr,
+    pub r_info:     u32,
+}
+
+/// **Rel**ocation entry with **a**ddend
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Rela {
+    pub r_offset:   Addr,
+    pub r_info:     u32,
+    pub r_addend:   i32,
+}
+
+macro_rules! invalid_data {
+    ( $reason:expr ) => {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, concat!("uvm::elf::run: ", $reason)))
+    };
+    ( $fmt:expr, $($arg:tt)* ) => {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!(concat!("uvm::elf::run: ", $fmt), $($arg)*)))
+    };
+}
+
+/// How [`run_with`] starts a process
+#[derive(Clone, Debug)]
+pub struct LoadConfig {
+    /// `argv`: conventionally starting with the program's name.  If empty, the guest sees a single empty `argv[0]`, as linux substitutes.
+    pub args:       Vec<String>,
+    /// `envp`: `"NAME=value"` strings
+    pub env:        Vec<String>,
+    /// Bytes of stack to map below `0xBF00_0000` (rounded up to whole pages), with a guard page below that.  Defaults to 8 MiB, linux's default RLIMIT_STACK.
+    /// The stack doesn't grow: guests overflowing it fault with [`arm::Fault::StackOverflow`].
+    pub stack_size: u32,
+    /// If the stack is mapped [`arm::MemoryFlags::EXECUTE`]able (e.g. for GCC's nested function trampolines).  Defaults to [`None`]: like linux, only if the
+    /// executable's PT_GNU_STACK has PF_X, or it has no PT_GNU_STACK (as older toolchains didn't emit).
+    pub exec_stack: Option<bool>,
+    /// Where to write an ELF core file (see [`Process::write_core`]) if the guest faults or hits a breakpoint.  Defaults to [`None`]: no core dumps.
+    pub core_dump:  Option<PathBuf>,
+    /// The CPU to emulate.  Defaults to [`None`]: whatever the executable's `.ARM.attributes` were built for (see [`Attributes::cpu_config`]),
+    /// or [`arm::CpuConfig::default`] if it has none.  Set this to run executables whose attributes ask for more than [`arm::Cpu`] implements anyways.
+    pub cpu:        Option<arm::CpuConfig>,
+    /// Who links dynamically linked executables.  Defaults to [`Linking::Interp`].
+    pub linking:    Linking,
+    /// Directories to search for DT_NEEDED libraries (e.g. `/usr/arm-linux-gnueabihf/lib`), after their dependents' DT_RUNPATH, when [`load_with`] links them itself
+    pub library_path: Vec<PathBuf>,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self { Self { args: Vec::new(), env: Vec::new(), stack_size: 8 << 20, exec_stack: None, core_dump: None, cpu: None, linking: Linking::Interp, library_path: Vec::new() } }
+}
+
+/// Who links a dynamically linked executable: its DT_NEEDED libraries are loaded, symbols resolved across them, and their initializers run, before its entry point
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Linking {
+    /// Load the executable's PT_INTERP (e.g. `/lib/ld-linux-armhf.so.3`) and leave the linking to it, like linux.
+    /// Executables without one are linked like [`Linking::Eager`].
+    Interp,
+    /// Ignore any PT_INTERP: [`load_with`] links the executable itself, binding every symbol up front (like `LD_BIND_NOW=1`)
+    Eager,
+    /// Ignore any PT_INTERP: [`load_with`] links the executable itself, binding functions called through the PLT on their first call
+    Lazy,
+}
+
+/// [`run_with`] the default [`LoadConfig`]: no arguments, and an empty environment
+pub fn run(elf: &mut impl ReadAtMut) -> io::Result<()> { run_with(elf, &LoadConfig::default()) }
+
+/// [`load_with`] the default [`LoadConfig`]: no arguments, and an empty environment
+pub fn load(elf: &mut impl ReadAtMut) -> io::Result<Process> { load_with(elf, &LoadConfig::default()) }
+
+/// A linux process, as loaded by [`load_with`]: drive it with [`Process::step`] or [`Process::run`], or step [`Process::cpu`] yourself
+pub struct Process {
+    pub mem:        arm::Memory,
+    /// The initial registers: r13 (SP) points at `argc`, and the PC at `entry`
+    pub cpu:        arm::Cpu,
+    /// Where execution starts: the PT_INTERP's entry point if there is one, otherwise the executable's (load biased)
+    pub entry:      u32,
+    /// The executable first, then its PT_INTERP or DT_NEEDED libraries (if any)
+    pub images:     Vec<LoadedImage>,
+    /// The initial program break: the page aligned end of the executable's PT_LOAD segments, which `brk` can't shrink below
+    pub start_brk:  u32,
+    /// The current program break (initially `start_brk`)
+    pub brk:        u32,
+    /// Host files open in the guest, by file descriptor, for file-backed `mmap2`.  There's no `open` syscall yet: embedders insert files themselves.
+    /// 0 ..= 2 are the host's stdio regardless, which `write` handles itself.
+    pub files:      BTreeMap<u32, std::fs::File>,
+    /// The guest's exit status, once it's called `exit`
+    pub exit_status: Option<i32>,
+    /// See [`LoadConfig::core_dump`]
+    pub core_dump:  Option<PathBuf>,
+    link:           link::Linker,
+}
+
+impl Process {
+    /// Execute a single instruction, handling any syscall it makes.  Returns the guest's exit status once it's exited.
+    /// Faults & breakpoints are errors, with the PC symbolized against [`Process::images`] (and a [`Process::backtrace`], if the images have unwind tables),
+    /// after dumping core if [`Process::core_dump`] is set.
+    pub fn step(&mut self) -> io::Result<Option<i32>> {
+        if self.exit_status.is_some() { return Ok(self.exit_status) }
+        match self.cpu.step(&self.mem) {
+            Ok(arm::Event::Continue)    => {},
+            Ok(arm::Event::Watchpoint(_)) => {}, // no watchpoints are set
+            Ok(arm::Event::UninitializedRead(_)) => {}, // uninitialized memory isn't tracked
+            Ok(arm::Event::Wait)        => std::thread::yield_now(), // nothing will wake us yet, but don't hog the host
+            Ok(arm::Event::Svc(imm))    => linux::syscall(self, imm),
+            Err(arm::Fault::PrefetchAbort { pc, .. }) if link::LINKER_TRAPS.contains(&pc) => self.link.trap(&mut self.cpu, &self.mem, pc)?,
+            Ok(arm::Event::Breakpoint { pc, imm }) => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf::run: breakpoint #{} at {}{}{}", imm, symbolize(&self.images, pc), self.dump_core(SIGTRAP), self.describe_backtrace(pc)))),
+            Err(fault)                  => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf::run: {}, pc = {}{}{}", fault.labeled(&self.mem), symbolize(&self.images, fault.pc()), self.dump_core(fault_signal(&fault)), self.describe_backtrace(fault.pc())))),
+        }
+        Ok(self.exit_status)
+    }
+
+    /// [`Process::step`] until the guest exits, returning its exit status
+    pub fn run(&mut self) -> io::Result<i32> {
+        loop {
+            if let Some(status) = self.step()? { return Ok(status) }
+        }
+    }
+}
+
+/// Load & run the linux executable `elf` until it exits (which exits the host process with the guest's exit status) or faults
+pub fn run_with(elf: &mut impl ReadAtMut, config: &LoadConfig) -> io::Result<()> {
+    let status = load_with(elf, config)?.run()?;
+    std::process::exit(status)
+}
+
+/// Load the linux executable `elf` (and its PT_INTERP) into a fresh address space, ready to execute its first instruction
+pub fn load_with(elf: &mut impl ReadAtMut, config: &LoadConfig) -> io::Result<Process> {
+    let ehdr = read_ehdr(elf)?;
+    let phdrs = read_phdrs(elf, &ehdr)?;
+    let bias = match ehdr.e_type {
+        2 => 0, // ET_EXEC
+        3 => PIE_BASE.wrapping_sub(load_span(&phdrs).start), // ET_DYN (position independent executables)
+        _ => invalid_data!("only elf executables are currently supported (e_type != ET_EXEC or ET_DYN)"),
+    };
+    if ehdr.big_endian() && !ehdr.be8() { invalid_data!("only BE8 big-endian elfs are supported: legacy BE32 code isn't") }
+
+    let mem = arm::Memory::with_config(arm::MemoryConfig { page_size: 0x1000, ..Default::default() }); // match linux's 4 KiB pages
+    load_segments(&mem, elf, &phdrs, bias, "")?;
+    let mut images = vec![LoadedImage::read(elf, "", bias)?];
+
+    let cpu_config = match config.cpu {
+        Some(cpu_config) => cpu_config,
+        None => match File::read(elf) { // like LoadedImage::read, tolerate unreadable section headers
+            Ok(file)    => Attributes::read(&file, elf)?.map_or(Ok(arm::CpuConfig::default()), |attributes| attributes.cpu_config())?,
+            Err(_)      => arm::CpuConfig::default(),
+        },
+    };
+    let mut core = arm::Cpu::with_config(cpu_config);
+    let mut link = link::Linker::default();
+    let (entry, interp_bias) = match phdrs.iter().find(|phdr| phdr.p_type == 3).filter(|_| config.linking == Linking::Interp) { // PT_INTERP
+        None => {
+            if ehdr.big_endian() && phdrs.iter().any(|phdr| phdr.p_type == 2) { invalid_data!("relocating big-endian elfs isn't yet supported") } // PT_DYNAMIC
+            link = link::Linker::link(&mem, &phdrs, bias, config, &mut images)?;
+            (ehdr.e_entry.wrapping_add(bias), 0)
+        },
+        Some(interp) => { // the dynamic linker relocates both the executable and itself: just load it, and let it take over
+            let path = read_interp(elf, interp)?;
+            let mut ld = std::fs::File::open(&path).map_err(|err| io::Error::new(err.kind(), format!("uvm::elf::run: unable to open PT_INTERP `{}`: {}", path, err)))?;
+            let ld_ehdr = read_ehdr(&mut ld)?;
+            if ld_ehdr.e_type != 2 && ld_ehdr.e_type != 3 { invalid_data!("PT_INTERP `{}` isn't an executable or shared object", path) }
+            let ld_phdrs = read_phdrs(&mut ld, &ld_ehdr)?;
+
+            let ld_bias = if ld_ehdr.e_type == 3 { // ET_DYN: place it wherever it fits, like the kernel's mmap would
+                let span = load_span(&ld_phdrs);
+                let size = span.end.wrapping_sub(span.start);
+                let Some(base) = mem.find_unmapped(INTERP_AREA, size, 0x1000) else { invalid_data!("no room to load PT_INTERP `{}` (0x{:x} bytes)", path, size) };
+                base.wrapping_sub(span.start)
+            } else { 0 };
+            load_segments(&mem, &mut ld, &ld_phdrs, ld_bias, &path)?;
+            images.push(LoadedImage::read(&mut ld, path.as_str(), ld_bias)?);
+            (ld_ehdr.e_entry.wrapping_add(ld_bias), ld_bias)
+        },
+    };
+
+    let phdr_addr = bias.wrapping_add(match phdrs.iter().find(|phdr| phdr.p_type == 6) { // PT_PHDR
+        Some(phdr) => phdr.p_vaddr,
+        None => phdrs.iter().find(|phdr| phdr.p_type == 1 && phdr.p_offset <= ehdr.e_phoff && ehdr.e_phoff - phdr.p_offset < phdr.p_filesz) // the PT_LOAD containing the program headers
+            .map_or(0, |phdr| phdr.p_vaddr.wrapping_add(ehdr.e_phoff - phdr.p_offset)),
+    });
+    let auxv = [
+        (AT_PHDR,   phdr_addr),
+        (AT_PHENT,  ehdr.e_phentsize.into()),
+        (AT_PHNUM,  ehdr.e_phnum.into()),
+        (AT_PAGESZ, mem.page_size()),
+        (AT_BASE,   interp_bias),
+        (AT_FLAGS,  0),
+        (AT_ENTRY,  ehdr.e_entry.wrapping_add(bias)),
+        (AT_UID,    0),
+        (AT_EUID,   0),
+        (AT_GID,    0),
+        (AT_EGID,   0),
+        (AT_HWCAP,  hwcap(&core.config)),
+        (AT_CLKTCK, 100),
+        (AT_SECURE, 0),
+    ];
+    let endian = if ehdr.big_endian() { arm::Endian::Big } else { arm::Endian::Little };
+    if endian == arm::Endian::Big { core.cpsr |= arm::Psr::E; } // BE8: big-endian data, little-endian code
+    tls::map_vectors(&mem, &core.config, endian)?;
+    let exec_stack = config.exec_stack.unwrap_or_else(|| phdrs.iter().find(|phdr| phdr.p_type == PT_GNU_STACK).is_none_or(|phdr| phdr.p_flags & 0x1 != 0)); // PF_X
+    core.registers[13] = init_stack(&mem, config, exec_stack, &auxv, platform(&core.config), endian)?;
+    link.start(&mut core, &mem, entry)?;
+
+    let start_brk = images[0].span.end; // like linux without brk randomization, right after the executable's .bss
+    Ok(Process { mem, cpu: core, entry, images, start_brk, brk: start_brk, files: BTreeMap::new(), exit_status: None, core_dump: config.core_dump.clone(), link })
+}
+
+const PIE_BASE      : u32 = 0x0001_0000; // where ET_DYN executables are loaded: the traditional ET_EXEC link address
+const INTERP_AREA   : std::ops::Range<u32> = 0x4000_0000 .. 0x8000_0000; // shared with linux::mmap2's
+const STACK_TOP     : u32 = 0xBF00_0000; // linux's default 3G/1G TASK_SIZE - 16 MiB
+const PT_GNU_STACK  : u32 = 0x6474_E551;
+
+const AT_NULL       : u32 = 0;
+const AT_PHDR       : u32 = 3;
+const AT_PHENT      : u32 = 4;
+const AT_PHNUM      : u32 = 5;
+const AT_PAGESZ     : u32 = 6;
+const AT_BASE       : u32 = 7;
+const AT_FLAGS      : u32 = 8;
+const AT_ENTRY      : u32 = 9;
+const AT_UID        : u32 = 11;
+const AT_EUID       : u32 = 12;
+const AT_GID        : u32 = 13;
+const AT_EGID       : u32 = 14;
+const AT_PLATFORM   : u32 = 15;
+const AT_HWCAP      : u32 = 16;
+const AT_CLKTCK     : u32 = 17;
+const AT_SECURE     : u32 = 23;
+const AT_RANDOM     : u32 = 25;
+const AT_EXECFN     : u32 = 31;
+
+/// Read and validate the ELF header of `elf` (of any e_type)
+fn read_ehdr(elf: &mut impl ReadAtMut) -> io::Result<Ehdr> {
+    let mut e_ident = [0u8; 16];
+    elf.read_exact_at(&mut e_ident[..], 0)?;
+    if e_ident[0..=3]   != *b"\x7FELF"  { invalid_data!("not an elf file (invalid magic)") } // EI_MAG0..=3
+    if e_ident[4]       != 1            { invalid_data!("only 32-bit elfs are currently supported (see elf64 for 64-bit elf headers)") } // EI_CLASS
+    if e_ident[5] != 1 && e_ident[5] != 2 { invalid_data!("EI_DATA is neither little nor big-endian") } // EI_DATA
+    if e_ident[6]       != 1            { invalid_data!("only EI_VERSION == 1 elfs are currently supported") } // EI_VERSION
+    let _osabi = e_ident[7];
+    let _abiversion = e_ident[8];
+    let _padding = &e_ident[9..];
+
+    let mut ehdr = Ehdr { e_ident, .. Zeroable::zeroed() };
+    elf.read_exact_at(&mut bytes_of_mut(&mut ehdr)[16..], 16)?;
+    if ehdr.big_endian() { ehdr.swap_bytes(); }
+    if ehdr.e_machine   != Machine::EM_ARM { invalid_data!("only ARM elfs can be loaded by elf32, not {} ones (see uvm::run_with to dispatch on e_machine)", Machine::from_e_machine(ehdr.e_machine)) }
+    if ehdr.e_version   != 1    { invalid_data!("only e_version == 1 elfs are currently supported") }
+    // e_entry
+    if ehdr.e_phoff     == 0    { invalid_data!("executable elfs must have a program header table (e_phoff == 0)") }
+    // e_shoff, e_flags
+    if usize::from(ehdr.e_ehsize) < std::mem::size_of_val(&ehdr) { invalid_data!("e_ehsize < size_of::<Ehdr>()") }
+    if ehdr.e_phentsize == 0    { invalid_data!("program header table entries must have nonzero size (e_phentsize == 0)") }
+    if ehdr.e_phnum == 0        { invalid_data!("executables must have at least one entry in their program header table (e_phnum == 0)") }
+    // e_shentsize, e_shnum, e_shstrndx
+    Ok(ehdr)
+}
+
+/// Read the program header table of `elf`
+fn read_phdrs(elf: &mut impl ReadAtMut, ehdr: &Ehdr) -> io::Result<Vec<Phdr>> {
+    let mut phdrs = Vec::new();
+    for iph in 0 .. ehdr.e_phnum {
+        let mut phdr = Phdr::zeroed();
+        let phdr_read = size_of_val(&phdr).min(ehdr.e_phentsize.into());
+        let phdr_off = u64::from(ehdr.e_phoff) + u64::from(iph) * u64::from(ehdr.e_phentsize);
+        elf.read_exact_at(&mut bytes_of_mut(&mut phdr)[..phdr_read], phdr_off)?;
+        if ehdr.big_endian() { phdr.swap_bytes(); }
+        phdrs.push(phdr);
+    }
+    Ok(phdrs)
+}
+
+/// The page aligned range of addresses spanned by the PT_LOAD segments of `phdrs`, before any load bias
+fn load_span(phdrs: &[Phdr]) -> std::ops::Range<u32> {
+    let loads = phdrs.iter().filter(|phdr| phdr.p_type == 1);
+    let start = loads.clone().map(|phdr| phdr.p_vaddr & !0xFFF).min().unwrap_or(0);
+    let end = loads.map(|phdr| (u64::from(phdr.p_vaddr) + u64::from(phdr.p_memsz) + 0xFFF) & !0xFFF).max().unwrap_or(0);
+    start .. u32::try_from(end).unwrap_or(u32::MAX & !0xFFF).max(start)
+}
+
+/// Load the PT_LOAD segments of `elf` into `mem`, `bias` bytes above their p_vaddr, [`arm::Memory::label`]ing them `"{name} PT_LOAD[i]"`
+fn load_segments(mem: &arm::Memory, elf: &mut impl ReadAtMut, phdrs: &[Phdr], bias: u32, name: &str) -> io::Result<()> {
+    for (iph, phdr) in phdrs.iter().enumerate() {
+        match phdr.p_type {
+            0 => continue, // PT_NULL
+            1 => { // PT_LOAD
+                if phdr.p_filesz > phdr.p_memsz { invalid_data!("program segment file size (p_filesz) exceeds memory size (p_memsz)") }
+                let vaddr = phdr.p_vaddr.wrapping_add(bias);
+                let io_size = phdr.p_filesz;
+                let zero_size = phdr.p_memsz - phdr.p_filesz;
+
+                let mut flags = arm::MemoryFlags::NONE;
+                if phdr.p_flags & 0x1 != 0 { flags |= arm::MemoryFlags::EXECUTE; } // PF_X
+                if phdr.p_flags & 0x2 != 0 { flags |= arm::MemoryFlags::WRITE;   } // PF_W
+                if phdr.p_flags & 0x4 != 0 { flags |= arm::MemoryFlags::READ;    } // PF_R
+
+                mem.init_copy_io(vaddr, flags, elf, phdr.p_offset.into(), io_size)?;
+                mem.init_zero(vaddr.wrapping_add(io_size), flags, zero_size)?; // .bss & co.
+                let label = if name.is_empty() { format!("PT_LOAD[{}]", iph) } else { format!("{} PT_LOAD[{}]", name, iph) };
+                mem.label(vaddr .. vaddr.saturating_add(phdr.p_memsz), label); // for fault messages
+            },
+            // PT_DYNAMIC & PT_INTERP are handled once every PT_LOAD is loaded, by run
+            // ...
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
+/// Read the path of the dynamic linker requested by the PT_INTERP segment `interp`
+fn read_interp(elf: &mut impl ReadAtMut, interp: &Phdr) -> io::Result<String> {
+    if interp.p_filesz == 0 || interp.p_filesz > 4096 { invalid_data!("PT_INTERP path of {} bytes", interp.p_filesz) }
+    let mut path = vec![0u8; interp.p_filesz as usize];
+    elf.read_exact_at(&mut path[..], interp.p_offset.into())?;
+    let Some(nul) = path.iter().position(|b| *b == 0) else { invalid_data!("PT_INTERP path isn't NUL terminated") };
+    path.truncate(nul);
+    String::from_utf8(path).or_else(|_| invalid_data!("PT_INTERP path isn't UTF-8"))
+}
+
+/// The AT_HWCAP bits linux would report for `config`
+fn hwcap(config: &arm::CpuConfig) -> u32 {
+    const HWCAP_SWP         : u32 = 1 << 0;
+    const HWCAP_HALF        : u32 = 1 << 1;
+    const HWCAP_THUMB       : u32 = 1 << 2;
+    const HWCAP_FAST_MULT   : u32 = 1 << 4;
+    const HWCAP_VFP         : u32 = 1 << 6;
+    const HWCAP_EDSP        : u32 = 1 << 7;
+    const HWCAP_NEON        : u32 = 1 << 12;
+    const HWCAP_VFPV3       : u32 = 1 << 13;
+    const HWCAP_TLS         : u32 = 1 << 15;
+
+    let mut hwcap = HWCAP_HALF | HWCAP_THUMB | HWCAP_FAST_MULT;
+    if config.features.contains(arm::Features::SWP) { hwcap |= HWCAP_SWP }
+    if config.features.contains(arm::Features::VFP) { hwcap |= HWCAP_VFP }
+    if config.features.contains(arm::Features::VFP) && config.arch >= arm::Arch::V7A { hwcap |= HWCAP_VFPV3 }
+    if config.features.contains(arm::Features::NEON) { hwcap |= HWCAP_NEON }
+    if config.arch >= arm::Arch::V5TE { hwcap |= HWCAP_EDSP }
+    if config.arch >= arm::Arch::V6 { hwcap |= HWCAP_TLS } // TPIDRURO
+    hwcap
+}
+
+/// The AT_PLATFORM string linux would report for `config` (ELF_PLATFORM)
+fn platform(config: &arm::CpuConfig) -> &'static str {
+    match config.arch {
+        arm::Arch::V4T  => "v4l",
+        arm::Arch::V5TE => "v5l",
+        arm::Arch::V6   => "v6l",
+        arm::Arch::V7A  => "v7l",
+    }
+}
+
+/// Map [`LoadConfig::stack_size`] bytes of stack below [`STACK_TOP`] (executable too if `exec`), and lay out the initial process stack the way linux's `create_elf_tables` does.  From the top down:
+/// the strings of `AT_EXECFN`, `envp` & `argv`, then AT_PLATFORM's string, AT_RANDOM's bytes, and finally (16 byte aligned)
+/// `argc`, the `argv` & `envp` pointers (each NULL terminated), and `auxv` itself (AT_NULL terminated.)
+/// Returns the initial stack pointer, which points at `argc`.
+fn init_stack(mem: &arm::Memory, config: &LoadConfig, exec: bool, auxv: &[(u32, u32)], platform: &str, endian: arm::Endian) -> io::Result<u32> {
+    use std::hash::{BuildHasher, Hasher};
+
+    let size = match config.stack_size.checked_add(0xFFF) { Some(size) if size < STACK_TOP - INTERP_AREA.end => size & !0xFFF, _ => invalid_data!("stack_size 0x{:x} is too large", config.stack_size) };
+    let stack = STACK_TOP - size .. STACK_TOP;
+    mem.map_stack(stack.clone(), 0x1000);
+    if exec { mem.map(stack.clone(), arm::MemoryFlags::READ | arm::MemoryFlags::WRITE | arm::MemoryFlags::EXECUTE); } // keeping map_stack's guard & labels
+    let mut sp = stack.end;
+    let mut push_bytes = |bytes: &[u8]| -> io::Result<u32> {
+        let Some(below) = sp.checked_sub(bytes.len() as u32).filter(|below| *below >= stack.end - size / 4) else { invalid_data!("arguments & environment exceed a quarter of the stack") }; // like linux's limit
+        sp = below;
+        write_guest(mem, sp, bytes)?;
+        Ok(sp)
+    };
+    let mut push_str = |s: &str| push_bytes(&[s.as_bytes(), b"\0"].concat());
+
+    let empty_argv0 = [String::new()];
+    let args = if config.args.is_empty() { &empty_argv0[..] } else { &config.args[..] };
+    let execfn = push_str(&args[0])?;
+    let envp = config.env.iter().rev().map(|var| push_str(var)).collect::<io::Result<Vec<_>>>()?;
+    let argv = args.iter().rev().map(|arg| push_str(arg)).collect::<io::Result<Vec<_>>>()?;
+    let platform = push_str(platform)?;
+    let mut random = [0u8; 16];
+    for half in random.chunks_mut(8) { half.copy_from_slice(&std::collections::hash_map::RandomState::new().build_hasher().finish().to_le_bytes()) }
+    let random = push_bytes(&random)?;
+
+    let mut words = vec![args.len() as u32]; // argc
+    words.extend(argv.iter().rev().copied().chain([0]));
+    words.extend(envp.iter().rev().copied().chain([0]));
+    for (key, value) in auxv.iter().copied().chain([(AT_PLATFORM, platform), (AT_RANDOM, random), (AT_EXECFN, execfn), (AT_NULL, 0)]) {
+        words.extend([key, value]);
+    }
+    let sp = (random - 4 * words.len() as u32) & !0xF;
+    let bytes : Vec<u8> = words.iter().flat_map(|word| if endian == arm::Endian::Big { word.to_be_bytes() } else { word.to_le_bytes() }).collect();
+    write_guest(mem, sp, &bytes)?;
+    Ok(sp)
+}
+
+/// Read loaded ELF data (e.g. the dynamic section) back out of guest memory, regardless of its page flags
+fn read_guest(mem: &arm::Memory, addr: u32, bytes: &mut [u8]) -> io::Result<()> {
+    mem.try_read_bytes(addr, arm::MemoryFlags::NONE, bytes).map_err(|fault| io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf::run: {}", fault)))
+}
+
+/// Write ELF data (e.g. the initial stack) into guest memory, regardless of its page flags
+fn write_guest(mem: &arm::Memory, addr: u32, bytes: &[u8]) -> io::Result<()> {
+    mem.try_write_bytes(addr, arm::MemoryFlags::NONE, bytes).map_err(|fault| io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf::run: {}", fault)))
+}
+
+/// Read a NUL terminated string (e.g. from DT_STRTAB) out of guest memory
+fn read_guest_cstr(mem: &arm::Memory, addr: u32) -> io::Result<String> {
+    let mut string = Vec::new();
+    loop {
+        let mut byte = 0u8;
+        read_guest(mem, addr.wrapping_add(string.len() as u32), bytes_of_mut(&mut byte))?;
+        if byte == 0 { return Ok(String::from_utf8_lossy(&string).into_owned()) }
+        if string.len() >= 4096 { invalid_data!("unterminated string at 0x{:08x}", addr) }
+        string.push(byte);
+    }
+}
@@ -0,0 +1,155 @@
+use super::*;
+
+// References:
+// - Addenda to, and Errata in, the ABI for the Arm Architecture (ABI-addenda) §3 Build Attributes: https://github.com/ARM-software/abi-aa/blob/main/addenda32/addenda32.rst#build-attributes
+//
+// Limitations:
+// - Only the "aeabi" subsection's file scope (Tag_File) attributes: per section & per symbol attributes are skipped
+// - Only the attributes that pick a CpuConfig are kept
+
+
+
+/// The build attributes of an ELF's `.ARM.attributes` section (SHT_ARM_ATTRIBUTES), describing what the code was compiled for
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attributes {
+    /// Tag_CPU_name (5): e.g. `"cortex-a8"` or `"ARM926EJ-S"`
+    pub cpu_name:           Option<String>,
+    /// Tag_CPU_arch (6): 1 = v4, 2 = v4T, 3 = v5T, 4 = v5TE, 5 = v5TEJ, 6 = v6, 7 = v6KZ, 8 = v6T2, 9 = v6K, 10 = v7, 11 = v6-M, 12 = v6S-M, 13 = v7E-M, 14 = v8-A, ...
+    pub cpu_arch:           Option<u64>,
+    /// Tag_CPU_arch_profile (7): `b'A'`pplication, `b'R'`eal-time, `b'M'`icrocontroller, `b'S'` (A or R), or 0 for pre-v7 architectures
+    pub cpu_arch_profile:   Option<u64>,
+    /// Tag_ARM_ISA_use (8): 0 = no ARM code, 1 = ARM code
+    pub arm_isa_use:        Option<u64>,
+    /// Tag_THUMB_ISA_use (9): 0 = no Thumb code, 1 = 16-bit Thumb, 2 = Thumb-2, 3 = whatever Tag_CPU_arch has
+    pub thumb_isa_use:      Option<u64>,
+    /// Tag_FP_arch (10): 0 = none, 1 = VFPv1, 2 = VFPv2, 3 = VFPv3, 4 = VFPv3-D16, 5 = VFPv4, 6 = VFPv4-D16, 7 = ARMv8 FP, 8 = ARMv8 FP-D16
+    pub fp_arch:            Option<u64>,
+    /// Tag_Advanced_SIMD_arch (12): 0 = none, 1 = NEON, 2 = NEON with fused multiply-accumulate, 3 = ARMv8 NEON, 4 = ARMv8.1 NEON
+    pub advanced_simd_arch: Option<u64>,
+}
+
+impl Attributes {
+    pub const SHT_ARM_ATTRIBUTES : u32 = 0x7000_0003;
+
+    /// Read the first SHT_ARM_ATTRIBUTES section of `file`, if it has one
+    pub fn read(file: &File, elf: &mut impl ReadAtMut) -> io::Result<Option<Self>> {
+        let Some(shdr) = file.shdrs.iter().find(|shdr| shdr.sh_type == Self::SHT_ARM_ATTRIBUTES) else { return Ok(None) };
+        Self::parse(&file.section_data(elf, shdr)?, file.ehdr.big_endian()).map(Some)
+    }
+
+    /// Parse the contents of a `.ARM.attributes` section, whose lengths are big-endian if `big_endian`
+    pub fn parse(section: &[u8], big_endian: bool) -> io::Result<Self> {
+        let mut attributes = Self::default();
+        match section.first() {
+            None        => return Ok(attributes),
+            Some(b'A')  => {},
+            Some(v)     => return Err(invalid(format!("unsupported format-version {:?}", char::from(*v)))),
+        }
+        let mut rest = &section[1..];
+        while !rest.is_empty() {
+            let (subsection, after) = split_length(rest, 0, big_endian)?;
+            rest = after;
+            let nul = subsection.iter().position(|b| *b == 0).ok_or_else(|| invalid("unterminated vendor name"))?;
+            if &subsection[..nul] != b"aeabi" { continue } // e.g. "gnu": toolchain specific
+            let mut subsubsections = &subsection[nul+1 ..];
+            while !subsubsections.is_empty() {
+                let mut after_tag = subsubsections;
+                let tag = uleb(&mut after_tag)?;
+                let (subsubsection, after) = split_length(subsubsections, subsubsections.len() - after_tag.len(), big_endian)?;
+                subsubsections = after;
+                if tag == 1 { attributes.parse_file(subsubsection)?; } // Tag_File (Tag_Section = 2 and Tag_Symbol = 3 apply to parts of the file only)
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn parse_file(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let tag = uleb(&mut data)?;
+            match tag {
+                4 | 5 | 67  => { // NTBS: Tag_CPU_raw_name, Tag_CPU_name, Tag_conformance
+                    let value = ntbs(&mut data)?;
+                    if tag == 5 { self.cpu_name = Some(value.into()); }
+                },
+                32          => { uleb(&mut data)?; ntbs(&mut data)?; }, // Tag_compatibility: flag, vendor name
+                65          => { ntbs(&mut data)?; }, // Tag_also_compatible_with
+                _ if tag < 32 || tag % 2 == 0 => {
+                    let value = uleb(&mut data)?;
+                    match tag {
+                        6   => self.cpu_arch            = Some(value),
+                        7   => self.cpu_arch_profile    = Some(value),
+                        8   => self.arm_isa_use         = Some(value),
+                        9   => self.thumb_isa_use       = Some(value),
+                        10  => self.fp_arch             = Some(value),
+                        12  => self.advanced_simd_arch  = Some(value),
+                        _   => {},
+                    }
+                },
+                _           => { ntbs(&mut data)?; }, // unknown odd tags >= 32 are NTBS
+            }
+        }
+        Ok(())
+    }
+
+    /// The [`arm::CpuConfig`] able to run code with these attributes: the oldest [`arm::Arch`] implementing Tag_CPU_arch, with VFP & NEON if they're used.
+    /// Fails if the code needs something [`arm::Cpu`] doesn't implement (M-profile or ARMv8 code, VFPv4 or newer, ...)
+    pub fn cpu_config(&self) -> io::Result<arm::CpuConfig> {
+        let name = self.cpu_name.as_deref().map_or(String::new(), |name| format!(" ({})", name));
+        if self.cpu_arch_profile == Some(u64::from(b'M')) { return Err(invalid(format!("M-profile code{} isn't supported", name))) }
+        let mut arch = match self.cpu_arch.unwrap_or(10) {
+            0 ..= 2     => arm::Arch::V4T,
+            3 ..= 5     => arm::Arch::V5TE,
+            6 | 7 | 9   => arm::Arch::V6,
+            8 | 10      => arm::Arch::V7A, // v6T2 is grouped with v7, see arm::Arch
+            11 ..= 13   => return Err(invalid(format!("M-profile code (Tag_CPU_arch {}){} isn't supported", self.cpu_arch.unwrap_or(0), name))),
+            other       => return Err(invalid(format!("ARMv8 and newer code (Tag_CPU_arch {}){} isn't supported", other, name))),
+        };
+        if self.thumb_isa_use == Some(2) { arch = arch.max(arm::Arch::V7A) } // Thumb-2
+
+        let mut config = arm::CpuConfig::new(arch);
+        match self.fp_arch.unwrap_or(0) {
+            0           => {},
+            1 | 2       => config.features |= arm::Features::VFP,
+            3 | 4 if arch >= arm::Arch::V7A => config.features |= arm::Features::VFP,
+            3 | 4       => return Err(invalid(format!("VFPv3 (Tag_FP_arch {}) needs ARMv7, but Tag_CPU_arch is {}", self.fp_arch.unwrap_or(0), self.cpu_arch.unwrap_or(0)))),
+            other       => return Err(invalid(format!("VFPv4 and newer floating point (Tag_FP_arch {}){} isn't supported: fused multiply-accumulate isn't implemented", other, name))),
+        }
+        match self.advanced_simd_arch.unwrap_or(0) {
+            0           => {},
+            1 if arch >= arm::Arch::V7A => config.features |= arm::Features::VFP | arm::Features::NEON,
+            1           => return Err(invalid(format!("NEON needs ARMv7, but Tag_CPU_arch is {}", self.cpu_arch.unwrap_or(0)))),
+            other       => return Err(invalid(format!("NEONv2 and newer SIMD (Tag_Advanced_SIMD_arch {}){} isn't supported: fused multiply-accumulate isn't implemented", other, name))),
+        }
+        Ok(config)
+    }
+}
+
+/// Split a (sub)subsection off the front of `data`, returning its contents and what follows it.
+/// Its 4 byte length follows a `header` bytes long tag, and includes both.
+fn split_length(data: &[u8], header: usize, big_endian: bool) -> io::Result<(&[u8], &[u8])> {
+    let Some(len) = data.get(header .. header + 4) else { return Err(invalid("truncated length")) };
+    let len = u32::from_le_bytes(len.try_into().unwrap());
+    let len = if big_endian { len.swap_bytes() } else { len } as usize;
+    if len < header + 4 || len > data.len() { return Err(invalid(format!("length {} is out of bounds", len))) }
+    Ok((&data[header + 4 .. len], &data[len..]))
+}
+
+fn uleb(data: &mut &[u8]) -> io::Result<u64> {
+    let (mut value, mut shift) = (0u64, 0);
+    loop {
+        let (byte, rest) = data.split_first().ok_or_else(|| invalid("truncated ULEB128"))?;
+        *data = rest;
+        if shift < 64 { value |= u64::from(byte & 0x7F) << shift; }
+        shift += 7;
+        if byte & 0x80 == 0 { return Ok(value) }
+    }
+}
+
+fn ntbs<'d>(data: &mut &'d [u8]) -> io::Result<&'d str> {
+    let nul = data.iter().position(|b| *b == 0).ok_or_else(|| invalid("unterminated string"))?;
+    let s = std::str::from_utf8(&data[..nul]).map_err(|_| invalid("string isn't UTF-8"))?;
+    *data = &data[nul+1 ..];
+    Ok(s)
+}
+
+fn invalid(reason: impl std::fmt::Display) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf32::Attributes: {}", reason)) }
@@ -0,0 +1,116 @@
+use super::*;
+
+use std::io::Write;
+
+use bytemuck::bytes_of;
+
+// References:
+// - linux's fs/binfmt_elf.c (elf_core_dump) and arch/arm/include/asm/elf.h (elf_gregset_t)
+// - gdb's arm-linux-tdep.c (ARM_LINUX_SIZEOF_PRSTATUS = 148)
+//
+// Limitations:
+// - NT_PRSTATUS only: no NT_PRPSINFO (process name), NT_AUXV (gdb can't find a PIE's or ld.so's load bias without the executable), NT_ARM_VFP, or NT_FILE
+// - A single thread
+// - MMIO regions are skipped, and regions without MemoryFlags::READ are dumped without contents (like linux's PROT_NONE mappings)
+
+
+
+pub const SIGILL    : u32 = 4;
+pub const SIGTRAP   : u32 = 5;
+pub const SIGSEGV   : u32 = 11;
+
+impl Process {
+    /// Write an ELF32 ET_CORE file of the process, as killed by `signal` (e.g. [`fault_signal`]) at its current registers, to `out`.
+    /// Open it with the executable in `gdb-multiarch`: `gdb-multiarch a.out core`.
+    pub fn write_core(&self, out: &mut impl Write, signal: u32) -> io::Result<()> {
+        let big_endian = self.cpu.cpsr.e();
+        let regions = self.mem.regions().into_iter().filter(|region| !region.mmio).collect::<Vec<_>>();
+
+        let mut pr_reg = [0u32; 18]; // r0 ..= r15, cpsr, orig_r0
+        pr_reg[..16].copy_from_slice(&self.cpu.registers);
+        pr_reg[15] = self.cpu.next_instruction_addr();
+        pr_reg[16] = self.cpu.cpsr.bits();
+        pr_reg[17] = self.cpu.registers[0];
+        let mut prstatus = [0u32; 37]; // elf_prstatus: 148 bytes on ARM
+        prstatus[0] = signal; // pr_info.si_signo
+        prstatus[3] = signal; // pr_cursig (a short, followed by padding)
+        prstatus[6] = 1; // pr_pid
+        prstatus[18 .. 36].copy_from_slice(&pr_reg);
+        if big_endian { prstatus[3] <<= 16; } // put the pr_cursig short first
+        let mut note = Vec::new();
+        for word in [5, 148, NT_PRSTATUS] { note.extend_from_slice(&word_bytes(word, big_endian)); } // namesz, descsz, type
+        note.extend_from_slice(b"CORE\0\0\0\0");
+        for word in prstatus { note.extend_from_slice(&word_bytes(word, big_endian)); }
+
+        let phnum = 1 + regions.len();
+        if phnum >= 0xFFFF { return Err(invalid(format!("{} memory regions is too many to dump", regions.len()))) }
+        let note_offset = size_of_val(&Ehdr::zeroed()) + phnum * size_of_val(&Phdr::zeroed());
+        let mut offset = (note_offset + note.len() + 0xFFF) as u32 & !0xFFF;
+
+        let mut ehdr = Ehdr {
+            e_ident:        *b"\x7FELF\x01\x01\x01\0\0\0\0\0\0\0\0\0",
+            e_type:         4, // ET_CORE
+            e_machine:      Machine::EM_ARM,
+            e_version:      1,
+            e_phoff:        size_of_val(&Ehdr::zeroed()) as u32,
+            e_ehsize:       size_of_val(&Ehdr::zeroed()) as u16,
+            e_phentsize:    size_of_val(&Phdr::zeroed()) as u16,
+            e_phnum:        phnum as u16,
+            .. Zeroable::zeroed()
+        };
+        if big_endian { ehdr.e_ident[5] = 2; ehdr.swap_bytes(); } // EI_DATA = ELFDATA2MSB
+        out.write_all(bytes_of(&ehdr))?;
+
+        let mut phdrs = vec![Phdr { p_type: 4, p_offset: note_offset as u32, p_filesz: note.len() as u32, p_align: 4, .. Zeroable::zeroed() }]; // PT_NOTE
+        for region in &regions {
+            let memsz = (region.end - u64::from(region.start)) as u32;
+            let filesz = if region.flags.contains(arm::MemoryFlags::READ) { memsz } else { 0 };
+            let p_flags = [(arm::MemoryFlags::READ, 4), (arm::MemoryFlags::WRITE, 2), (arm::MemoryFlags::EXECUTE, 1)].iter() // PF_R, PF_W, PF_X
+                .filter(|(flag, _)| region.flags.contains(*flag)).fold(0, |p_flags, (_, pf)| p_flags | pf);
+            phdrs.push(Phdr { p_type: 1, p_offset: offset, p_vaddr: region.start, p_filesz: filesz, p_memsz: memsz, p_flags, p_align: 0x1000, .. Zeroable::zeroed() }); // PT_LOAD
+            offset = offset.checked_add(filesz).ok_or_else(|| invalid("core file would exceed 4 GiB"))?;
+        }
+        for mut phdr in phdrs.iter().copied() {
+            if big_endian { phdr.swap_bytes(); }
+            out.write_all(bytes_of(&phdr))?;
+        }
+
+        out.write_all(&note)?;
+        out.write_all(&vec![0u8; phdrs.get(1).map_or(0, |load| load.p_offset as usize - note_offset - note.len())])?;
+        for (region, phdr) in regions.iter().zip(&phdrs[1..]) {
+            if phdr.p_filesz == 0 { continue }
+            self.mem.dump_raw(region.start ..= region.start + (phdr.p_filesz - 1), out)?; // inclusive, as [vectors] ends at 4 GiB
+        }
+        Ok(())
+    }
+
+    /// If [`LoadConfig::core_dump`] is set, write a core file there, describing how that went for [`Process::step`]'s errors
+    pub(super) fn dump_core(&self, signal: u32) -> String {
+        let Some(path) = self.core_dump.as_ref() else { return String::new() };
+        let written = std::fs::File::create(path).and_then(|file| {
+            let mut out = io::BufWriter::new(file);
+            self.write_core(&mut out, signal)?;
+            out.flush()
+        });
+        match written {
+            Ok(())      => format!(" (core dumped to `{}`)", path.display()),
+            Err(err)    => format!(" (unable to dump core to `{}`: {})", path.display(), err),
+        }
+    }
+}
+
+/// The signal linux would kill a process with for `fault`
+pub fn fault_signal(fault: &arm::Fault) -> u32 {
+    match fault {
+        arm::Fault::Undefined { .. }        => SIGILL,
+        arm::Fault::PrefetchAbort { .. }    => SIGSEGV,
+        arm::Fault::DataAbort { .. }        => SIGSEGV,
+        arm::Fault::StackOverflow { .. }    => SIGSEGV,
+    }
+}
+
+const NT_PRSTATUS : u32 = 1;
+
+fn word_bytes(word: u32, big_endian: bool) -> [u8; 4] { if big_endian { word.to_be_bytes() } else { word.to_le_bytes() } }
+
+fn invalid(reason: impl std::fmt::Display) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf32::coredump: {}", reason)) }
@@ -0,0 +1,285 @@
+use super::*;
+
+use std::fmt::{self, Display, Formatter};
+
+// References:
+// - DWARF 5 §6.2 Line Number Information: https://dwarfstd.org/doc/DWARF5.pdf
+// - DWARF 4 §6.2 (the file & directory tables of versions 2 ..= 4): https://dwarfstd.org/doc/DWARF4.pdf
+//
+// Limitations:
+// - Only .debug_line: no .debug_info, so no inlined frames, and DWARF < 5 relative paths lack their compilation directory
+// - DW_FORM_strx* file names (which need .debug_str_offsets & a unit's DW_AT_str_offsets_base) aren't supported
+// - VLIW op_index is ignored (ARM doesn't use it)
+
+
+
+/// The address → source line mapping of an ELF's `.debug_line` section (built with `-g`), at its linked addresses
+#[derive(Clone, Debug, Default)]
+pub struct LineTable {
+    files:  Vec<String>,
+    rows:   Vec<LineRow>, // sorted by address, each sequence ending in an end_sequence row
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LineRow {
+    addr:           u32,
+    file:           u32, // index into LineTable::files
+    line:           u32,
+    column:         u32,
+    end_sequence:   bool,
+}
+
+/// A source location, as found by [`LineTable::location`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceLocation<'t> {
+    pub file:   &'t str,
+    /// 1-based, or 0 if the instruction has no line
+    pub line:   u32,
+    /// 1-based, or 0 if unknown
+    pub column: u32,
+}
+
+impl LineTable {
+    /// Read `.debug_line` (and the `.debug_line_str` & `.debug_str` sections it refers to) from `elf`, if present
+    pub fn read(file: &File, elf: &mut impl ReadAtMut) -> io::Result<Option<Self>> {
+        let Some(debug_line) = file.section_by_name(".debug_line") else { return Ok(None) };
+        let debug_line = file.section_data(elf, debug_line)?;
+        let mut section = |name| match file.section_by_name(name) { Some(shdr) => file.section_data(elf, shdr), None => Ok(Vec::new()) };
+        let (debug_line_str, debug_str) = (section(".debug_line_str")?, section(".debug_str")?);
+        Self::parse(&debug_line, &debug_line_str, &debug_str).map(Some)
+    }
+
+    /// Parse every line number program of `debug_line`
+    pub fn parse(debug_line: &[u8], debug_line_str: &[u8], debug_str: &[u8]) -> io::Result<Self> {
+        let mut table = Self::default();
+        let mut units = Reader { data: debug_line, pos: 0 };
+        while units.pos < debug_line.len() {
+            let (unit, dwarf64) = units.initial_length()?;
+            table.parse_unit(Reader { data: units.bytes(unit)?, pos: 0 }, dwarf64, debug_line_str, debug_str)?;
+        }
+        table.rows.sort_by_key(|row| (row.addr, !row.end_sequence)); // a sequence ending where the next begins ends first
+        Ok(table)
+    }
+
+    /// The source location of the instruction at the (linked) address `addr`
+    pub fn location(&self, addr: u32) -> Option<SourceLocation<'_>> {
+        let i = self.rows.partition_point(|row| row.addr <= addr).checked_sub(1)?;
+        let row = self.rows[i];
+        if row.end_sequence { return None }
+        Some(SourceLocation { file: self.files.get(row.file as usize).map_or("", |file| file.as_str()), line: row.line, column: row.column })
+    }
+
+    fn parse_unit<'d>(&mut self, mut r: Reader<'d>, dwarf64: bool, debug_line_str: &'d [u8], debug_str: &'d [u8]) -> io::Result<()> {
+        let version = r.u16()?;
+        if !(2 ..= 5).contains(&version) { return Err(invalid(format!("unsupported .debug_line version {}", version))) }
+        let address_size = if version >= 5 { let size = r.u8()?; let _segment_selector_size = r.u8()?; size } else { 4 };
+        let header_length = r.offset(dwarf64)?;
+        let mut program = Reader { data: r.data, pos: r.pos.saturating_add(header_length as usize) };
+
+        let min_inst_length = u32::from(r.u8()?);
+        if version >= 4 { let _max_ops_per_inst = r.u8()?; }
+        let _default_is_stmt = r.u8()?;
+        let line_base = i64::from(r.u8()? as i8);
+        let line_range = r.u8()?;
+        let opcode_base = r.u8()?;
+        if line_range == 0 { return Err(invalid("line_range of 0")) }
+        let standard_opcode_lengths = r.bytes(usize::from(opcode_base.saturating_sub(1)))?.to_vec();
+
+        // directories & files
+        let first_file = self.files.len() as u32;
+        let mut dirs = Vec::new();
+        if version >= 5 {
+            let strs = Strs { dwarf64, debug_line_str, debug_str };
+            for entry in r.entries(strs)? { dirs.push(entry.path); }
+            for entry in r.entries(strs)? { self.files.push(join(dirs.get(entry.dir as usize).map_or("", |d| d.as_str()), &entry.path)); }
+        } else {
+            dirs.push(String::new()); // 0: the compilation directory, which only .debug_info knows
+            loop { let dir = r.cstr()?; if dir.is_empty() { break } dirs.push(dir.into()); }
+            self.files.push(String::new()); // file numbers are 1-based before DWARF 5
+            loop {
+                let path = r.cstr()?;
+                if path.is_empty() { break }
+                let (dir, _mtime, _len) = (r.uleb()?, r.uleb()?, r.uleb()?);
+                self.files.push(join(dirs.get(dir as usize).map_or("", |d| d.as_str()), path));
+            }
+        }
+
+        // the line number program
+        let new_state = || LineRow { addr: 0, file: if version >= 5 { 0 } else { 1 }, line: 1, column: 0, end_sequence: false };
+        let mut state = new_state();
+        let rows = &mut self.rows;
+        let mut emit = |state: &LineRow| rows.push(LineRow { file: first_file.wrapping_add(state.file), ..*state });
+        while program.pos < program.data.len() {
+            let opcode = program.u8()?;
+            if opcode >= opcode_base { // special opcode
+                let adjusted = opcode - opcode_base;
+                state.addr = state.addr.wrapping_add(u32::from(adjusted / line_range) * min_inst_length);
+                state.line = (i64::from(state.line) + line_base + i64::from(adjusted % line_range)) as u32;
+                emit(&state);
+                continue;
+            }
+            match opcode {
+                0 => { // extended opcode
+                    let len = program.uleb()? as usize;
+                    let mut ext = Reader { data: program.bytes(len)?, pos: 0 };
+                    match ext.u8()? {
+                        1 => { state.end_sequence = true; emit(&state); state = new_state(); }, // DW_LNE_end_sequence
+                        2 => state.addr = ext.uint(usize::from(address_size))? as u32, // DW_LNE_set_address
+                        3 => { // DW_LNE_define_file (DWARF < 5)
+                            let path = ext.cstr()?;
+                            let dir = ext.uleb()?;
+                            self.files.push(join(dirs.get(dir as usize).map_or("", |d| d.as_str()), path));
+                        },
+                        _ => {}, // DW_LNE_set_discriminator, vendor extensions, ...
+                    }
+                },
+                1 => emit(&state), // DW_LNS_copy
+                2 => state.addr = state.addr.wrapping_add((program.uleb()? as u32).wrapping_mul(min_inst_length)), // DW_LNS_advance_pc
+                3 => state.line = i64::from(state.line).wrapping_add(program.sleb()?) as u32, // DW_LNS_advance_line
+                4 => state.file = program.uleb()? as u32, // DW_LNS_set_file
+                5 => state.column = program.uleb()? as u32, // DW_LNS_set_column
+                8 => state.addr = state.addr.wrapping_add(u32::from((255 - opcode_base) / line_range) * min_inst_length), // DW_LNS_const_add_pc
+                9 => state.addr = state.addr.wrapping_add(u32::from(program.u16()?)), // DW_LNS_fixed_advance_pc
+                _ => for _ in 0 .. standard_opcode_lengths[usize::from(opcode) - 1] { program.uleb()?; }, // DW_LNS_negate_stmt, set_basic_block, set_prologue_end, set_isa, ...
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for SourceLocation<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if self.column != 0 { write!(f, ":{}", self.column)?; }
+        Ok(())
+    }
+}
+
+/// DWARF 5 directory & file entries, as far as they're needed
+struct Entry {
+    path:   String,
+    dir:    u64,
+}
+
+/// The string sections DWARF 5 entries may point into
+#[derive(Clone, Copy)]
+struct Strs<'s> {
+    dwarf64:        bool,
+    debug_line_str: &'s [u8],
+    debug_str:      &'s [u8],
+}
+
+struct Reader<'d> {
+    data:   &'d [u8],
+    pos:    usize,
+}
+
+impl<'d> Reader<'d> {
+    fn bytes(&mut self, n: usize) -> io::Result<&'d [u8]> {
+        let bytes = self.data.get(self.pos .. self.pos.saturating_add(n)).ok_or_else(|| invalid("truncated .debug_line"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> { Ok(self.bytes(1)?[0]) }
+    fn u16(&mut self) -> io::Result<u16> { Ok(self.uint(2)? as u16) }
+
+    /// A little-endian unsigned integer of `n` bytes
+    fn uint(&mut self, n: usize) -> io::Result<u64> {
+        if n > 8 { return Err(invalid(format!("{} byte integer", n))) }
+        Ok(self.bytes(n)?.iter().rev().fold(0, |value, byte| value << 8 | u64::from(*byte)))
+    }
+
+    fn uleb(&mut self) -> io::Result<u64> {
+        let (mut value, mut shift) = (0u64, 0);
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 { value |= u64::from(byte & 0x7F) << shift; }
+            shift += 7;
+            if byte & 0x80 == 0 { return Ok(value) }
+        }
+    }
+
+    fn sleb(&mut self) -> io::Result<i64> {
+        let (mut value, mut shift) = (0i64, 0);
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 { value |= i64::from(byte & 0x7F) << shift; }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 { value |= -1 << shift; }
+                return Ok(value)
+            }
+        }
+    }
+
+    fn cstr(&mut self) -> io::Result<&'d str> {
+        let rest = &self.data[self.pos.min(self.data.len()) ..];
+        let nul = rest.iter().position(|b| *b == 0).ok_or_else(|| invalid("unterminated string"))?;
+        self.pos += nul + 1;
+        std::str::from_utf8(&rest[..nul]).map_err(|_| invalid("string isn't UTF-8"))
+    }
+
+    /// unit_length: the length of a unit, and if it's in the 64-bit DWARF format
+    fn initial_length(&mut self) -> io::Result<(usize, bool)> {
+        match self.uint(4)? {
+            0xFFFF_FFFF             => Ok((self.uint(8)? as usize, true)),
+            len if len < 0xFFFF_FFF0 => Ok((len as usize, false)),
+            reserved                => Err(invalid(format!("reserved unit_length 0x{:08x}", reserved))),
+        }
+    }
+
+    fn offset(&mut self, dwarf64: bool) -> io::Result<u64> { self.uint(if dwarf64 { 8 } else { 4 }) }
+
+    /// A DWARF 5 directory or file name table
+    fn entries(&mut self, strs: Strs<'d>) -> io::Result<Vec<Entry>> {
+        let format_count = self.u8()?;
+        let mut format = Vec::new();
+        for _ in 0 .. format_count { format.push((self.uleb()?, self.uleb()?)); }
+        let count = self.uleb()?;
+        let mut entries = Vec::new();
+        for _ in 0 .. count {
+            let mut entry = Entry { path: String::new(), dir: 0 };
+            for &(content, form) in &format {
+                let value = self.form(form, strs)?;
+                match (content, value) {
+                    (1, Value::Str(path))   => entry.path = path.into(), // DW_LNCT_path
+                    (2, Value::Uint(dir))   => entry.dir = dir, // DW_LNCT_directory_index
+                    _                       => {}, // DW_LNCT_timestamp, DW_LNCT_size, DW_LNCT_MD5, vendor extensions
+                }
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    fn form(&mut self, form: u64, strs: Strs<'d>) -> io::Result<Value<'d>> {
+        let str_at = |section: &'d [u8], offset: u64| Reader { data: section, pos: offset as usize }.cstr();
+        Ok(match form {
+            0x08 => Value::Str(self.cstr()?), // DW_FORM_string
+            0x1F => Value::Str(str_at(strs.debug_line_str, self.offset(strs.dwarf64)?)?), // DW_FORM_line_strp
+            0x0E => Value::Str(str_at(strs.debug_str, self.offset(strs.dwarf64)?)?), // DW_FORM_strp
+            0x0B => Value::Uint(self.uint(1)?), // DW_FORM_data1
+            0x05 => Value::Uint(self.uint(2)?), // DW_FORM_data2
+            0x06 => Value::Uint(self.uint(4)?), // DW_FORM_data4
+            0x07 => Value::Uint(self.uint(8)?), // DW_FORM_data8
+            0x0F => Value::Uint(self.uleb()?),  // DW_FORM_udata
+            0x1E => { self.bytes(16)?; Value::Other }, // DW_FORM_data16 (MD5s)
+            0x09 => { let len = self.uleb()? as usize; self.bytes(len)?; Value::Other }, // DW_FORM_block
+            other => return Err(invalid(format!("unsupported DW_FORM 0x{:x} in a file name table", other))),
+        })
+    }
+}
+
+enum Value<'d> {
+    Str(&'d str),
+    Uint(u64),
+    Other,
+}
+
+/// `path`, relative to `dir` unless absolute
+fn join(dir: &str, path: &str) -> String {
+    if dir.is_empty() || path.starts_with('/') { path.into() } else { format!("{}/{}", dir, path) }
+}
+
+fn invalid(reason: impl Display) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf32::dwarf: {}", reason)) }
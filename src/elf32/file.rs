@@ -0,0 +1,141 @@
+use super::*;
+
+use std::ops::Range;
+
+// Limitations:
+// - Headers, names & symbols are read eagerly: section contents are read on demand, via File::section_data
+// - SHT_SYMTAB_SHNDX (extended section indices of symbols) isn't read: st_shndx of SHN_XINDEX (0xFFFF) is left as is
+
+
+
+/// The headers of an ELF file - program headers, section headers, and section names - for tooling.
+/// Unlike [`run`], which only ever needs the program headers, this also reads the section header table.
+#[derive(Clone, Debug)]
+pub struct File {
+    pub ehdr:   Ehdr,
+    pub phdrs:  Vec<Phdr>,
+    pub shdrs:  Vec<Shdr>,
+    shstrtab:   StringTable,
+}
+
+/// A string table section (`.strtab`, `.dynstr`, `.shstrtab`): NUL terminated strings, indexed by byte offset
+#[derive(Clone, Debug, Default)]
+pub struct StringTable(Vec<u8>);
+
+/// A symbol table section (`.symtab` or `.dynsym`), along with the string table it names symbols from
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    pub syms:   Vec<Sym>,
+    pub strtab: StringTable,
+}
+
+impl File {
+    pub const SHT_SYMTAB : u32 = 2;
+    pub const SHT_STRTAB : u32 = 3;
+    pub const SHT_DYNSYM : u32 = 11;
+
+    /// Read and validate the headers of `elf`
+    pub fn read(elf: &mut impl ReadAtMut) -> io::Result<Self> {
+        let ehdr = read_ehdr(elf)?;
+        let phdrs = read_phdrs(elf, &ehdr)?;
+        let mut file = Self { ehdr, phdrs, shdrs: Vec::new(), shstrtab: StringTable::default() };
+        if ehdr.e_shoff == 0 { return Ok(file) }
+        if ehdr.e_shentsize == 0 { return Err(invalid("section header table entries must have nonzero size (e_shentsize == 0)")) }
+
+        let read_shdr = |elf: &mut dyn ReadAtMut, i: u32| -> io::Result<Shdr> {
+            let mut shdr = Shdr::zeroed();
+            let shdr_read = size_of_val(&shdr).min(ehdr.e_shentsize.into());
+            let shdr_off = u64::from(ehdr.e_shoff) + u64::from(i) * u64::from(ehdr.e_shentsize);
+            elf.read_exact_at(&mut bytes_of_mut(&mut shdr)[..shdr_read], shdr_off)?;
+            if ehdr.big_endian() { shdr.swap_bytes(); }
+            Ok(shdr)
+        };
+        let shdr0 = read_shdr(elf, 0)?;
+        let shnum = if ehdr.e_shnum == 0 { shdr0.sh_size } else { ehdr.e_shnum.into() }; // 0: the real count is in section 0's sh_size
+        if shnum > 0x10_0000 { return Err(invalid(format!("{} section headers is too many to be plausible", shnum))) }
+        file.shdrs.push(shdr0);
+        for i in 1 .. shnum { file.shdrs.push(read_shdr(elf, i)?); }
+
+        let shstrndx = if ehdr.e_shstrndx == 0xFFFF { shdr0.sh_link } else { ehdr.e_shstrndx.into() }; // SHN_XINDEX: the real index is in section 0's sh_link
+        if shstrndx != 0 {
+            let Some(shstrtab) = file.shdrs.get(shstrndx as usize).copied() else { return Err(invalid(format!("e_shstrndx {} is out of bounds", shstrndx))) };
+            file.shstrtab = StringTable(file.section_data(elf, &shstrtab)?);
+        }
+        Ok(file)
+    }
+
+    /// The name of `shdr` (from `.shstrtab`), or `""` if it has none
+    pub fn section_name(&self, shdr: &Shdr) -> &str { self.shstrtab.get(shdr.sh_name).unwrap_or("") }
+
+    /// The first section named `name` (e.g. `".text"`)
+    pub fn section_by_name(&self, name: &str) -> Option<&Shdr> { self.shdrs.iter().find(|shdr| self.section_name(shdr) == name) }
+
+    /// The section containing the (unbiased) address `addr`, if any section with an address does
+    pub fn section_at(&self, addr: Addr) -> Option<&Shdr> { self.shdrs.iter().find(|shdr| shdr.sh_addr != 0 && section_range(shdr).contains(&addr)) }
+
+    /// Read the contents of `shdr` (empty for SHT_NOBITS sections like `.bss`)
+    pub fn section_data(&self, elf: &mut impl ReadAtMut, shdr: &Shdr) -> io::Result<Vec<u8>> {
+        if shdr.sh_type == 8 { return Ok(Vec::new()) } // SHT_NOBITS
+        let mut data = vec![0u8; shdr.sh_size as usize];
+        elf.read_exact_at(&mut data[..], shdr.sh_offset.into())?;
+        Ok(data)
+    }
+
+    /// Read the SHT_SYMTAB / SHT_DYNSYM section `shdr`, and the string table its sh_link refers to
+    pub fn symbol_table(&self, elf: &mut impl ReadAtMut, shdr: &Shdr) -> io::Result<SymbolTable> {
+        if shdr.sh_type != Self::SHT_SYMTAB && shdr.sh_type != Self::SHT_DYNSYM { return Err(invalid(format!("section `{}` isn't a symbol table", self.section_name(shdr)))) }
+        let entsize = if shdr.sh_entsize == 0 { size_of_val(&Sym::zeroed()) } else { shdr.sh_entsize as usize };
+        if entsize < size_of_val(&Sym::zeroed()) { return Err(invalid(format!("section `{}` has sh_entsize {} < size_of::<Sym>()", self.section_name(shdr), entsize))) }
+
+        let data = self.section_data(elf, shdr)?;
+        let syms = data.chunks_exact(entsize).map(|entry| {
+            let mut sym : Sym = bytemuck::pod_read_unaligned(&entry[.. size_of_val(&Sym::zeroed())]);
+            if self.ehdr.big_endian() { sym.swap_bytes(); }
+            sym
+        }).collect();
+        let strtab = match self.shdrs.get(shdr.sh_link as usize) {
+            Some(strtab) if shdr.sh_link != 0 => StringTable(self.section_data(elf, strtab)?),
+            _                                 => StringTable::default(),
+        };
+        Ok(SymbolTable { syms, strtab })
+    }
+
+    /// Read `.symtab` (every symbol, unless stripped), if present
+    pub fn symtab(&self, elf: &mut impl ReadAtMut) -> io::Result<Option<SymbolTable>> { self.first_of_type(elf, Self::SHT_SYMTAB) }
+
+    /// Read `.dynsym` (the symbols used for dynamic linking, which survive stripping), if present
+    pub fn dynsym(&self, elf: &mut impl ReadAtMut) -> io::Result<Option<SymbolTable>> { self.first_of_type(elf, Self::SHT_DYNSYM) }
+
+    fn first_of_type(&self, elf: &mut impl ReadAtMut, sh_type: u32) -> io::Result<Option<SymbolTable>> {
+        match self.shdrs.iter().find(|shdr| shdr.sh_type == sh_type) {
+            Some(shdr)  => self.symbol_table(elf, shdr).map(Some),
+            None        => Ok(None),
+        }
+    }
+}
+
+impl StringTable {
+    pub fn new(bytes: Vec<u8>) -> Self { Self(bytes) }
+
+    /// The string starting at byte `offset`, if in bounds, NUL terminated, and UTF-8
+    pub fn get(&self, offset: u32) -> Option<&str> {
+        let rest = self.0.get(offset as usize ..)?;
+        let nul = rest.iter().position(|b| *b == 0)?;
+        std::str::from_utf8(&rest[..nul]).ok()
+    }
+}
+
+impl SymbolTable {
+    /// The name of `sym`, or `""` if it has none
+    pub fn name(&self, sym: &Sym) -> &str { self.strtab.get(sym.st_name).unwrap_or("") }
+
+    /// Every symbol with its name, in table order (starting with the null symbol at index 0)
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Sym)> { self.syms.iter().map(move |sym| (self.name(sym), sym)) }
+
+    /// The first symbol named `name`
+    pub fn by_name(&self, name: &str) -> Option<&Sym> { self.syms.iter().find(|sym| self.name(sym) == name) }
+}
+
+fn section_range(shdr: &Shdr) -> Range<Addr> { shdr.sh_addr .. shdr.sh_addr.saturating_add(shdr.sh_size) }
+
+fn invalid(reason: impl std::fmt::Display) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf32::File: {}", reason)) }
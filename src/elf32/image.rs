@@ -0,0 +1,103 @@
+use super::*;
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+// Limitations:
+// - Symbols come from .symtab (or .dynsym if stripped) only: nothing for images without section headers
+// - Sizeless symbols (st_size == 0, e.g. hand written assembly) cover everything up to the next symbol
+
+
+
+/// An ELF image loaded into guest memory by [`load`], kept around after loading to symbolize addresses for diagnostics
+#[derive(Clone, Debug, Default)]
+pub struct LoadedImage {
+    /// `""` for the main executable, otherwise the path it was loaded from (e.g. PT_INTERP's)
+    pub name:       String,
+    /// How far above its linked addresses the image was loaded (0 unless ET_DYN)
+    pub bias:       u32,
+    /// The (biased) addresses spanned by the image's PT_LOAD segments
+    pub span:       Range<u32>,
+    /// The image's function & object symbols, biased and sorted by address
+    pub symbols:    Vec<Symbol>,
+    /// The image's `.debug_line` table, if built with `-g`
+    pub lines:      Option<LineTable>,
+    /// The (biased) addresses of the image's `.ARM.exidx` unwind table (its PT_ARM_EXIDX segment), if it has one - see [`backtrace`]
+    pub exidx:      Option<Range<u32>>,
+}
+
+/// A named address range of a [`LoadedImage`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name:   String,
+    pub addr:   u32,
+    pub size:   u32,
+}
+
+impl LoadedImage {
+    /// Describe `elf` as loaded `bias` bytes above its linked addresses.  Unreadable or missing section headers
+    /// (which [`run`] never needs) just leave the image without symbols.
+    pub fn read(elf: &mut impl ReadAtMut, name: impl Into<String>, bias: u32) -> io::Result<Self> {
+        let ehdr = read_ehdr(elf)?;
+        let phdrs = read_phdrs(elf, &ehdr)?;
+        let span = load_span(&phdrs);
+        let exidx = phdrs.iter().find(|phdr| phdr.p_type == PT_ARM_EXIDX).map(|phdr| phdr.p_vaddr.wrapping_add(bias) .. phdr.p_vaddr.wrapping_add(bias).wrapping_add(phdr.p_memsz & !7));
+        let mut image = Self { name: name.into(), bias, span: span.start.wrapping_add(bias) .. span.end.wrapping_add(bias), symbols: Vec::new(), lines: None, exidx };
+
+        let Ok(file) = File::read(elf) else { return Ok(image) };
+        if !ehdr.big_endian() { image.lines = LineTable::read(&file, elf).ok().flatten(); } // the DWARF reader is little-endian only
+        let table = match file.symtab(elf) {
+            Ok(Some(symtab))    => symtab,
+            _                   => match file.dynsym(elf) { Ok(Some(dynsym)) => dynsym, _ => return Ok(image) },
+        };
+        for (name, sym) in table.iter() {
+            if name.is_empty() || name.starts_with('$') { continue } // ARM mapping symbols ($a, $t, $d) mark code & data, and aren't names
+            if sym.st_shndx == 0 || sym.st_type() > 2 { continue } // undefined, or not STT_NOTYPE / STT_OBJECT / STT_FUNC
+            let addr = if sym.st_type() == 2 { sym.st_value & !1 } else { sym.st_value }; // STT_FUNC: bit 0 is the Thumb bit
+            let addr = if sym.st_shndx == 0xFFF1 { addr } else { addr.wrapping_add(bias) }; // SHN_ABS
+            image.symbols.push(Symbol { name: name.into(), addr, size: sym.st_size });
+        }
+        image.symbols.sort_by_key(|sym| (sym.addr, std::cmp::Reverse(sym.size)));
+        image.symbols.dedup_by_key(|sym| sym.addr); // aliases: keep the first, largest symbol
+        Ok(image)
+    }
+
+    /// The source location of the instruction at `addr`, if the image has line numbers for it
+    pub fn location(&self, addr: u32) -> Option<SourceLocation<'_>> { self.lines.as_ref()?.location(addr.wrapping_sub(self.bias)) }
+
+    /// The symbol containing `addr`, and how far into it `addr` is
+    pub fn symbol_at(&self, addr: u32) -> Option<(&Symbol, u32)> {
+        let i = self.symbols.partition_point(|sym| sym.addr <= addr).checked_sub(1)?;
+        let sym = &self.symbols[i];
+        let offset = addr - sym.addr;
+        if sym.size != 0 && offset >= sym.size { return None }
+        if sym.size == 0 && !self.span.contains(&addr) { return None }
+        Some((sym, offset))
+    }
+}
+
+/// Display `addr` along with the symbol of `images` containing it, and its source location if known, e.g. `0x000102f4 (memcpy+0x14 at memcpy.c:12)`
+pub fn symbolize(images: &[LoadedImage], addr: u32) -> impl Display + '_ { Symbolized { images, addr } }
+
+struct Symbolized<'i> {
+    images: &'i [LoadedImage],
+    addr:   u32,
+}
+
+impl Display for Symbolized<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "0x{:08x}", self.addr)?;
+        let location = self.images.iter().find_map(|image| image.location(self.addr));
+        if let Some((sym, offset)) = self.images.iter().find_map(|image| image.symbol_at(self.addr)) {
+            if offset == 0 { write!(f, " ({}", sym.name)?; } else { write!(f, " ({}+0x{:x}", sym.name, offset)?; }
+        } else if let Some(image) = self.images.iter().find(|image| !image.name.is_empty() && image.span.contains(&self.addr)) {
+            write!(f, " ({}+0x{:x}", image.name, self.addr - image.span.start)?;
+        } else if let Some(location) = location {
+            return write!(f, " (at {})", location);
+        } else {
+            return Ok(());
+        }
+        if let Some(location) = location { write!(f, " at {}", location)?; }
+        write!(f, ")")
+    }
+}
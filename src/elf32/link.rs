@@ -0,0 +1,429 @@
+use super::*;
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+// References:
+// - System V gABI, Dynamic Linking (DT_*, DT_HASH): https://www.sco.com/developers/gabi/latest/ch5.dynamic.html
+// - ELF for the Arm(R) Architecture (AAELF32) §6.1 PLT: https://github.com/ARM-software/abi-aa/blob/main/aaelf32/aaelf32.rst#plt-sequences-and-usage-models
+//
+// Limitations:
+// - No symbol versioning (DT_VERSYM & co. are ignored: the first definition of a name in load order wins), DF_SYMBOLIC, or dlopen
+// - Static TLS only (see tls.rs): the TLS relocations are resolved against the initial thread's layout, and there's no `__tls_get_addr`
+// - Libraries' initializers (DT_INIT, DT_INIT_ARRAY) run before the entry point, but finalizers (DT_FINI*) never run.  The executable's are left to its crt.
+// - DT_NEEDED libraries are only searched for in DT_RPATH / DT_RUNPATH and LoadConfig::library_path, never the host's /lib
+// - Lazy binding only works for DT_PLTREL == DT_REL objects (as ARM linux uses): DT_RELA JUMP_SLOTs are bound eagerly
+
+
+
+/// Addresses the guest "calls" to hand control back to the [`Linker`], which never get mapped: executing them prefetch aborts into [`Linker::trap`]
+pub(super) const LINKER_TRAPS : Range<u32> = 0xFFFE_F000 .. 0xFFFF_0000;
+const RESOLVE       : u32 = LINKER_TRAPS.start;     // GOT[2] of lazily bound objects: their PLT0 jumps here
+const INIT_RETURN   : u32 = LINKER_TRAPS.start + 4; // the return address of library initializers
+
+/// The link map of a process [`load_with`] linked itself (with [`Linking::Eager`] or [`Linking::Lazy`], or for executables without a PT_INTERP)
+#[derive(Default)]
+pub(super) struct Linker {
+    objects:    Vec<Object>, // the executable first, then DT_NEEDED libraries breadth first
+    globals:    HashMap<String, Vec<Definition>>, // in load order
+    lazy:       HashMap<u32, (usize, u32)>, // GOT slot → (object, r_info) of JUMP_SLOTs to bind on first call
+    inits:      Vec<u32>, // library initializers yet to run, last first
+    start:      Option<([u32; 16], [u32; 3], u32)>, // the initial registers, initializer arguments, & entry point, while running initializers
+}
+
+/// A loaded executable or shared object, and its PT_DYNAMIC segment
+struct Object {
+    name:       String,
+    bias:       u32,
+    dynamic:    Dynamic,
+    tls:        Option<TlsBlock>,
+}
+
+#[derive(Clone, Copy)]
+struct Definition {
+    addr:       u32,
+    thumb:      bool,
+    size:       u32,
+    object:     usize,
+}
+
+/// The parts of a PT_DYNAMIC segment the linker uses, with addresses load biased
+struct Dynamic {
+    needed:     Vec<String>,
+    soname:     Option<String>,
+    search:     Vec<String>, // DT_RUNPATH, or DT_RPATH without one
+    strtab:     Option<u32>,
+    symtab:     Option<u32>,
+    nsyms:      u32, // from DT_HASH / DT_GNU_HASH, or 0 without either
+    rel:        (u32, u32, u32), // address, size, entry size
+    rela:       (u32, u32, u32),
+    jmprel:     (u32, u32, bool), // address, size, DT_PLTREL == DT_RELA
+    pltgot:     Option<u32>,
+    init:       Option<u32>,
+    init_array: (u32, u32), // address, size
+}
+
+impl Linker {
+    /// Link the executable already loaded into `mem` `bias` bytes above its linked addresses, loading its DT_NEEDED libraries (and theirs) into `images`
+    pub(super) fn link(mem: &arm::Memory, phdrs: &[Phdr], bias: u32, config: &LoadConfig, images: &mut Vec<LoadedImage>) -> io::Result<Self> {
+        let mut linker = Self::default();
+        let dynamic = match phdrs.iter().find(|phdr| phdr.p_type == 2) { // PT_DYNAMIC
+            Some(dynamic)   => Dynamic::read(mem, dynamic, bias)?,
+            None            => Dynamic::default(),
+        };
+        linker.objects.push(Object { name: String::new(), bias, dynamic, tls: TlsBlock::read(phdrs, bias)? });
+
+        let mut loaded = HashMap::<String, usize>::new(); // DT_NEEDED names & DT_SONAMEs → objects
+        let mut i = 0;
+        while i < linker.objects.len() {
+            for needed in linker.objects[i].dynamic.needed.clone() {
+                if loaded.contains_key(&needed) { continue }
+                let object = linker.load_library(mem, i, &needed, config, images)?;
+                if let Some(soname) = linker.objects[object].dynamic.soname.clone() { loaded.entry(soname).or_insert(object); }
+                loaded.insert(needed, object);
+            }
+            i += 1;
+        }
+
+        tls::layout_static_tls(linker.objects.iter_mut().filter_map(|object| object.tls.as_mut()))?;
+        for object in 0 .. linker.objects.len() { linker.define_globals(mem, object)?; }
+        for object in (0 .. linker.objects.len()).rev() { linker.relocate(mem, object, config.linking == Linking::Lazy)?; } // dependencies before their dependents
+        for object in (1 .. linker.objects.len()).rev() {
+            let dynamic = &linker.objects[object].dynamic;
+            let mut inits = dynamic.init.into_iter().collect::<Vec<_>>();
+            for offset in (0 .. dynamic.init_array.1).step_by(4) { inits.push(read_u32(mem, dynamic.init_array.0.wrapping_add(offset))?); }
+            linker.inits.extend(inits.into_iter().filter(|init| *init != 0 && *init != !0));
+        }
+        linker.inits.reverse();
+        Ok(linker)
+    }
+
+    /// Set up `cpu`'s static TLS, then start it at its library initializers (if any), arranging for them to return to [`Linker::trap`], which eventually starts `cpu` at `entry`
+    pub(super) fn start(&mut self, cpu: &mut arm::Cpu, mem: &arm::Memory, entry: u32) -> io::Result<()> {
+        tls::init_static_tls(cpu, mem, &self.objects.iter().filter_map(|object| object.tls).collect::<Vec<_>>())?;
+        if self.inits.is_empty() {
+            branch(cpu, entry);
+            return Ok(());
+        }
+        let sp = cpu.registers[13];
+        let argc = read_u32(mem, sp)?;
+        let (argv, envp) = (sp.wrapping_add(4), sp.wrapping_add(8).wrapping_add(argc.wrapping_mul(4)));
+        self.start = Some((cpu.registers, [argc, argv, envp], entry)); // initializers are called like glibc does: `init(argc, argv, envp)`
+        self.next_init(cpu);
+        Ok(())
+    }
+
+    /// Handle `cpu` executing `pc` within [`LINKER_TRAPS`]
+    pub(super) fn trap(&mut self, cpu: &mut arm::Cpu, mem: &arm::Memory, pc: u32) -> io::Result<()> {
+        match pc {
+            RESOLVE => { // PLT0: `str lr, [sp, #-4]!` ... `ldr pc, [lr, #8]!`, after PLTn left `ip` pointing at its GOT slot
+                let slot = cpu.registers[12];
+                let Some((object, r_info)) = self.lazy.remove(&slot) else { return Err(invalid(format!("lazy binding of unknown GOT slot 0x{:08x}", slot))) };
+                let def = self.resolve(mem, object, r_info)?;
+                reloc::apply(mem, &reloc::Relocation { r_type: reloc::R_ARM_JUMP_SLOT, place: slot, symbol: def.addr, thumb: def.thumb, base: 0, addend: Some(0) })?;
+                let sp = cpu.registers[13];
+                cpu.registers[14] = read_u32(mem, sp)?;
+                cpu.registers[13] = sp.wrapping_add(4);
+                branch(cpu, if def.thumb { def.addr | 1 } else { def.addr & !1 });
+            },
+            INIT_RETURN if self.start.is_some() => self.next_init(cpu),
+            _ => return Err(invalid(format!("jump into the dynamic linker's trap page at 0x{:08x}", pc))),
+        }
+        Ok(())
+    }
+
+    fn next_init(&mut self, cpu: &mut arm::Cpu) {
+        let Some((registers, args, entry)) = self.start else { return };
+        cpu.registers = registers; // argc & co. are above the initial SP, which initializers don't touch
+        match self.inits.pop() {
+            Some(init) => {
+                cpu.registers[..3].copy_from_slice(&args);
+                cpu.registers[14] = INIT_RETURN;
+                branch(cpu, init);
+            },
+            None => {
+                self.start = None;
+                branch(cpu, entry);
+            },
+        }
+    }
+
+    /// Find & load the DT_NEEDED library `needed` of `parent`
+    fn load_library(&mut self, mem: &arm::Memory, parent: usize, needed: &str, config: &LoadConfig, images: &mut Vec<LoadedImage>) -> io::Result<usize> {
+        let parent = &self.objects[parent];
+        let mut candidates = Vec::new();
+        if needed.contains('/') {
+            candidates.push(PathBuf::from(needed));
+        } else {
+            let origin = Path::new(&parent.name).parent().filter(|_| !parent.name.is_empty());
+            for dir in parent.dynamic.search.iter().flat_map(|search| search.split(':')) {
+                match (dir.strip_prefix("$ORIGIN"), origin) {
+                    (Some(rest), Some(origin))  => candidates.push(origin.join(format!(".{}", rest)).join(needed)),
+                    (Some(_), None)             => {}, // the executable's path is unknown
+                    (None, _)                   => candidates.push(Path::new(dir).join(needed)),
+                }
+            }
+            candidates.extend(config.library_path.iter().map(|dir| dir.join(needed)));
+        }
+
+        for path in candidates {
+            let Ok(mut lib) = std::fs::File::open(&path) else { continue };
+            let Ok(ehdr) = read_ehdr(&mut lib) else { continue }; // e.g. a host library of the wrong architecture
+            if ehdr.e_type != 3 { continue } // ET_DYN
+            if ehdr.big_endian() { return Err(invalid(format!("relocating big-endian elfs isn't yet supported (`{}`)", path.display()))) }
+            let phdrs = read_phdrs(&mut lib, &ehdr)?;
+            let span = load_span(&phdrs);
+            let size = span.end.wrapping_sub(span.start);
+            let name = path.display().to_string();
+            let Some(base) = mem.find_unmapped(INTERP_AREA, size, 0x1000) else { return Err(invalid(format!("no room to load `{}` (0x{:x} bytes)", name, size))) };
+            let bias = base.wrapping_sub(span.start);
+            load_segments(mem, &mut lib, &phdrs, bias, &name)?;
+            images.push(LoadedImage::read(&mut lib, name.as_str(), bias)?);
+            let dynamic = match phdrs.iter().find(|phdr| phdr.p_type == 2) {
+                Some(dynamic)   => Dynamic::read(mem, dynamic, bias)?,
+                None            => Dynamic::default(),
+            };
+            self.objects.push(Object { name, bias, dynamic, tls: TlsBlock::read(&phdrs, bias)? });
+            return Ok(self.objects.len() - 1);
+        }
+        let by = if parent.name.is_empty() { "the executable" } else { parent.name.as_str() };
+        Err(invalid(format!("unable to find `{}` (DT_NEEDED by {}) - see LoadConfig::library_path", needed, by)))
+    }
+
+    /// Add the global & weak symbols `object` defines to [`Linker::globals`]
+    fn define_globals(&mut self, mem: &arm::Memory, object: usize) -> io::Result<()> {
+        for index in 1 .. self.objects[object].dynamic.nsyms {
+            let sym = self.sym(mem, object, index)?;
+            if sym.st_shndx == 0 || sym.st_bind() == 0 || !matches!(sym.st_type(), 0 | 1 | 2 | 6) { continue } // undefined, STB_LOCAL, or not STT_NOTYPE / STT_OBJECT / STT_FUNC / STT_TLS
+            let name = self.name(mem, object, sym.st_name)?;
+            let definition = self.definition(object, &sym);
+            self.globals.entry(name).or_default().push(definition);
+        }
+        Ok(())
+    }
+
+    /// Apply the relocations of `object`, leaving its JUMP_SLOTs to be bound by [`Linker::trap`] if `lazy`
+    fn relocate(&mut self, mem: &arm::Memory, object: usize, lazy: bool) -> io::Result<()> {
+        let Object { bias, ref dynamic, .. } = self.objects[object];
+        let (rel, relsz, relent) = dynamic.rel;
+        let (rela, relasz, relaent) = dynamic.rela;
+        let (jmprel, pltrelsz, plt_rela) = dynamic.jmprel;
+        let lazy = lazy && !plt_rela && dynamic.pltgot.is_some();
+        let pltgot = dynamic.pltgot;
+
+        let mut relocs = Vec::new();
+        for offset in (0 .. relsz).step_by(relent as usize) {
+            let mut r = Rel::zeroed();
+            read_guest(mem, rel.wrapping_add(offset), bytes_of_mut(&mut r))?;
+            relocs.push((r.r_offset, r.r_info, None));
+        }
+        for offset in (0 .. relasz).step_by(relaent as usize) {
+            let mut r = Rela::zeroed();
+            read_guest(mem, rela.wrapping_add(offset), bytes_of_mut(&mut r))?;
+            relocs.push((r.r_offset, r.r_info, Some(r.r_addend)));
+        }
+        for offset in (0 .. pltrelsz).step_by(if plt_rela { relaent } else { relent } as usize) {
+            if plt_rela {
+                let mut r = Rela::zeroed();
+                read_guest(mem, jmprel.wrapping_add(offset), bytes_of_mut(&mut r))?;
+                relocs.push((r.r_offset, r.r_info, Some(r.r_addend)));
+            } else {
+                let mut r = Rel::zeroed();
+                read_guest(mem, jmprel.wrapping_add(offset), bytes_of_mut(&mut r))?;
+                relocs.push((r.r_offset, r.r_info, None));
+            }
+        }
+
+        for (r_offset, r_info, addend) in relocs {
+            let place = r_offset.wrapping_add(bias);
+            match r_info & 0xFF {
+                reloc::R_ARM_JUMP_SLOT if lazy => { // until bound, the GOT slot points (unbiased) at PLT0
+                    write_u32(mem, place, read_u32(mem, place)?.wrapping_add(bias))?;
+                    self.lazy.insert(place, (object, r_info));
+                },
+                reloc::R_ARM_COPY => { // the executable's copy of a library's data object
+                    let sym = self.sym(mem, object, r_info >> 8)?;
+                    let name = self.name(mem, object, sym.st_name)?;
+                    let Some(from) = self.globals.get(&name).and_then(|defs| defs.iter().find(|def| def.object != object)) else { return Err(invalid(format!("undefined symbol `{}` (R_ARM_COPY of {})", name, self.describe(object)))) };
+                    let mut data = vec![0u8; sym.st_size.min(from.size) as usize];
+                    read_guest(mem, from.addr, &mut data)?;
+                    write_guest(mem, place, &data)?;
+                },
+                r_type @ (reloc::R_ARM_TLS_DTPMOD32 | reloc::R_ARM_TLS_DTPOFF32 | reloc::R_ARM_TLS_TPOFF32) => {
+                    let def = self.resolve(mem, object, r_info)?; // STT_TLS: addr is the offset within its object's block
+                    let Some(block) = self.objects[def.object].tls else { return Err(invalid(format!("{} at 0x{:08x} refers to {}, which has no PT_TLS", reloc::name(r_type), place, self.describe(def.object)))) };
+                    let a = match addend { Some(a) => a as u32, None => read_u32(mem, place)? };
+                    let value = match r_type {
+                        reloc::R_ARM_TLS_DTPMOD32   => block.module,
+                        reloc::R_ARM_TLS_DTPOFF32   => def.addr.wrapping_add(a),
+                        _                           => block.offset.wrapping_add(def.addr).wrapping_add(a), // R_ARM_TLS_TPOFF32
+                    };
+                    write_u32(mem, place, value)?;
+                },
+                r_type => {
+                    let def = self.resolve(mem, object, r_info)?;
+                    reloc::apply(mem, &reloc::Relocation { r_type, place, symbol: def.addr, thumb: def.thumb, base: self.objects[def.object].bias, addend })?;
+                },
+            }
+        }
+
+        if lazy {
+            let pltgot = pltgot.unwrap_or(0);
+            write_u32(mem, pltgot.wrapping_add(4), object as u32)?; // GOT[1]: ld.so's link_map, unused by PLT0
+            write_u32(mem, pltgot.wrapping_add(8), RESOLVE)?; // GOT[2]: _dl_runtime_resolve
+        }
+        Ok(())
+    }
+
+    /// The definition of the symbol relocation `r_info` of `object` refers to (S, T, and the object whose load bias is B(S)), or 0 in `object` for no / undefined weak symbols
+    fn resolve(&self, mem: &arm::Memory, object: usize, r_info: u32) -> io::Result<Definition> {
+        let none = Definition { addr: 0, thumb: false, size: 0, object };
+        if r_info >> 8 == 0 { return Ok(none) } // STN_UNDEF: no symbol
+        let sym = self.sym(mem, object, r_info >> 8)?;
+        let local = sym.st_bind() == 0 || matches!(sym.st_other & 3, 2 | 3); // STB_LOCAL, STV_HIDDEN, STV_PROTECTED: not interposable
+        if sym.st_shndx != 0 && local { return Ok(self.definition(object, &sym)) }
+        let name = self.name(mem, object, sym.st_name)?;
+        if let Some(def) = self.globals.get(&name).and_then(|defs| defs.first()) { return Ok(*def) }
+        if sym.st_shndx != 0 { return Ok(self.definition(object, &sym)) } // defined, but not exported (no DT_HASH / DT_GNU_HASH)
+        if sym.st_bind() == 2 { return Ok(none) } // undefined STB_WEAK
+        Err(invalid(format!("undefined symbol `{}` (referenced by {})", name, self.describe(object))))
+    }
+
+    fn definition(&self, object: usize, sym: &Sym) -> Definition {
+        let thumb = sym.st_type() == 2 && sym.st_value & 1 != 0; // STT_FUNC
+        let addr = if sym.st_shndx == 0xFFF1 || sym.st_type() == 6 { sym.st_value } else { sym.st_value.wrapping_add(self.objects[object].bias) }; // SHN_ABS, or STT_TLS: an offset into the object's TLS block
+        Definition { addr, thumb, size: sym.st_size, object }
+    }
+
+    fn sym(&self, mem: &arm::Memory, object: usize, index: u32) -> io::Result<Sym> {
+        let Some(symtab) = self.objects[object].dynamic.symtab else { return Err(invalid(format!("relocation refers to a symbol, but {} has no DT_SYMTAB", self.describe(object)))) };
+        let mut sym = Sym::zeroed();
+        read_guest(mem, symtab.wrapping_add(index.wrapping_mul(size_of_val(&sym) as u32)), bytes_of_mut(&mut sym))?;
+        Ok(sym)
+    }
+
+    fn name(&self, mem: &arm::Memory, object: usize, offset: u32) -> io::Result<String> {
+        let Some(strtab) = self.objects[object].dynamic.strtab else { return Err(invalid(format!("{} has symbol names, but no DT_STRTAB", self.describe(object)))) };
+        read_guest_cstr(mem, strtab.wrapping_add(offset))
+    }
+
+    fn describe(&self, object: usize) -> String {
+        match self.objects[object].name.as_str() {
+            ""      => "the executable".into(),
+            name    => format!("`{}`", name),
+        }
+    }
+}
+
+impl Default for Dynamic {
+    fn default() -> Self {
+        let (relent, relaent) = (size_of_val(&Rel::zeroed()) as u32, size_of_val(&Rela::zeroed()) as u32);
+        Self { needed: Vec::new(), soname: None, search: Vec::new(), strtab: None, symtab: None, nsyms: 0, rel: (0, 0, relent), rela: (0, 0, relaent), jmprel: (0, 0, false), pltgot: None, init: None, init_array: (0, 0) }
+    }
+}
+
+impl Dynamic {
+    /// Read the PT_DYNAMIC segment `dynamic` of an object loaded `bias` bytes above its linked addresses
+    fn read(mem: &arm::Memory, dynamic: &Phdr, bias: u32) -> io::Result<Self> {
+        const DT_NULL       : i32 = 0;
+        const DT_NEEDED     : i32 = 1;
+        const DT_PLTRELSZ   : i32 = 2;
+        const DT_PLTGOT     : i32 = 3;
+        const DT_HASH       : i32 = 4;
+        const DT_STRTAB     : i32 = 5;
+        const DT_SYMTAB     : i32 = 6;
+        const DT_RELA       : i32 = 7;
+        const DT_RELASZ     : i32 = 8;
+        const DT_RELAENT    : i32 = 9;
+        const DT_INIT       : i32 = 12;
+        const DT_SONAME     : i32 = 14;
+        const DT_RPATH      : i32 = 15;
+        const DT_REL        : i32 = 17;
+        const DT_RELSZ      : i32 = 18;
+        const DT_RELENT     : i32 = 19;
+        const DT_PLTREL     : i32 = 20;
+        const DT_JMPREL     : i32 = 23;
+        const DT_INIT_ARRAY : i32 = 25;
+        const DT_INIT_ARRAYSZ : i32 = 27;
+        const DT_RUNPATH    : i32 = 29;
+        const DT_GNU_HASH   : i32 = 0x6FFF_FEF5;
+
+        let mut d = Self::default();
+        let (mut needed, mut soname, mut rpath, mut runpath) = (Vec::new(), None, None, None);
+        let (mut hash, mut gnu_hash) = (None, None);
+        for i in 0 .. dynamic.p_memsz / 8 {
+            let mut dyn_ = Dyn::zeroed();
+            read_guest(mem, dynamic.p_vaddr.wrapping_add(bias).wrapping_add(i * 8), bytes_of_mut(&mut dyn_))?;
+            let (val, ptr) = (dyn_.d_val, dyn_.d_val.wrapping_add(bias));
+            match dyn_.d_tag {
+                DT_NULL         => break,
+                DT_NEEDED       => needed.push(val), // string table offsets, resolved once DT_STRTAB is known
+                DT_SONAME       => soname = Some(val),
+                DT_RPATH        => rpath = Some(val),
+                DT_RUNPATH      => runpath = Some(val),
+                DT_PLTRELSZ     => d.jmprel.1 = val,
+                DT_PLTGOT       => d.pltgot = Some(ptr),
+                DT_HASH         => hash = Some(ptr),
+                DT_GNU_HASH     => gnu_hash = Some(ptr),
+                DT_STRTAB       => d.strtab = Some(ptr),
+                DT_SYMTAB       => d.symtab = Some(ptr),
+                DT_RELA         => d.rela.0 = ptr,
+                DT_RELASZ       => d.rela.1 = val,
+                DT_RELAENT      => d.rela.2 = val,
+                DT_INIT         => d.init = Some(ptr),
+                DT_REL          => d.rel.0 = ptr,
+                DT_RELSZ        => d.rel.1 = val,
+                DT_RELENT       => d.rel.2 = val,
+                DT_PLTREL       => d.jmprel.2 = val == DT_RELA as u32,
+                DT_JMPREL       => d.jmprel.0 = ptr,
+                DT_INIT_ARRAY   => d.init_array.0 = ptr,
+                DT_INIT_ARRAYSZ => d.init_array.1 = val,
+                _               => {}, // DT_FINI, DT_DEBUG, DT_VERSYM, ...
+            }
+        }
+        if d.rel.2 == 0 || d.rela.2 == 0 { return Err(invalid("DT_RELENT / DT_RELAENT of 0")) }
+
+        let name = |offset: u32| -> io::Result<String> {
+            let Some(strtab) = d.strtab else { return Err(invalid("PT_DYNAMIC segment has names, but no DT_STRTAB")) };
+            read_guest_cstr(mem, strtab.wrapping_add(offset))
+        };
+        d.needed = needed.into_iter().map(name).collect::<io::Result<Vec<_>>>()?;
+        d.soname = soname.map(name).transpose()?;
+        d.search = runpath.or(rpath).map(name).transpose()?.into_iter().collect(); // DT_RUNPATH supersedes DT_RPATH
+        d.nsyms = match (hash, gnu_hash) {
+            (Some(hash), _)     => read_u32(mem, hash.wrapping_add(4))?, // nchain
+            (None, Some(gnu))   => gnu_hash_nsyms(mem, gnu)?,
+            (None, None)        => 0,
+        };
+        Ok(d)
+    }
+}
+
+/// The number of symbols in a DT_GNU_HASH'ed symbol table: one past the end of the last hash chain
+fn gnu_hash_nsyms(mem: &arm::Memory, gnu_hash: u32) -> io::Result<u32> {
+    let (nbuckets, symoffset, bloom_size) = (read_u32(mem, gnu_hash)?, read_u32(mem, gnu_hash.wrapping_add(4))?, read_u32(mem, gnu_hash.wrapping_add(8))?);
+    let buckets = gnu_hash.wrapping_add(16).wrapping_add(bloom_size.wrapping_mul(4));
+    let mut last = 0;
+    for i in 0 .. nbuckets { last = last.max(read_u32(mem, buckets.wrapping_add(i * 4))?); }
+    if last < symoffset { return Ok(symoffset) }
+    let chains = buckets.wrapping_add(nbuckets.wrapping_mul(4));
+    while read_u32(mem, chains.wrapping_add((last - symoffset).wrapping_mul(4)))? & 1 == 0 { last += 1; } // bit 0 ends a chain
+    Ok(last + 1)
+}
+
+/// Branch to `target`, with bit 0 selecting Thumb (1) vs ARM (0) state
+fn branch(cpu: &mut arm::Cpu, target: u32) {
+    cpu.cpsr.set(arm::Psr::T, target & 1 != 0);
+    cpu.set_next_instruction_addr(target & !1);
+}
+
+fn read_u32(mem: &arm::Memory, addr: u32) -> io::Result<u32> {
+    let mut value = [0u8; 4];
+    read_guest(mem, addr, &mut value)?;
+    Ok(u32::from_le_bytes(value))
+}
+
+fn write_u32(mem: &arm::Memory, addr: u32, value: u32) -> io::Result<()> { write_guest(mem, addr, &value.to_le_bytes()) }
+
+fn invalid(reason: impl std::fmt::Display) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf32::link: {}", reason)) }
@@ -0,0 +1,1026 @@
+use super::*;
+
+use std::io::Write;
+
+// References:
+// - linux's arch/arm/tools/syscall.tbl (the EABI syscall numbers), and arch/arm/kernel/traps.c (arm_syscall: the ARM private syscalls from 0x0f0000)
+// - syscall(2): "arm/EABI: swi 0x0, r7 = syscall number, r0 ..= r6 = arguments, r0 = result"
+//
+// Limitations:
+// - EABI only: the `svc` immediate is ignored, as kernels without CONFIG_OABI_COMPAT do, so OABI executables (`swi #0x900000 + nr`) don't work
+// - mmap2's MAP_SHARED file mappings are private copies: writes never reach the file, and changes to the file never reach the guest
+// - Only the syscalls [`syscall`] dispatches are implemented: the rest of the table fails with ENOSYS, as syscalls the kernel was built without do
+
+
+
+pub const SC_RESTART_SYSCALL              : u32 = 0;
+pub const SC_EXIT                         : u32 = 1;
+pub const SC_FORK                         : u32 = 2;
+pub const SC_READ                         : u32 = 3;
+pub const SC_WRITE                        : u32 = 4;
+pub const SC_OPEN                         : u32 = 5;
+pub const SC_CLOSE                        : u32 = 6;
+pub const SC_CREAT                        : u32 = 8;
+pub const SC_LINK                         : u32 = 9;
+pub const SC_UNLINK                       : u32 = 10;
+pub const SC_EXECVE                       : u32 = 11;
+pub const SC_CHDIR                        : u32 = 12;
+pub const SC_MKNOD                        : u32 = 14;
+pub const SC_CHMOD                        : u32 = 15;
+pub const SC_LCHOWN                       : u32 = 16;
+pub const SC_LSEEK                        : u32 = 19;
+pub const SC_GETPID                       : u32 = 20;
+pub const SC_MOUNT                        : u32 = 21;
+pub const SC_SETUID                       : u32 = 23;
+pub const SC_GETUID                       : u32 = 24;
+pub const SC_PTRACE                       : u32 = 26;
+pub const SC_PAUSE                        : u32 = 29;
+pub const SC_ACCESS                       : u32 = 33;
+pub const SC_NICE                         : u32 = 34;
+pub const SC_SYNC                         : u32 = 36;
+pub const SC_KILL                         : u32 = 37;
+pub const SC_RENAME                       : u32 = 38;
+pub const SC_MKDIR                        : u32 = 39;
+pub const SC_RMDIR                        : u32 = 40;
+pub const SC_DUP                          : u32 = 41;
+pub const SC_PIPE                         : u32 = 42;
+pub const SC_TIMES                        : u32 = 43;
+pub const SC_BRK                          : u32 = 45;
+pub const SC_SETGID                       : u32 = 46;
+pub const SC_GETGID                       : u32 = 47;
+pub const SC_GETEUID                      : u32 = 49;
+pub const SC_GETEGID                      : u32 = 50;
+pub const SC_ACCT                         : u32 = 51;
+pub const SC_UMOUNT2                      : u32 = 52;
+pub const SC_IOCTL                        : u32 = 54;
+pub const SC_FCNTL                        : u32 = 55;
+pub const SC_SETPGID                      : u32 = 57;
+pub const SC_UMASK                        : u32 = 60;
+pub const SC_CHROOT                       : u32 = 61;
+pub const SC_USTAT                        : u32 = 62;
+pub const SC_DUP2                         : u32 = 63;
+pub const SC_GETPPID                      : u32 = 64;
+pub const SC_GETPGRP                      : u32 = 65;
+pub const SC_SETSID                       : u32 = 66;
+pub const SC_SIGACTION                    : u32 = 67;
+pub const SC_SETREUID                     : u32 = 70;
+pub const SC_SETREGID                     : u32 = 71;
+pub const SC_SIGSUSPEND                   : u32 = 72;
+pub const SC_SIGPENDING                   : u32 = 73;
+pub const SC_SETHOSTNAME                  : u32 = 74;
+pub const SC_SETRLIMIT                    : u32 = 75;
+pub const SC_GETRUSAGE                    : u32 = 77;
+pub const SC_GETTIMEOFDAY                 : u32 = 78;
+pub const SC_SETTIMEOFDAY                 : u32 = 79;
+pub const SC_GETGROUPS                    : u32 = 80;
+pub const SC_SETGROUPS                    : u32 = 81;
+pub const SC_SYMLINK                      : u32 = 83;
+pub const SC_READLINK                     : u32 = 85;
+pub const SC_USELIB                       : u32 = 86;
+pub const SC_SWAPON                       : u32 = 87;
+pub const SC_REBOOT                       : u32 = 88;
+pub const SC_MUNMAP                       : u32 = 91;
+pub const SC_TRUNCATE                     : u32 = 92;
+pub const SC_FTRUNCATE                    : u32 = 93;
+pub const SC_FCHMOD                       : u32 = 94;
+pub const SC_FCHOWN                       : u32 = 95;
+pub const SC_GETPRIORITY                  : u32 = 96;
+pub const SC_SETPRIORITY                  : u32 = 97;
+pub const SC_STATFS                       : u32 = 99;
+pub const SC_FSTATFS                      : u32 = 100;
+pub const SC_SYSLOG                       : u32 = 103;
+pub const SC_SETITIMER                    : u32 = 104;
+pub const SC_GETITIMER                    : u32 = 105;
+pub const SC_STAT                         : u32 = 106;
+pub const SC_LSTAT                        : u32 = 107;
+pub const SC_FSTAT                        : u32 = 108;
+pub const SC_VHANGUP                      : u32 = 111;
+pub const SC_WAIT4                        : u32 = 114;
+pub const SC_SWAPOFF                      : u32 = 115;
+pub const SC_SYSINFO                      : u32 = 116;
+pub const SC_FSYNC                        : u32 = 118;
+pub const SC_SIGRETURN                    : u32 = 119;
+pub const SC_CLONE                        : u32 = 120;
+pub const SC_SETDOMAINNAME                : u32 = 121;
+pub const SC_UNAME                        : u32 = 122;
+pub const SC_ADJTIMEX                     : u32 = 124;
+pub const SC_MPROTECT                     : u32 = 125;
+pub const SC_SIGPROCMASK                  : u32 = 126;
+pub const SC_INIT_MODULE                  : u32 = 128;
+pub const SC_DELETE_MODULE                : u32 = 129;
+pub const SC_QUOTACTL                     : u32 = 131;
+pub const SC_GETPGID                      : u32 = 132;
+pub const SC_FCHDIR                       : u32 = 133;
+pub const SC_BDFLUSH                      : u32 = 134;
+pub const SC_SYSFS                        : u32 = 135;
+pub const SC_PERSONALITY                  : u32 = 136;
+pub const SC_SETFSUID                     : u32 = 138;
+pub const SC_SETFSGID                     : u32 = 139;
+pub const SC_LLSEEK                       : u32 = 140;
+pub const SC_GETDENTS                     : u32 = 141;
+pub const SC_NEWSELECT                    : u32 = 142;
+pub const SC_FLOCK                        : u32 = 143;
+pub const SC_MSYNC                        : u32 = 144;
+pub const SC_READV                        : u32 = 145;
+pub const SC_WRITEV                       : u32 = 146;
+pub const SC_GETSID                       : u32 = 147;
+pub const SC_FDATASYNC                    : u32 = 148;
+pub const SC_SYSCTL                       : u32 = 149;
+pub const SC_MLOCK                        : u32 = 150;
+pub const SC_MUNLOCK                      : u32 = 151;
+pub const SC_MLOCKALL                     : u32 = 152;
+pub const SC_MUNLOCKALL                   : u32 = 153;
+pub const SC_SCHED_SETPARAM               : u32 = 154;
+pub const SC_SCHED_GETPARAM               : u32 = 155;
+pub const SC_SCHED_SETSCHEDULER           : u32 = 156;
+pub const SC_SCHED_GETSCHEDULER           : u32 = 157;
+pub const SC_SCHED_YIELD                  : u32 = 158;
+pub const SC_SCHED_GET_PRIORITY_MAX       : u32 = 159;
+pub const SC_SCHED_GET_PRIORITY_MIN       : u32 = 160;
+pub const SC_SCHED_RR_GET_INTERVAL        : u32 = 161;
+pub const SC_NANOSLEEP                    : u32 = 162;
+pub const SC_MREMAP                       : u32 = 163;
+pub const SC_SETRESUID                    : u32 = 164;
+pub const SC_GETRESUID                    : u32 = 165;
+pub const SC_POLL                         : u32 = 168;
+pub const SC_NFSSERVCTL                   : u32 = 169;
+pub const SC_SETRESGID                    : u32 = 170;
+pub const SC_GETRESGID                    : u32 = 171;
+pub const SC_PRCTL                        : u32 = 172;
+pub const SC_RT_SIGRETURN                 : u32 = 173;
+pub const SC_RT_SIGACTION                 : u32 = 174;
+pub const SC_RT_SIGPROCMASK               : u32 = 175;
+pub const SC_RT_SIGPENDING                : u32 = 176;
+pub const SC_RT_SIGTIMEDWAIT              : u32 = 177;
+pub const SC_RT_SIGQUEUEINFO              : u32 = 178;
+pub const SC_RT_SIGSUSPEND                : u32 = 179;
+pub const SC_PREAD64                      : u32 = 180;
+pub const SC_PWRITE64                     : u32 = 181;
+pub const SC_CHOWN                        : u32 = 182;
+pub const SC_GETCWD                       : u32 = 183;
+pub const SC_CAPGET                       : u32 = 184;
+pub const SC_CAPSET                       : u32 = 185;
+pub const SC_SIGALTSTACK                  : u32 = 186;
+pub const SC_SENDFILE                     : u32 = 187;
+pub const SC_VFORK                        : u32 = 190;
+pub const SC_UGETRLIMIT                   : u32 = 191;
+pub const SC_MMAP2                        : u32 = 192;
+pub const SC_TRUNCATE64                   : u32 = 193;
+pub const SC_FTRUNCATE64                  : u32 = 194;
+pub const SC_STAT64                       : u32 = 195;
+pub const SC_LSTAT64                      : u32 = 196;
+pub const SC_FSTAT64                      : u32 = 197;
+pub const SC_LCHOWN32                     : u32 = 198;
+pub const SC_GETUID32                     : u32 = 199;
+pub const SC_GETGID32                     : u32 = 200;
+pub const SC_GETEUID32                    : u32 = 201;
+pub const SC_GETEGID32                    : u32 = 202;
+pub const SC_SETREUID32                   : u32 = 203;
+pub const SC_SETREGID32                   : u32 = 204;
+pub const SC_GETGROUPS32                  : u32 = 205;
+pub const SC_SETGROUPS32                  : u32 = 206;
+pub const SC_FCHOWN32                     : u32 = 207;
+pub const SC_SETRESUID32                  : u32 = 208;
+pub const SC_GETRESUID32                  : u32 = 209;
+pub const SC_SETRESGID32                  : u32 = 210;
+pub const SC_GETRESGID32                  : u32 = 211;
+pub const SC_CHOWN32                      : u32 = 212;
+pub const SC_SETUID32                     : u32 = 213;
+pub const SC_SETGID32                     : u32 = 214;
+pub const SC_SETFSUID32                   : u32 = 215;
+pub const SC_SETFSGID32                   : u32 = 216;
+pub const SC_GETDENTS64                   : u32 = 217;
+pub const SC_PIVOT_ROOT                   : u32 = 218;
+pub const SC_MINCORE                      : u32 = 219;
+pub const SC_MADVISE                      : u32 = 220;
+pub const SC_FCNTL64                      : u32 = 221;
+pub const SC_GETTID                       : u32 = 224;
+pub const SC_READAHEAD                    : u32 = 225;
+pub const SC_SETXATTR                     : u32 = 226;
+pub const SC_LSETXATTR                    : u32 = 227;
+pub const SC_FSETXATTR                    : u32 = 228;
+pub const SC_GETXATTR                     : u32 = 229;
+pub const SC_LGETXATTR                    : u32 = 230;
+pub const SC_FGETXATTR                    : u32 = 231;
+pub const SC_LISTXATTR                    : u32 = 232;
+pub const SC_LLISTXATTR                   : u32 = 233;
+pub const SC_FLISTXATTR                   : u32 = 234;
+pub const SC_REMOVEXATTR                  : u32 = 235;
+pub const SC_LREMOVEXATTR                 : u32 = 236;
+pub const SC_FREMOVEXATTR                 : u32 = 237;
+pub const SC_TKILL                        : u32 = 238;
+pub const SC_SENDFILE64                   : u32 = 239;
+pub const SC_FUTEX                        : u32 = 240;
+pub const SC_SCHED_SETAFFINITY            : u32 = 241;
+pub const SC_SCHED_GETAFFINITY            : u32 = 242;
+pub const SC_IO_SETUP                     : u32 = 243;
+pub const SC_IO_DESTROY                   : u32 = 244;
+pub const SC_IO_GETEVENTS                 : u32 = 245;
+pub const SC_IO_SUBMIT                    : u32 = 246;
+pub const SC_IO_CANCEL                    : u32 = 247;
+pub const SC_EXIT_GROUP                   : u32 = 248;
+pub const SC_LOOKUP_DCOOKIE               : u32 = 249;
+pub const SC_EPOLL_CREATE                 : u32 = 250;
+pub const SC_EPOLL_CTL                    : u32 = 251;
+pub const SC_EPOLL_WAIT                   : u32 = 252;
+pub const SC_REMAP_FILE_PAGES             : u32 = 253;
+pub const SC_SET_TID_ADDRESS              : u32 = 256;
+pub const SC_TIMER_CREATE                 : u32 = 257;
+pub const SC_TIMER_SETTIME                : u32 = 258;
+pub const SC_TIMER_GETTIME                : u32 = 259;
+pub const SC_TIMER_GETOVERRUN             : u32 = 260;
+pub const SC_TIMER_DELETE                 : u32 = 261;
+pub const SC_CLOCK_SETTIME                : u32 = 262;
+pub const SC_CLOCK_GETTIME                : u32 = 263;
+pub const SC_CLOCK_GETRES                 : u32 = 264;
+pub const SC_CLOCK_NANOSLEEP              : u32 = 265;
+pub const SC_STATFS64                     : u32 = 266;
+pub const SC_FSTATFS64                    : u32 = 267;
+pub const SC_TGKILL                       : u32 = 268;
+pub const SC_UTIMES                       : u32 = 269;
+pub const SC_ARM_FADVISE64_64             : u32 = 270;
+pub const SC_PCICONFIG_IOBASE             : u32 = 271;
+pub const SC_PCICONFIG_READ               : u32 = 272;
+pub const SC_PCICONFIG_WRITE              : u32 = 273;
+pub const SC_MQ_OPEN                      : u32 = 274;
+pub const SC_MQ_UNLINK                    : u32 = 275;
+pub const SC_MQ_TIMEDSEND                 : u32 = 276;
+pub const SC_MQ_TIMEDRECEIVE              : u32 = 277;
+pub const SC_MQ_NOTIFY                    : u32 = 278;
+pub const SC_MQ_GETSETATTR                : u32 = 279;
+pub const SC_WAITID                       : u32 = 280;
+pub const SC_SOCKET                       : u32 = 281;
+pub const SC_BIND                         : u32 = 282;
+pub const SC_CONNECT                      : u32 = 283;
+pub const SC_LISTEN                       : u32 = 284;
+pub const SC_ACCEPT                       : u32 = 285;
+pub const SC_GETSOCKNAME                  : u32 = 286;
+pub const SC_GETPEERNAME                  : u32 = 287;
+pub const SC_SOCKETPAIR                   : u32 = 288;
+pub const SC_SEND                         : u32 = 289;
+pub const SC_SENDTO                       : u32 = 290;
+pub const SC_RECV                         : u32 = 291;
+pub const SC_RECVFROM                     : u32 = 292;
+pub const SC_SHUTDOWN                     : u32 = 293;
+pub const SC_SETSOCKOPT                   : u32 = 294;
+pub const SC_GETSOCKOPT                   : u32 = 295;
+pub const SC_SENDMSG                      : u32 = 296;
+pub const SC_RECVMSG                      : u32 = 297;
+pub const SC_SEMOP                        : u32 = 298;
+pub const SC_SEMGET                       : u32 = 299;
+pub const SC_SEMCTL                       : u32 = 300;
+pub const SC_MSGSND                       : u32 = 301;
+pub const SC_MSGRCV                       : u32 = 302;
+pub const SC_MSGGET                       : u32 = 303;
+pub const SC_MSGCTL                       : u32 = 304;
+pub const SC_SHMAT                        : u32 = 305;
+pub const SC_SHMDT                        : u32 = 306;
+pub const SC_SHMGET                       : u32 = 307;
+pub const SC_SHMCTL                       : u32 = 308;
+pub const SC_ADD_KEY                      : u32 = 309;
+pub const SC_REQUEST_KEY                  : u32 = 310;
+pub const SC_KEYCTL                       : u32 = 311;
+pub const SC_SEMTIMEDOP                   : u32 = 312;
+pub const SC_VSERVER                      : u32 = 313;
+pub const SC_IOPRIO_SET                   : u32 = 314;
+pub const SC_IOPRIO_GET                   : u32 = 315;
+pub const SC_INOTIFY_INIT                 : u32 = 316;
+pub const SC_INOTIFY_ADD_WATCH            : u32 = 317;
+pub const SC_INOTIFY_RM_WATCH             : u32 = 318;
+pub const SC_MBIND                        : u32 = 319;
+pub const SC_GET_MEMPOLICY                : u32 = 320;
+pub const SC_SET_MEMPOLICY                : u32 = 321;
+pub const SC_OPENAT                       : u32 = 322;
+pub const SC_MKDIRAT                      : u32 = 323;
+pub const SC_MKNODAT                      : u32 = 324;
+pub const SC_FCHOWNAT                     : u32 = 325;
+pub const SC_FUTIMESAT                    : u32 = 326;
+pub const SC_FSTATAT64                    : u32 = 327;
+pub const SC_UNLINKAT                     : u32 = 328;
+pub const SC_RENAMEAT                     : u32 = 329;
+pub const SC_LINKAT                       : u32 = 330;
+pub const SC_SYMLINKAT                    : u32 = 331;
+pub const SC_READLINKAT                   : u32 = 332;
+pub const SC_FCHMODAT                     : u32 = 333;
+pub const SC_FACCESSAT                    : u32 = 334;
+pub const SC_PSELECT6                     : u32 = 335;
+pub const SC_PPOLL                        : u32 = 336;
+pub const SC_UNSHARE                      : u32 = 337;
+pub const SC_SET_ROBUST_LIST              : u32 = 338;
+pub const SC_GET_ROBUST_LIST              : u32 = 339;
+pub const SC_SPLICE                       : u32 = 340;
+pub const SC_ARM_SYNC_FILE_RANGE          : u32 = 341;
+pub const SC_TEE                          : u32 = 342;
+pub const SC_VMSPLICE                     : u32 = 343;
+pub const SC_MOVE_PAGES                   : u32 = 344;
+pub const SC_GETCPU                       : u32 = 345;
+pub const SC_EPOLL_PWAIT                  : u32 = 346;
+pub const SC_KEXEC_LOAD                   : u32 = 347;
+pub const SC_UTIMENSAT                    : u32 = 348;
+pub const SC_SIGNALFD                     : u32 = 349;
+pub const SC_TIMERFD_CREATE               : u32 = 350;
+pub const SC_EVENTFD                      : u32 = 351;
+pub const SC_FALLOCATE                    : u32 = 352;
+pub const SC_TIMERFD_SETTIME              : u32 = 353;
+pub const SC_TIMERFD_GETTIME              : u32 = 354;
+pub const SC_SIGNALFD4                    : u32 = 355;
+pub const SC_EVENTFD2                     : u32 = 356;
+pub const SC_EPOLL_CREATE1                : u32 = 357;
+pub const SC_DUP3                         : u32 = 358;
+pub const SC_PIPE2                        : u32 = 359;
+pub const SC_INOTIFY_INIT1                : u32 = 360;
+pub const SC_PREADV                       : u32 = 361;
+pub const SC_PWRITEV                      : u32 = 362;
+pub const SC_RT_TGSIGQUEUEINFO            : u32 = 363;
+pub const SC_PERF_EVENT_OPEN              : u32 = 364;
+pub const SC_RECVMMSG                     : u32 = 365;
+pub const SC_ACCEPT4                      : u32 = 366;
+pub const SC_FANOTIFY_INIT                : u32 = 367;
+pub const SC_FANOTIFY_MARK                : u32 = 368;
+pub const SC_PRLIMIT64                    : u32 = 369;
+pub const SC_NAME_TO_HANDLE_AT            : u32 = 370;
+pub const SC_OPEN_BY_HANDLE_AT            : u32 = 371;
+pub const SC_CLOCK_ADJTIME                : u32 = 372;
+pub const SC_SYNCFS                       : u32 = 373;
+pub const SC_SENDMMSG                     : u32 = 374;
+pub const SC_SETNS                        : u32 = 375;
+pub const SC_PROCESS_VM_READV             : u32 = 376;
+pub const SC_PROCESS_VM_WRITEV            : u32 = 377;
+pub const SC_KCMP                         : u32 = 378;
+pub const SC_FINIT_MODULE                 : u32 = 379;
+pub const SC_SCHED_SETATTR                : u32 = 380;
+pub const SC_SCHED_GETATTR                : u32 = 381;
+pub const SC_RENAMEAT2                    : u32 = 382;
+pub const SC_SECCOMP                      : u32 = 383;
+pub const SC_GETRANDOM                    : u32 = 384;
+pub const SC_MEMFD_CREATE                 : u32 = 385;
+pub const SC_BPF                          : u32 = 386;
+pub const SC_EXECVEAT                     : u32 = 387;
+pub const SC_USERFAULTFD                  : u32 = 388;
+pub const SC_MEMBARRIER                   : u32 = 389;
+pub const SC_MLOCK2                       : u32 = 390;
+pub const SC_COPY_FILE_RANGE              : u32 = 391;
+pub const SC_PREADV2                      : u32 = 392;
+pub const SC_PWRITEV2                     : u32 = 393;
+pub const SC_PKEY_MPROTECT                : u32 = 394;
+pub const SC_PKEY_ALLOC                   : u32 = 395;
+pub const SC_PKEY_FREE                    : u32 = 396;
+pub const SC_STATX                        : u32 = 397;
+pub const SC_RSEQ                         : u32 = 398;
+pub const SC_IO_PGETEVENTS                : u32 = 399;
+pub const SC_MIGRATE_PAGES                : u32 = 400;
+pub const SC_KEXEC_FILE_LOAD              : u32 = 401;
+pub const SC_CLOCK_GETTIME64              : u32 = 403;
+pub const SC_CLOCK_SETTIME64              : u32 = 404;
+pub const SC_CLOCK_ADJTIME64              : u32 = 405;
+pub const SC_CLOCK_GETRES_TIME64          : u32 = 406;
+pub const SC_CLOCK_NANOSLEEP_TIME64       : u32 = 407;
+pub const SC_TIMER_GETTIME64              : u32 = 408;
+pub const SC_TIMER_SETTIME64              : u32 = 409;
+pub const SC_TIMERFD_GETTIME64            : u32 = 410;
+pub const SC_TIMERFD_SETTIME64            : u32 = 411;
+pub const SC_UTIMENSAT_TIME64             : u32 = 412;
+pub const SC_PSELECT6_TIME64              : u32 = 413;
+pub const SC_PPOLL_TIME64                 : u32 = 414;
+pub const SC_IO_PGETEVENTS_TIME64         : u32 = 416;
+pub const SC_RECVMMSG_TIME64              : u32 = 417;
+pub const SC_MQ_TIMEDSEND_TIME64          : u32 = 418;
+pub const SC_MQ_TIMEDRECEIVE_TIME64       : u32 = 419;
+pub const SC_SEMTIMEDOP_TIME64            : u32 = 420;
+pub const SC_RT_SIGTIMEDWAIT_TIME64       : u32 = 421;
+pub const SC_FUTEX_TIME64                 : u32 = 422;
+pub const SC_SCHED_RR_GET_INTERVAL_TIME64 : u32 = 423;
+pub const SC_PIDFD_SEND_SIGNAL            : u32 = 424;
+pub const SC_IO_URING_SETUP               : u32 = 425;
+pub const SC_IO_URING_ENTER               : u32 = 426;
+pub const SC_IO_URING_REGISTER            : u32 = 427;
+pub const SC_OPEN_TREE                    : u32 = 428;
+pub const SC_MOVE_MOUNT                   : u32 = 429;
+pub const SC_FSOPEN                       : u32 = 430;
+pub const SC_FSCONFIG                     : u32 = 431;
+pub const SC_FSMOUNT                      : u32 = 432;
+pub const SC_FSPICK                       : u32 = 433;
+pub const SC_PIDFD_OPEN                   : u32 = 434;
+pub const SC_CLONE3                       : u32 = 435;
+pub const SC_CLOSE_RANGE                  : u32 = 436;
+pub const SC_OPENAT2                      : u32 = 437;
+pub const SC_PIDFD_GETFD                  : u32 = 438;
+pub const SC_FACCESSAT2                   : u32 = 439;
+pub const SC_PROCESS_MADVISE              : u32 = 440;
+pub const SC_EPOLL_PWAIT2                 : u32 = 441;
+pub const SC_MOUNT_SETATTR                : u32 = 442;
+pub const SC_QUOTACTL_FD                  : u32 = 443;
+pub const SC_LANDLOCK_CREATE_RULESET      : u32 = 444;
+pub const SC_LANDLOCK_ADD_RULE            : u32 = 445;
+pub const SC_LANDLOCK_RESTRICT_SELF       : u32 = 446;
+pub const SC_PROCESS_MRELEASE             : u32 = 448;
+pub const SC_FUTEX_WAITV                  : u32 = 449;
+pub const SC_SET_MEMPOLICY_HOME_NODE      : u32 = 450;
+pub const SC_CACHESTAT                    : u32 = 451;
+pub const SC_FCHMODAT2                    : u32 = 452;
+pub const SC_MAP_SHADOW_STACK             : u32 = 453;
+pub const SC_FUTEX_WAKE                   : u32 = 454;
+pub const SC_FUTEX_WAIT                   : u32 = 455;
+pub const SC_FUTEX_REQUEUE                : u32 = 456;
+pub const SC_STATMOUNT                    : u32 = 457;
+pub const SC_LISTMOUNT                    : u32 = 458;
+pub const SC_LSM_GET_SELF_ATTR            : u32 = 459;
+pub const SC_LSM_SET_SELF_ATTR            : u32 = 460;
+pub const SC_LSM_LIST_MODULES             : u32 = 461;
+pub const SC_MSEAL                        : u32 = 462;
+pub const SC_ARM_BREAKPOINT               : u32 = 0xF0001;
+pub const SC_ARM_CACHEFLUSH               : u32 = 0xF0002;
+pub const SC_ARM_USR26                    : u32 = 0xF0003;
+pub const SC_ARM_USR32                    : u32 = 0xF0004;
+pub const SC_ARM_SET_TLS                  : u32 = 0xF0005;
+pub const SC_ARM_GET_TLS                  : u32 = 0xF0006;
+
+/// The name of the syscall numbered `nr` (e.g. `"write"` for [`SC_WRITE`], `"arm_set_tls"` for [`SC_ARM_SET_TLS`]), if it's in the EABI table
+pub fn name(nr: u32) -> Option<&'static str> {
+    Some(match nr {
+        SC_RESTART_SYSCALL              => "restart_syscall",
+        SC_EXIT                         => "exit",
+        SC_FORK                         => "fork",
+        SC_READ                         => "read",
+        SC_WRITE                        => "write",
+        SC_OPEN                         => "open",
+        SC_CLOSE                        => "close",
+        SC_CREAT                        => "creat",
+        SC_LINK                         => "link",
+        SC_UNLINK                       => "unlink",
+        SC_EXECVE                       => "execve",
+        SC_CHDIR                        => "chdir",
+        SC_MKNOD                        => "mknod",
+        SC_CHMOD                        => "chmod",
+        SC_LCHOWN                       => "lchown",
+        SC_LSEEK                        => "lseek",
+        SC_GETPID                       => "getpid",
+        SC_MOUNT                        => "mount",
+        SC_SETUID                       => "setuid",
+        SC_GETUID                       => "getuid",
+        SC_PTRACE                       => "ptrace",
+        SC_PAUSE                        => "pause",
+        SC_ACCESS                       => "access",
+        SC_NICE                         => "nice",
+        SC_SYNC                         => "sync",
+        SC_KILL                         => "kill",
+        SC_RENAME                       => "rename",
+        SC_MKDIR                        => "mkdir",
+        SC_RMDIR                        => "rmdir",
+        SC_DUP                          => "dup",
+        SC_PIPE                         => "pipe",
+        SC_TIMES                        => "times",
+        SC_BRK                          => "brk",
+        SC_SETGID                       => "setgid",
+        SC_GETGID                       => "getgid",
+        SC_GETEUID                      => "geteuid",
+        SC_GETEGID                      => "getegid",
+        SC_ACCT                         => "acct",
+        SC_UMOUNT2                      => "umount2",
+        SC_IOCTL                        => "ioctl",
+        SC_FCNTL                        => "fcntl",
+        SC_SETPGID                      => "setpgid",
+        SC_UMASK                        => "umask",
+        SC_CHROOT                       => "chroot",
+        SC_USTAT                        => "ustat",
+        SC_DUP2                         => "dup2",
+        SC_GETPPID                      => "getppid",
+        SC_GETPGRP                      => "getpgrp",
+        SC_SETSID                       => "setsid",
+        SC_SIGACTION                    => "sigaction",
+        SC_SETREUID                     => "setreuid",
+        SC_SETREGID                     => "setregid",
+        SC_SIGSUSPEND                   => "sigsuspend",
+        SC_SIGPENDING                   => "sigpending",
+        SC_SETHOSTNAME                  => "sethostname",
+        SC_SETRLIMIT                    => "setrlimit",
+        SC_GETRUSAGE                    => "getrusage",
+        SC_GETTIMEOFDAY                 => "gettimeofday",
+        SC_SETTIMEOFDAY                 => "settimeofday",
+        SC_GETGROUPS                    => "getgroups",
+        SC_SETGROUPS                    => "setgroups",
+        SC_SYMLINK                      => "symlink",
+        SC_READLINK                     => "readlink",
+        SC_USELIB                       => "uselib",
+        SC_SWAPON                       => "swapon",
+        SC_REBOOT                       => "reboot",
+        SC_MUNMAP                       => "munmap",
+        SC_TRUNCATE                     => "truncate",
+        SC_FTRUNCATE                    => "ftruncate",
+        SC_FCHMOD                       => "fchmod",
+        SC_FCHOWN                       => "fchown",
+        SC_GETPRIORITY                  => "getpriority",
+        SC_SETPRIORITY                  => "setpriority",
+        SC_STATFS                       => "statfs",
+        SC_FSTATFS                      => "fstatfs",
+        SC_SYSLOG                       => "syslog",
+        SC_SETITIMER                    => "setitimer",
+        SC_GETITIMER                    => "getitimer",
+        SC_STAT                         => "stat",
+        SC_LSTAT                        => "lstat",
+        SC_FSTAT                        => "fstat",
+        SC_VHANGUP                      => "vhangup",
+        SC_WAIT4                        => "wait4",
+        SC_SWAPOFF                      => "swapoff",
+        SC_SYSINFO                      => "sysinfo",
+        SC_FSYNC                        => "fsync",
+        SC_SIGRETURN                    => "sigreturn",
+        SC_CLONE                        => "clone",
+        SC_SETDOMAINNAME                => "setdomainname",
+        SC_UNAME                        => "uname",
+        SC_ADJTIMEX                     => "adjtimex",
+        SC_MPROTECT                     => "mprotect",
+        SC_SIGPROCMASK                  => "sigprocmask",
+        SC_INIT_MODULE                  => "init_module",
+        SC_DELETE_MODULE                => "delete_module",
+        SC_QUOTACTL                     => "quotactl",
+        SC_GETPGID                      => "getpgid",
+        SC_FCHDIR                       => "fchdir",
+        SC_BDFLUSH                      => "bdflush",
+        SC_SYSFS                        => "sysfs",
+        SC_PERSONALITY                  => "personality",
+        SC_SETFSUID                     => "setfsuid",
+        SC_SETFSGID                     => "setfsgid",
+        SC_LLSEEK                       => "_llseek",
+        SC_GETDENTS                     => "getdents",
+        SC_NEWSELECT                    => "_newselect",
+        SC_FLOCK                        => "flock",
+        SC_MSYNC                        => "msync",
+        SC_READV                        => "readv",
+        SC_WRITEV                       => "writev",
+        SC_GETSID                       => "getsid",
+        SC_FDATASYNC                    => "fdatasync",
+        SC_SYSCTL                       => "_sysctl",
+        SC_MLOCK                        => "mlock",
+        SC_MUNLOCK                      => "munlock",
+        SC_MLOCKALL                     => "mlockall",
+        SC_MUNLOCKALL                   => "munlockall",
+        SC_SCHED_SETPARAM               => "sched_setparam",
+        SC_SCHED_GETPARAM               => "sched_getparam",
+        SC_SCHED_SETSCHEDULER           => "sched_setscheduler",
+        SC_SCHED_GETSCHEDULER           => "sched_getscheduler",
+        SC_SCHED_YIELD                  => "sched_yield",
+        SC_SCHED_GET_PRIORITY_MAX       => "sched_get_priority_max",
+        SC_SCHED_GET_PRIORITY_MIN       => "sched_get_priority_min",
+        SC_SCHED_RR_GET_INTERVAL        => "sched_rr_get_interval",
+        SC_NANOSLEEP                    => "nanosleep",
+        SC_MREMAP                       => "mremap",
+        SC_SETRESUID                    => "setresuid",
+        SC_GETRESUID                    => "getresuid",
+        SC_POLL                         => "poll",
+        SC_NFSSERVCTL                   => "nfsservctl",
+        SC_SETRESGID                    => "setresgid",
+        SC_GETRESGID                    => "getresgid",
+        SC_PRCTL                        => "prctl",
+        SC_RT_SIGRETURN                 => "rt_sigreturn",
+        SC_RT_SIGACTION                 => "rt_sigaction",
+        SC_RT_SIGPROCMASK               => "rt_sigprocmask",
+        SC_RT_SIGPENDING                => "rt_sigpending",
+        SC_RT_SIGTIMEDWAIT              => "rt_sigtimedwait",
+        SC_RT_SIGQUEUEINFO              => "rt_sigqueueinfo",
+        SC_RT_SIGSUSPEND                => "rt_sigsuspend",
+        SC_PREAD64                      => "pread64",
+        SC_PWRITE64                     => "pwrite64",
+        SC_CHOWN                        => "chown",
+        SC_GETCWD                       => "getcwd",
+        SC_CAPGET                       => "capget",
+        SC_CAPSET                       => "capset",
+        SC_SIGALTSTACK                  => "sigaltstack",
+        SC_SENDFILE                     => "sendfile",
+        SC_VFORK                        => "vfork",
+        SC_UGETRLIMIT                   => "ugetrlimit",
+        SC_MMAP2                        => "mmap2",
+        SC_TRUNCATE64                   => "truncate64",
+        SC_FTRUNCATE64                  => "ftruncate64",
+        SC_STAT64                       => "stat64",
+        SC_LSTAT64                      => "lstat64",
+        SC_FSTAT64                      => "fstat64",
+        SC_LCHOWN32                     => "lchown32",
+        SC_GETUID32                     => "getuid32",
+        SC_GETGID32                     => "getgid32",
+        SC_GETEUID32                    => "geteuid32",
+        SC_GETEGID32                    => "getegid32",
+        SC_SETREUID32                   => "setreuid32",
+        SC_SETREGID32                   => "setregid32",
+        SC_GETGROUPS32                  => "getgroups32",
+        SC_SETGROUPS32                  => "setgroups32",
+        SC_FCHOWN32                     => "fchown32",
+        SC_SETRESUID32                  => "setresuid32",
+        SC_GETRESUID32                  => "getresuid32",
+        SC_SETRESGID32                  => "setresgid32",
+        SC_GETRESGID32                  => "getresgid32",
+        SC_CHOWN32                      => "chown32",
+        SC_SETUID32                     => "setuid32",
+        SC_SETGID32                     => "setgid32",
+        SC_SETFSUID32                   => "setfsuid32",
+        SC_SETFSGID32                   => "setfsgid32",
+        SC_GETDENTS64                   => "getdents64",
+        SC_PIVOT_ROOT                   => "pivot_root",
+        SC_MINCORE                      => "mincore",
+        SC_MADVISE                      => "madvise",
+        SC_FCNTL64                      => "fcntl64",
+        SC_GETTID                       => "gettid",
+        SC_READAHEAD                    => "readahead",
+        SC_SETXATTR                     => "setxattr",
+        SC_LSETXATTR                    => "lsetxattr",
+        SC_FSETXATTR                    => "fsetxattr",
+        SC_GETXATTR                     => "getxattr",
+        SC_LGETXATTR                    => "lgetxattr",
+        SC_FGETXATTR                    => "fgetxattr",
+        SC_LISTXATTR                    => "listxattr",
+        SC_LLISTXATTR                   => "llistxattr",
+        SC_FLISTXATTR                   => "flistxattr",
+        SC_REMOVEXATTR                  => "removexattr",
+        SC_LREMOVEXATTR                 => "lremovexattr",
+        SC_FREMOVEXATTR                 => "fremovexattr",
+        SC_TKILL                        => "tkill",
+        SC_SENDFILE64                   => "sendfile64",
+        SC_FUTEX                        => "futex",
+        SC_SCHED_SETAFFINITY            => "sched_setaffinity",
+        SC_SCHED_GETAFFINITY            => "sched_getaffinity",
+        SC_IO_SETUP                     => "io_setup",
+        SC_IO_DESTROY                   => "io_destroy",
+        SC_IO_GETEVENTS                 => "io_getevents",
+        SC_IO_SUBMIT                    => "io_submit",
+        SC_IO_CANCEL                    => "io_cancel",
+        SC_EXIT_GROUP                   => "exit_group",
+        SC_LOOKUP_DCOOKIE               => "lookup_dcookie",
+        SC_EPOLL_CREATE                 => "epoll_create",
+        SC_EPOLL_CTL                    => "epoll_ctl",
+        SC_EPOLL_WAIT                   => "epoll_wait",
+        SC_REMAP_FILE_PAGES             => "remap_file_pages",
+        SC_SET_TID_ADDRESS              => "set_tid_address",
+        SC_TIMER_CREATE                 => "timer_create",
+        SC_TIMER_SETTIME                => "timer_settime",
+        SC_TIMER_GETTIME                => "timer_gettime",
+        SC_TIMER_GETOVERRUN             => "timer_getoverrun",
+        SC_TIMER_DELETE                 => "timer_delete",
+        SC_CLOCK_SETTIME                => "clock_settime",
+        SC_CLOCK_GETTIME                => "clock_gettime",
+        SC_CLOCK_GETRES                 => "clock_getres",
+        SC_CLOCK_NANOSLEEP              => "clock_nanosleep",
+        SC_STATFS64                     => "statfs64",
+        SC_FSTATFS64                    => "fstatfs64",
+        SC_TGKILL                       => "tgkill",
+        SC_UTIMES                       => "utimes",
+        SC_ARM_FADVISE64_64             => "arm_fadvise64_64",
+        SC_PCICONFIG_IOBASE             => "pciconfig_iobase",
+        SC_PCICONFIG_READ               => "pciconfig_read",
+        SC_PCICONFIG_WRITE              => "pciconfig_write",
+        SC_MQ_OPEN                      => "mq_open",
+        SC_MQ_UNLINK                    => "mq_unlink",
+        SC_MQ_TIMEDSEND                 => "mq_timedsend",
+        SC_MQ_TIMEDRECEIVE              => "mq_timedreceive",
+        SC_MQ_NOTIFY                    => "mq_notify",
+        SC_MQ_GETSETATTR                => "mq_getsetattr",
+        SC_WAITID                       => "waitid",
+        SC_SOCKET                       => "socket",
+        SC_BIND                         => "bind",
+        SC_CONNECT                      => "connect",
+        SC_LISTEN                       => "listen",
+        SC_ACCEPT                       => "accept",
+        SC_GETSOCKNAME                  => "getsockname",
+        SC_GETPEERNAME                  => "getpeername",
+        SC_SOCKETPAIR                   => "socketpair",
+        SC_SEND                         => "send",
+        SC_SENDTO                       => "sendto",
+        SC_RECV                         => "recv",
+        SC_RECVFROM                     => "recvfrom",
+        SC_SHUTDOWN                     => "shutdown",
+        SC_SETSOCKOPT                   => "setsockopt",
+        SC_GETSOCKOPT                   => "getsockopt",
+        SC_SENDMSG                      => "sendmsg",
+        SC_RECVMSG                      => "recvmsg",
+        SC_SEMOP                        => "semop",
+        SC_SEMGET                       => "semget",
+        SC_SEMCTL                       => "semctl",
+        SC_MSGSND                       => "msgsnd",
+        SC_MSGRCV                       => "msgrcv",
+        SC_MSGGET                       => "msgget",
+        SC_MSGCTL                       => "msgctl",
+        SC_SHMAT                        => "shmat",
+        SC_SHMDT                        => "shmdt",
+        SC_SHMGET                       => "shmget",
+        SC_SHMCTL                       => "shmctl",
+        SC_ADD_KEY                      => "add_key",
+        SC_REQUEST_KEY                  => "request_key",
+        SC_KEYCTL                       => "keyctl",
+        SC_SEMTIMEDOP                   => "semtimedop",
+        SC_VSERVER                      => "vserver",
+        SC_IOPRIO_SET                   => "ioprio_set",
+        SC_IOPRIO_GET                   => "ioprio_get",
+        SC_INOTIFY_INIT                 => "inotify_init",
+        SC_INOTIFY_ADD_WATCH            => "inotify_add_watch",
+        SC_INOTIFY_RM_WATCH             => "inotify_rm_watch",
+        SC_MBIND                        => "mbind",
+        SC_GET_MEMPOLICY                => "get_mempolicy",
+        SC_SET_MEMPOLICY                => "set_mempolicy",
+        SC_OPENAT                       => "openat",
+        SC_MKDIRAT                      => "mkdirat",
+        SC_MKNODAT                      => "mknodat",
+        SC_FCHOWNAT                     => "fchownat",
+        SC_FUTIMESAT                    => "futimesat",
+        SC_FSTATAT64                    => "fstatat64",
+        SC_UNLINKAT                     => "unlinkat",
+        SC_RENAMEAT                     => "renameat",
+        SC_LINKAT                       => "linkat",
+        SC_SYMLINKAT                    => "symlinkat",
+        SC_READLINKAT                   => "readlinkat",
+        SC_FCHMODAT                     => "fchmodat",
+        SC_FACCESSAT                    => "faccessat",
+        SC_PSELECT6                     => "pselect6",
+        SC_PPOLL                        => "ppoll",
+        SC_UNSHARE                      => "unshare",
+        SC_SET_ROBUST_LIST              => "set_robust_list",
+        SC_GET_ROBUST_LIST              => "get_robust_list",
+        SC_SPLICE                       => "splice",
+        SC_ARM_SYNC_FILE_RANGE          => "arm_sync_file_range",
+        SC_TEE                          => "tee",
+        SC_VMSPLICE                     => "vmsplice",
+        SC_MOVE_PAGES                   => "move_pages",
+        SC_GETCPU                       => "getcpu",
+        SC_EPOLL_PWAIT                  => "epoll_pwait",
+        SC_KEXEC_LOAD                   => "kexec_load",
+        SC_UTIMENSAT                    => "utimensat",
+        SC_SIGNALFD                     => "signalfd",
+        SC_TIMERFD_CREATE               => "timerfd_create",
+        SC_EVENTFD                      => "eventfd",
+        SC_FALLOCATE                    => "fallocate",
+        SC_TIMERFD_SETTIME              => "timerfd_settime",
+        SC_TIMERFD_GETTIME              => "timerfd_gettime",
+        SC_SIGNALFD4                    => "signalfd4",
+        SC_EVENTFD2                     => "eventfd2",
+        SC_EPOLL_CREATE1                => "epoll_create1",
+        SC_DUP3                         => "dup3",
+        SC_PIPE2                        => "pipe2",
+        SC_INOTIFY_INIT1                => "inotify_init1",
+        SC_PREADV                       => "preadv",
+        SC_PWRITEV                      => "pwritev",
+        SC_RT_TGSIGQUEUEINFO            => "rt_tgsigqueueinfo",
+        SC_PERF_EVENT_OPEN              => "perf_event_open",
+        SC_RECVMMSG                     => "recvmmsg",
+        SC_ACCEPT4                      => "accept4",
+        SC_FANOTIFY_INIT                => "fanotify_init",
+        SC_FANOTIFY_MARK                => "fanotify_mark",
+        SC_PRLIMIT64                    => "prlimit64",
+        SC_NAME_TO_HANDLE_AT            => "name_to_handle_at",
+        SC_OPEN_BY_HANDLE_AT            => "open_by_handle_at",
+        SC_CLOCK_ADJTIME                => "clock_adjtime",
+        SC_SYNCFS                       => "syncfs",
+        SC_SENDMMSG                     => "sendmmsg",
+        SC_SETNS                        => "setns",
+        SC_PROCESS_VM_READV             => "process_vm_readv",
+        SC_PROCESS_VM_WRITEV            => "process_vm_writev",
+        SC_KCMP                         => "kcmp",
+        SC_FINIT_MODULE                 => "finit_module",
+        SC_SCHED_SETATTR                => "sched_setattr",
+        SC_SCHED_GETATTR                => "sched_getattr",
+        SC_RENAMEAT2                    => "renameat2",
+        SC_SECCOMP                      => "seccomp",
+        SC_GETRANDOM                    => "getrandom",
+        SC_MEMFD_CREATE                 => "memfd_create",
+        SC_BPF                          => "bpf",
+        SC_EXECVEAT                     => "execveat",
+        SC_USERFAULTFD                  => "userfaultfd",
+        SC_MEMBARRIER                   => "membarrier",
+        SC_MLOCK2                       => "mlock2",
+        SC_COPY_FILE_RANGE              => "copy_file_range",
+        SC_PREADV2                      => "preadv2",
+        SC_PWRITEV2                     => "pwritev2",
+        SC_PKEY_MPROTECT                => "pkey_mprotect",
+        SC_PKEY_ALLOC                   => "pkey_alloc",
+        SC_PKEY_FREE                    => "pkey_free",
+        SC_STATX                        => "statx",
+        SC_RSEQ                         => "rseq",
+        SC_IO_PGETEVENTS                => "io_pgetevents",
+        SC_MIGRATE_PAGES                => "migrate_pages",
+        SC_KEXEC_FILE_LOAD              => "kexec_file_load",
+        SC_CLOCK_GETTIME64              => "clock_gettime64",
+        SC_CLOCK_SETTIME64              => "clock_settime64",
+        SC_CLOCK_ADJTIME64              => "clock_adjtime64",
+        SC_CLOCK_GETRES_TIME64          => "clock_getres_time64",
+        SC_CLOCK_NANOSLEEP_TIME64       => "clock_nanosleep_time64",
+        SC_TIMER_GETTIME64              => "timer_gettime64",
+        SC_TIMER_SETTIME64              => "timer_settime64",
+        SC_TIMERFD_GETTIME64            => "timerfd_gettime64",
+        SC_TIMERFD_SETTIME64            => "timerfd_settime64",
+        SC_UTIMENSAT_TIME64             => "utimensat_time64",
+        SC_PSELECT6_TIME64              => "pselect6_time64",
+        SC_PPOLL_TIME64                 => "ppoll_time64",
+        SC_IO_PGETEVENTS_TIME64         => "io_pgetevents_time64",
+        SC_RECVMMSG_TIME64              => "recvmmsg_time64",
+        SC_MQ_TIMEDSEND_TIME64          => "mq_timedsend_time64",
+        SC_MQ_TIMEDRECEIVE_TIME64       => "mq_timedreceive_time64",
+        SC_SEMTIMEDOP_TIME64            => "semtimedop_time64",
+        SC_RT_SIGTIMEDWAIT_TIME64       => "rt_sigtimedwait_time64",
+        SC_FUTEX_TIME64                 => "futex_time64",
+        SC_SCHED_RR_GET_INTERVAL_TIME64 => "sched_rr_get_interval_time64",
+        SC_PIDFD_SEND_SIGNAL            => "pidfd_send_signal",
+        SC_IO_URING_SETUP               => "io_uring_setup",
+        SC_IO_URING_ENTER               => "io_uring_enter",
+        SC_IO_URING_REGISTER            => "io_uring_register",
+        SC_OPEN_TREE                    => "open_tree",
+        SC_MOVE_MOUNT                   => "move_mount",
+        SC_FSOPEN                       => "fsopen",
+        SC_FSCONFIG                     => "fsconfig",
+        SC_FSMOUNT                      => "fsmount",
+        SC_FSPICK                       => "fspick",
+        SC_PIDFD_OPEN                   => "pidfd_open",
+        SC_CLONE3                       => "clone3",
+        SC_CLOSE_RANGE                  => "close_range",
+        SC_OPENAT2                      => "openat2",
+        SC_PIDFD_GETFD                  => "pidfd_getfd",
+        SC_FACCESSAT2                   => "faccessat2",
+        SC_PROCESS_MADVISE              => "process_madvise",
+        SC_EPOLL_PWAIT2                 => "epoll_pwait2",
+        SC_MOUNT_SETATTR                => "mount_setattr",
+        SC_QUOTACTL_FD                  => "quotactl_fd",
+        SC_LANDLOCK_CREATE_RULESET      => "landlock_create_ruleset",
+        SC_LANDLOCK_ADD_RULE            => "landlock_add_rule",
+        SC_LANDLOCK_RESTRICT_SELF       => "landlock_restrict_self",
+        SC_PROCESS_MRELEASE             => "process_mrelease",
+        SC_FUTEX_WAITV                  => "futex_waitv",
+        SC_SET_MEMPOLICY_HOME_NODE      => "set_mempolicy_home_node",
+        SC_CACHESTAT                    => "cachestat",
+        SC_FCHMODAT2                    => "fchmodat2",
+        SC_MAP_SHADOW_STACK             => "map_shadow_stack",
+        SC_FUTEX_WAKE                   => "futex_wake",
+        SC_FUTEX_WAIT                   => "futex_wait",
+        SC_FUTEX_REQUEUE                => "futex_requeue",
+        SC_STATMOUNT                    => "statmount",
+        SC_LISTMOUNT                    => "listmount",
+        SC_LSM_GET_SELF_ATTR            => "lsm_get_self_attr",
+        SC_LSM_SET_SELF_ATTR            => "lsm_set_self_attr",
+        SC_LSM_LIST_MODULES             => "lsm_list_modules",
+        SC_MSEAL                        => "mseal",
+        SC_ARM_BREAKPOINT               => "arm_breakpoint",
+        SC_ARM_CACHEFLUSH               => "arm_cacheflush",
+        SC_ARM_USR26                    => "arm_usr26",
+        SC_ARM_USR32                    => "arm_usr32",
+        SC_ARM_SET_TLS                  => "arm_set_tls",
+        SC_ARM_GET_TLS                  => "arm_get_tls",
+        _ => return None,
+    })
+}
+
+const EIO       : u32 = 5;  // I/O error
+const EBADF     : u32 = 9;  // Bad file number
+const ENOMEM    : u32 = 12; // Out of memory
+const EFAULT    : u32 = 14; // Bad address
+const EEXIST    : u32 = 17; // File exists
+const EINVAL    : u32 = 22; // Invalid argument
+const ENOSYS    : u32 = 38; // Invalid system call number
+
+/// A syscall's result: the value to return in r0, or the errno to return the negation of
+type SysResult = Result<u32, u32>;
+
+/// Handle the syscall the guest just made with `svc #imm`, keyed on r7, and return its result in r0
+pub(super) fn syscall(process: &mut Process, _imm: u32) {
+    let result = match process.cpu.registers[7] {
+        SC_EXIT | SC_EXIT_GROUP => exit(process),
+        SC_WRITE        => write(process),
+        SC_BRK          => brk(process),
+        SC_MUNMAP       => munmap(process),
+        SC_MPROTECT     => mprotect(process),
+        SC_MMAP2        => mmap2(process),
+        SC_ARM_SET_TLS  => set_tls(process),
+        _               => Err(ENOSYS),
+    };
+    process.cpu.registers[0] = match result {
+        Ok(r0)      => r0,
+        Err(errno)  => errno.wrapping_neg(),
+    };
+}
+
+fn exit(process: &mut Process) -> SysResult {
+    let status = process.cpu.registers[0];
+    process.exit_status = Some(status as i32);
+    Ok(status)
+}
+
+fn write(process: &mut Process) -> SysResult {
+    let [fileno, mut addr, size, ..] = process.cpu.registers;
+    let mut size = size as usize; // not 16-bit safe... but do you think I care?
+
+    let mut stderr : io::Stderr;
+    let mut stdout : io::Stdout;
+    let out : &mut dyn Write = match fileno {
+        1 => { stdout = io::stdout(); &mut stdout },
+        2 => { stderr = io::stderr(); &mut stderr },
+        _ => return Err(EBADF),
+    };
+
+    let mut buffer = [0u8; 512];
+    let mut written = 0u32;
+    while size > 0 {
+        let read = size.min(buffer.len());
+        if process.mem.try_read_bytes(addr, arm::MemoryFlags::READ, &mut buffer[..read]).is_err() {
+            return if written == 0 { Err(EFAULT) } else { Ok(written) }; // unless some was written
+        }
+        out.write_all(&buffer[..read]).unwrap();
+        addr = addr.wrapping_add(read as u32);
+        size -= read;
+        written += read as u32;
+    }
+    Ok(written)
+}
+
+/// Move the program break to r0 (if it's at least [`Process::start_brk`], and the pages in between are free), returning the new break - or the old one, on failure
+fn brk(process: &mut Process) -> SysResult {
+    let requested = process.cpu.registers[0];
+    let Process { mem, start_brk, brk, .. } = process;
+    if requested < *start_brk { return Ok(*brk) } // including brk(0), which just queries it
+
+    let page = mem.page_size() - 1;
+    let (Some(old_end), Some(new_end)) = (brk.checked_add(page), requested.checked_add(page)) else { return Ok(*brk) };
+    let (old_end, new_end) = (old_end & !page, new_end & !page);
+    if new_end > old_end {
+        if mem.find_unmapped(old_end .. new_end, new_end - old_end, page + 1) != Some(old_end) { return Ok(*brk) } // would collide with another mapping
+        mem.map(old_end .. new_end, arm::MemoryFlags::READ | arm::MemoryFlags::WRITE);
+    } else if new_end < old_end {
+        mem.unmap(new_end .. old_end);
+    }
+    mem.unlabel(*start_brk .. old_end.max(new_end));
+    mem.label(*start_brk .. new_end, "[heap]");
+    *brk = requested;
+    Ok(requested)
+}
+
+fn munmap(process: &mut Process) -> SysResult {
+    let [addr, size, ..] = process.cpu.registers;
+    let end = match page_end(addr, size) { Some(end) if addr & 0xFFF == 0 && size != 0 => end, _ => return Err(EINVAL) };
+    process.mem.unmap(addr .. end);
+    process.mem.unlabel(addr .. end);
+    Ok(0)
+}
+
+/// Map anonymous memory, or part of a [`Process::files`] file, returning the address.  Without MAP_FIXED, `addr` is a hint: it's used if it's free,
+/// otherwise the mapping goes in the lowest free range of [`INTERP_AREA`] (like linux's bottom-up `mmap_base` layout.)
+fn mmap2(process: &mut Process) -> SysResult {
+    const MAP_TYPE              : u32 = 0x0F; // MAP_SHARED (1), MAP_PRIVATE (2), MAP_SHARED_VALIDATE (3)
+    const MAP_FIXED             : u32 = 0x10;
+    const MAP_ANONYMOUS         : u32 = 0x20;
+    const MAP_FIXED_NOREPLACE   : u32 = 0x10_0000;
+
+    let [addr, size, prot, map, fd, pgoffset, ..] = process.cpu.registers;
+    let Process { mem, files, .. } = process;
+
+    if !(1 ..= 3).contains(&(map & MAP_TYPE)) || size == 0 { return Err(EINVAL) }
+    let Some(bytes) = page_end(0, size) else { return Err(ENOMEM) };
+    let is_free = |base: u32| mem.find_unmapped(base .. base.saturating_add(bytes), bytes, 0x1000) == Some(base); // PROT_NONE mappings aren't free
+
+    let base = if map & (MAP_FIXED | MAP_FIXED_NOREPLACE) != 0 {
+        if addr & 0xFFF != 0 { return Err(EINVAL) }
+        if page_end(addr, size).is_none() { return Err(ENOMEM) }
+        if map & MAP_FIXED == 0 && !is_free(addr) { return Err(EEXIST) }
+        addr
+    } else if addr != 0 && addr & 0xFFF == 0 && page_end(addr, size).is_some() && is_free(addr) {
+        addr
+    } else {
+        mem.find_unmapped(INTERP_AREA, bytes, 0x1000).ok_or(ENOMEM)?
+    };
+
+    let file = if map & MAP_ANONYMOUS != 0 { None } else { Some(files.get_mut(&fd).ok_or(EBADF)?) };
+    let flags = prot_flags(prot);
+    mem.map(base .. base + bytes, flags);
+    mem.unlabel(base .. base + bytes);
+    if let Some(file) = file {
+        let offset = u64::from(pgoffset) * 0x1000;
+        let len = file.metadata().map_or(0, |metadata| metadata.len());
+        let io_bytes = len.saturating_sub(offset).min(bytes.into()) as u32; // the rest of the last page (and any pages past EOF) stay zeroed
+        if mem.init_copy_io(base, flags, file, offset, io_bytes).is_err() {
+            mem.unmap(base .. base + bytes);
+            return Err(EIO);
+        }
+    }
+    Ok(base)
+}
+
+fn mprotect(process: &mut Process) -> SysResult {
+    let [addr, size, prot, ..] = process.cpu.registers;
+    if addr & 0xFFF != 0 { return Err(EINVAL) }
+    let Some(end) = page_end(addr, size) else { return Err(ENOMEM) };
+    process.mem.protect(addr .. end, prot_flags(prot)).map_err(|_| ENOMEM)?; // not (entirely) mapped - PROT_NONE reservations are, for malloc's mmap(PROT_NONE) then mprotect(PROT_READ | PROT_WRITE)
+    Ok(0)
+}
+
+fn set_tls(process: &mut Process) -> SysResult {
+    let tp = process.cpu.registers[0];
+    tls::set_tls(&mut process.cpu, &process.mem, tp);
+    Ok(0)
+}
+
+/// The page aligned end of the `size` bytes at `addr`, if they don't run past the end of the address space
+fn page_end(addr: u32, size: u32) -> Option<u32> {
+    let end = u64::from(addr) + ((u64::from(size) + 0xFFF) & !0xFFF);
+    u32::try_from(end).ok()
+}
+
+/// The memory flags of mmap / mprotect's `PROT_*` bits
+fn prot_flags(prot: u32) -> arm::MemoryFlags {
+    let mut flags = arm::MemoryFlags::NONE;
+    if prot & 0x1 != 0 { flags |= arm::MemoryFlags::READ;    } // PROT_READ
+    if prot & 0x2 != 0 { flags |= arm::MemoryFlags::WRITE;   } // PROT_WRITE
+    if prot & 0x4 != 0 { flags |= arm::MemoryFlags::EXECUTE; } // PROT_EXEC
+    flags
+}
@@ -0,0 +1,157 @@
+use crate::arm;
+
+use std::io;
+
+// References:
+// - ELF for the Arm(R) Architecture (AAELF32) §5.6 Relocation: https://github.com/ARM-software/abi-aa/blob/main/aaelf32/aaelf32.rst#relocation
+//
+// Limitations:
+// - Only the relocation types below: no GOT / PLT generating types (R_ARM_GOT_BREL, R_ARM_PLT32, ...), or group relocations.  The TLS relocations are left to the dynamic linker, which knows the TLS layout.
+// - No veneers: R_ARM_JUMP24 / R_ARM_THM_JUMP24 can't switch instruction sets, and out of range branches are errors
+// - Places are read & written regardless of their page flags, as little-endian data (BE8 code isn't supported)
+
+
+
+pub const R_ARM_NONE            : u32 = 0;
+pub const R_ARM_PC24            : u32 = 1;
+pub const R_ARM_ABS32           : u32 = 2;
+pub const R_ARM_REL32           : u32 = 3;
+pub const R_ARM_THM_CALL        : u32 = 10;
+pub const R_ARM_TLS_DTPMOD32    : u32 = 17;
+pub const R_ARM_TLS_DTPOFF32    : u32 = 18;
+pub const R_ARM_TLS_TPOFF32     : u32 = 19;
+pub const R_ARM_COPY            : u32 = 20;
+pub const R_ARM_GLOB_DAT        : u32 = 21;
+pub const R_ARM_JUMP_SLOT       : u32 = 22;
+pub const R_ARM_RELATIVE        : u32 = 23;
+pub const R_ARM_CALL            : u32 = 28;
+pub const R_ARM_JUMP24          : u32 = 29;
+pub const R_ARM_THM_JUMP24      : u32 = 30;
+pub const R_ARM_MOVW_ABS_NC     : u32 = 43;
+pub const R_ARM_MOVT_ABS        : u32 = 44;
+
+/// A relocation to apply to guest memory, with its symbol already resolved
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// `R_ARM_*`: the low 8 bits of `r_info`
+    pub r_type:     u32,
+    /// P: the (load biased) address of the place being relocated
+    pub place:      u32,
+    /// S: the (load biased) address of the symbol, or 0 for relocations without one
+    pub symbol:     u32,
+    /// T: `true` if `symbol` is a Thumb function (bit 0 of `symbol` is ignored either way)
+    pub thumb:      bool,
+    /// B(S): the load bias of the object defining `symbol` - used by R_ARM_RELATIVE
+    pub base:       u32,
+    /// A: the addend of an `Elf32_Rela`, or `None` for `Elf32_Rel`s, which keep their addend in the place itself
+    pub addend:     Option<i32>,
+}
+
+/// Apply `reloc` to `mem`
+pub fn apply(mem: &arm::Memory, reloc: &Relocation) -> io::Result<()> {
+    let Relocation { r_type, place: p, thumb, base, addend, .. } = *reloc;
+    let t = u32::from(thumb);
+    let s = if thumb { reloc.symbol & !1 } else { reloc.symbol };
+
+    match r_type {
+        R_ARM_NONE => Ok(()),
+        R_ARM_ABS32 | R_ARM_REL32 | R_ARM_GLOB_DAT | R_ARM_JUMP_SLOT | R_ARM_RELATIVE => {
+            let a = match (r_type, addend) {
+                (_, Some(a))                                => a as u32,
+                (R_ARM_GLOB_DAT | R_ARM_JUMP_SLOT, None)    => 0, // REL addends are ignored, as glibc does
+                (_, None)                                   => read_u32(mem, p)?,
+            };
+            let value = match r_type {
+                R_ARM_ABS32         => s.wrapping_add(a) | t,
+                R_ARM_REL32         => (s.wrapping_add(a) | t).wrapping_sub(p),
+                R_ARM_RELATIVE      => base.wrapping_add(a),
+                _                   => s.wrapping_add(a) | t, // R_ARM_GLOB_DAT, R_ARM_JUMP_SLOT
+            };
+            write_u32(mem, p, value)
+        },
+        R_ARM_PC24 | R_ARM_CALL | R_ARM_JUMP24 => {
+            let insn = read_u32(mem, p)?;
+            let h = if insn >> 28 == 0xF { (insn >> 23) & 2 } else { 0 }; // BLX's H bit: halfword offset
+            let a = addend.map_or((((insn << 8) as i32 >> 6) as u32) | h, |a| a as u32); // imm24 * 4 (+ H * 2), sign extended
+            let x = (s.wrapping_add(a) | t).wrapping_sub(p);
+            let insn = match (r_type, thumb) {
+                (_, false) if x & 3 != 0                    => return Err(invalid(format!("misaligned ARM branch target 0x{:08x} at 0x{:08x}", x.wrapping_add(p), p))),
+                (_, false)                                  => insn,
+                (R_ARM_CALL, true)                          => 0xFA00_0000 | ((x & 2) << 23), // BL (or BLX) → BLX, H = X[1]
+                (_, true)                                   => return Err(invalid(format!("{} can't branch to the Thumb function at 0x{:08x} from 0x{:08x} without a veneer", name(r_type), s, p))),
+            };
+            check_branch_range(x, 26, r_type, p)?;
+            write_u32(mem, p, (insn & 0xFF00_0000) | ((x >> 2) & 0x00FF_FFFF))
+        },
+        R_ARM_THM_CALL | R_ARM_THM_JUMP24 => {
+            let (hi, lo) = (read_u16(mem, p)?, read_u16(mem, p.wrapping_add(2))?);
+            let a = addend.map_or_else(|| thumb_branch_offset(hi, lo), |a| a as u32);
+            let (mut lo, x) = match (r_type, thumb) {
+                (_, true)               => (lo | 0x1000, (s.wrapping_add(a) | t).wrapping_sub(p)), // BL / B.W
+                (R_ARM_THM_CALL, false) => (lo & !0x1000, s.wrapping_add(a).wrapping_sub(p & !3)), // BL → BLX
+                (_, false)              => return Err(invalid(format!("R_ARM_THM_JUMP24 can't branch to the ARM function at 0x{:08x} from 0x{:08x} without a veneer", s, p))),
+            };
+            check_branch_range(x, 25, r_type, p)?;
+            let (sign, imm10, imm11) = ((x >> 24) & 1, (x >> 12) & 0x3FF, (x >> 1) & 0x7FF);
+            let (j1, j2) = (((x >> 23) & 1 ^ 1) ^ sign, ((x >> 22) & 1 ^ 1) ^ sign); // I1 = NOT(J1 EOR S)
+            let hi = (hi & 0xF800) | (sign << 10) as u16 | imm10 as u16;
+            lo = (lo & 0xD000) | (j1 << 13) as u16 | (j2 << 11) as u16 | imm11 as u16;
+            write_u16(mem, p, hi)?;
+            write_u16(mem, p.wrapping_add(2), lo)
+        },
+        R_ARM_MOVW_ABS_NC | R_ARM_MOVT_ABS => {
+            let insn = read_u32(mem, p)?;
+            let a = addend.map_or_else(|| (((insn >> 4) & 0xF000) | (insn & 0xFFF)) as i16 as u32, |a| a as u32); // imm4:imm12, sign extended
+            let value = if r_type == R_ARM_MOVW_ABS_NC { s.wrapping_add(a) | t } else { s.wrapping_add(a) >> 16 };
+            write_u32(mem, p, (insn & 0xFFF0_F000) | ((value & 0xF000) << 4) | (value & 0xFFF))
+        },
+        other => Err(invalid(format!("unsupported relocation type {} at 0x{:08x}", other, p))),
+    }
+}
+
+/// The name of the `R_ARM_*` relocation type `r_type`, if it's one [`apply`] supports (or R_ARM_COPY & the TLS relocations, which the loader handles itself)
+pub fn name(r_type: u32) -> &'static str {
+    match r_type {
+        R_ARM_NONE          => "R_ARM_NONE",
+        R_ARM_PC24          => "R_ARM_PC24",
+        R_ARM_ABS32         => "R_ARM_ABS32",
+        R_ARM_REL32         => "R_ARM_REL32",
+        R_ARM_THM_CALL      => "R_ARM_THM_CALL",
+        R_ARM_TLS_DTPMOD32  => "R_ARM_TLS_DTPMOD32",
+        R_ARM_TLS_DTPOFF32  => "R_ARM_TLS_DTPOFF32",
+        R_ARM_TLS_TPOFF32   => "R_ARM_TLS_TPOFF32",
+        R_ARM_COPY          => "R_ARM_COPY",
+        R_ARM_GLOB_DAT      => "R_ARM_GLOB_DAT",
+        R_ARM_JUMP_SLOT     => "R_ARM_JUMP_SLOT",
+        R_ARM_RELATIVE      => "R_ARM_RELATIVE",
+        R_ARM_CALL          => "R_ARM_CALL",
+        R_ARM_JUMP24        => "R_ARM_JUMP24",
+        R_ARM_THM_JUMP24    => "R_ARM_THM_JUMP24",
+        R_ARM_MOVW_ABS_NC   => "R_ARM_MOVW_ABS_NC",
+        R_ARM_MOVT_ABS      => "R_ARM_MOVT_ABS",
+        _                   => "R_ARM_???",
+    }
+}
+
+/// The sign extended S:I1:I2:imm10:imm11:0 offset encoded by a Thumb-2 BL / BLX / B.W
+fn thumb_branch_offset(hi: u16, lo: u16) -> u32 {
+    let (hi, lo) = (u32::from(hi), u32::from(lo));
+    let sign = (hi >> 10) & 1;
+    let (i1, i2) = (((lo >> 13) & 1 ^ sign) ^ 1, ((lo >> 11) & 1 ^ sign) ^ 1);
+    let offset = (sign << 24) | (i1 << 23) | (i2 << 22) | ((hi & 0x3FF) << 12) | ((lo & 0x7FF) << 1);
+    ((offset << 7) as i32 >> 7) as u32
+}
+
+/// Fail unless `x` fits in a signed `bits` bit branch offset
+fn check_branch_range(x: u32, bits: u32, r_type: u32, p: u32) -> io::Result<()> {
+    let x = x as i32;
+    if x < -(1 << (bits - 1)) || x >= 1 << (bits - 1) { return Err(invalid(format!("{} branch offset {} at 0x{:08x} is out of range", name(r_type), x, p))) }
+    Ok(())
+}
+
+fn read_u16(mem: &arm::Memory, addr: u32) -> io::Result<u16> { mem.try_read_u16_unaligned(addr, arm::MemoryFlags::NONE).map_err(|fault| invalid(fault.to_string())) }
+fn read_u32(mem: &arm::Memory, addr: u32) -> io::Result<u32> { mem.try_read_u32_unaligned(addr, arm::MemoryFlags::NONE).map_err(|fault| invalid(fault.to_string())) }
+fn write_u16(mem: &arm::Memory, addr: u32, value: u16) -> io::Result<()> { mem.try_write_u16_unaligned(addr, arm::MemoryFlags::NONE, value).map_err(|fault| invalid(fault.to_string())) }
+fn write_u32(mem: &arm::Memory, addr: u32, value: u32) -> io::Result<()> { mem.try_write_u32_unaligned(addr, arm::MemoryFlags::NONE, value).map_err(|fault| invalid(fault.to_string())) }
+
+fn invalid(reason: String) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf32::reloc: {}", reason)) }
@@ -0,0 +1,123 @@
+use super::*;
+
+// References:
+// - linux's Documentation/arch/arm/kernel_user_helpers.rst, and arch/arm/kernel/entry-armv.S (__kuser_helper_start)
+// - ELF Handling For Thread-Local Storage §3.4.1 (variant I, which ARM uses): https://www.akkadia.org/drepper/tls.pdf
+// - ELF for the Arm(R) Architecture (AAELF32) §5.6.1.1 (TLS relocations): https://github.com/ARM-software/abi-aa/blob/main/aaelf32/aaelf32.rst
+//
+// Limitations:
+// - Static TLS only: the PT_TLS blocks of the executable & its DT_NEEDED libraries are laid out once, before entry.  No `__tls_get_addr`, dlopen, or threads.
+// - With Linking::Interp, ld.so lays out TLS itself: only the vectors page & set_tls are provided
+// - The kuser helpers are __kuser_memory_barrier, __kuser_cmpxchg, __kuser_get_tls & __kuser_helper_version (3): no __kuser_cmpxchg64 (version 5)
+
+
+
+/// The vectors page linux maps into every ARM process, at the top of the address space, holding the "kuser" helpers
+pub(super) const VECTORS : std::ops::Range<u32> = 0xFFFF_0000 .. 0xFFFF_1000;
+const KUSER_TLS : u32 = 0xFFFF_0FF0; // where pre-ARMv6 __kuser_get_tls reads the TLS pointer from, without TPIDRURO
+
+/// The PT_TLS segment of a loaded object: its TLS initialization image (`.tdata`, followed by `.tbss`), and where its block went
+#[derive(Clone, Copy, Debug)]
+pub(super) struct TlsBlock {
+    /// The (load biased) address of the initialization image, within a PT_LOAD segment
+    pub image:  u32,
+    pub filesz: u32,
+    pub memsz:  u32,
+    pub align:  u32,
+    /// The TLS module ID (1 for the executable, if it has a PT_TLS), for R_ARM_TLS_DTPMOD32
+    pub module: u32,
+    /// The offset of the block from the thread pointer, for R_ARM_TLS_TPOFF32
+    pub offset: u32,
+}
+
+impl TlsBlock {
+    /// The PT_TLS segment of `phdrs`, as loaded `bias` bytes above its linked addresses, if there is one
+    pub(super) fn read(phdrs: &[Phdr], bias: u32) -> io::Result<Option<Self>> {
+        let Some(tls) = phdrs.iter().find(|phdr| phdr.p_type == 7) else { return Ok(None) }; // PT_TLS
+        if tls.p_filesz > tls.p_memsz { return Err(invalid("PT_TLS file size (p_filesz) exceeds memory size (p_memsz)")) }
+        if tls.p_align > 0x1000 || (tls.p_align != 0 && !tls.p_align.is_power_of_two()) { return Err(invalid(format!("PT_TLS has an unsupported alignment of 0x{:x}", tls.p_align))) }
+        Ok(Some(Self { image: tls.p_vaddr.wrapping_add(bias), filesz: tls.p_filesz, memsz: tls.p_memsz, align: tls.p_align.max(1), module: 0, offset: 0 }))
+    }
+}
+
+/// Lay out `blocks` variant I style: the thread pointer points at an 8 byte TCB (a DTV pointer & a reserved word), followed by each block in turn, suitably aligned.
+/// Assigns each block its module ID & offset.
+pub(super) fn layout_static_tls<'b>(blocks: impl IntoIterator<Item = &'b mut TlsBlock>) -> io::Result<()> {
+    let mut end = 8u32; // the TCB
+    for (i, block) in blocks.into_iter().enumerate() {
+        block.module = i as u32 + 1;
+        block.offset = end.checked_add(block.align - 1).ok_or_else(|| invalid("static TLS exceeds 4 GiB"))? & !(block.align - 1);
+        end = block.offset.checked_add(block.memsz).ok_or_else(|| invalid("static TLS exceeds 4 GiB"))?;
+    }
+    Ok(())
+}
+
+/// Map the initial thread's static TLS area for `blocks` (as laid out by [`layout_static_tls`]), initialized from their images and followed by the DTV,
+/// and point `cpu`'s thread pointer at it.  Does nothing if there are no blocks.
+pub(super) fn init_static_tls(cpu: &mut arm::Cpu, mem: &arm::Memory, blocks: &[TlsBlock]) -> io::Result<()> {
+    if blocks.is_empty() { return Ok(()) }
+    let word = |value: u32| if cpu.cpsr.e() { value.to_be_bytes() } else { value.to_le_bytes() };
+    let end = blocks.iter().map(|block| block.offset + block.memsz).max().unwrap_or(8);
+    let dtv = (end + 3) & !3;
+    let size = (dtv + 4 * (blocks.len() as u32 + 1) + 0xFFF) & !0xFFF;
+    let align = blocks.iter().map(|block| block.align).max().unwrap_or(1).max(0x1000);
+    let Some(tp) = mem.find_unmapped(INTERP_AREA, size, align) else { return Err(invalid(format!("no room for 0x{:x} bytes of static TLS", size))) };
+    mem.map(tp .. tp + size, arm::MemoryFlags::READ | arm::MemoryFlags::WRITE);
+    mem.label(tp .. tp + size, "[tls]");
+
+    write_guest(mem, tp, &word(tp + dtv))?; // TCB[0]: the DTV
+    write_guest(mem, tp + dtv, &word(blocks.len() as u32))?; // DTV[0]: the generation, here just the number of modules
+    for block in blocks {
+        let mut image = vec![0u8; block.filesz as usize];
+        read_guest(mem, block.image, &mut image)?;
+        write_guest(mem, tp + block.offset, &image)?; // .tbss stays zeroed
+        write_guest(mem, tp + dtv + 4 * block.module, &word(tp + block.offset))?; // DTV[module]
+    }
+    set_tls(cpu, mem, tp);
+    Ok(())
+}
+
+/// Set the thread pointer, as ARM linux's `set_tls` syscall does: TPIDRURO, and the copy pre-ARMv6 `__kuser_get_tls` reads
+pub(super) fn set_tls(cpu: &mut arm::Cpu, mem: &arm::Memory, tp: u32) {
+    cpu.cp15.tpidruro = tp;
+    let tp = if cpu.cpsr.e() { tp.to_be_bytes() } else { tp.to_le_bytes() };
+    let _ = write_guest(mem, KUSER_TLS, &tp); // only fails if the guest unmapped VECTORS, which it can't call into then either
+}
+
+/// Map [`VECTORS`] read & execute only, with the kuser helpers for `config`'s architecture, and data words of `endian`ness
+pub(super) fn map_vectors(mem: &arm::Memory, config: &arm::CpuConfig, endian: arm::Endian) -> io::Result<()> {
+    const BX_LR : u32 = 0xE12F_FF1E;
+    let v6 = config.arch >= arm::Arch::V6;
+    let memory_barrier : &[u32] = match config.arch { // 0xffff0fa0
+        arm::Arch::V7A  => &[0xF57F_F05B, BX_LR], // dmb ish
+        arm::Arch::V6   => &[0xEE07_0FBA, BX_LR], // mcr p15, 0, r0, c7, c10, 5 (CP15DMB)
+        _               => &[BX_LR],
+    };
+    let cmpxchg : &[u32] = if v6 { &[ // 0xffff0fc0: r0 = oldval, r1 = newval, r2 = ptr.  Returns r0 = 0 & C set if *ptr was changed.
+        0xE192_3F9F, // 1: ldrex r3, [r2]
+        0xE053_3000, // subs r3, r3, r0
+        0x0182_3F91, // strexeq r3, r1, [r2]
+        0x0333_0001, // teqeq r3, #1
+        0x0AFF_FFFA, // beq 1b
+        0xE273_0000, // rsbs r0, r3, #0
+        BX_LR,
+    ] } else { &[ // uniprocessor: nothing can interrupt the guest mid helper
+        0xE592_3000, // ldr r3, [r2]
+        0xE053_3000, // subs r3, r3, r0
+        0x0582_1000, // streq r1, [r2]
+        0xE273_0000, // rsbs r0, r3, #0
+        BX_LR,
+    ] };
+    let get_tls : &[u32] = if v6 { &[0xEE1D_0F70, BX_LR] } else { &[0xE59F_0008, BX_LR] }; // 0xffff0fe0: mrc p15, 0, r0, c13, c0, 3 (TPIDRURO), or ldr r0, [pc, #8] (KUSER_TLS)
+
+    mem.map(VECTORS, arm::MemoryFlags::READ | arm::MemoryFlags::EXECUTE);
+    mem.label(VECTORS, "[vectors]");
+    for (offset, code) in [(0xFA0, memory_barrier), (0xFC0, cmpxchg), (0xFE0, get_tls)] {
+        let code = code.iter().flat_map(|insn| insn.to_le_bytes()).collect::<Vec<_>>(); // code is little-endian, even in BE8 images
+        write_guest(mem, VECTORS.start + offset, &code)?;
+    }
+    let version = if endian == arm::Endian::Big { 3u32.to_be_bytes() } else { 3u32.to_le_bytes() };
+    write_guest(mem, VECTORS.start + 0xFFC, &version) // __kuser_helper_version
+}
+
+fn invalid(reason: impl std::fmt::Display) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::elf32::tls: {}", reason)) }
@@ -0,0 +1,211 @@
+use super::*;
+
+// References:
+// - Exception Handling ABI for the Arm(R) Architecture (EHABI32) §6 The Exception-Handling Table, §10 ARM-defined personality routines & table formats:
+//   https://github.com/ARM-software/abi-aa/blob/main/ehabi32/ehabi32.rst
+//
+// Limitations:
+// - Only the classic ARM-defined unwinding instructions: generic model (e.g. `__gxx_personality_v0`) entries are assumed to use them too, as GCC's & LLVM's do
+// - Only r4 ..= r15 & SP are tracked: VFP & iWMMXt pops just adjust the virtual SP, and callers' r0 ..= r3 aren't recovered
+// - Frames without a .ARM.exidx entry (e.g. hand written assembly, or code compiled with neither `-funwind-tables` nor `-fexceptions`) end the backtrace,
+//   except for the faulting frame itself, which is treated as a leaf function if it's outside any table (e.g. after calling a bad function pointer)
+
+
+
+/// PT_ARM_EXIDX: the segment of a loaded image holding its `.ARM.exidx` section
+pub const PT_ARM_EXIDX : u32 = 0x7000_0001;
+
+/// A frame of a guest call stack, as unwound by [`backtrace`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The instruction executing in this frame: the faulting instruction for the innermost frame, the return address (minus any Thumb bit) for its callers
+    pub pc: u32,
+    /// The stack pointer on entry to the instruction
+    pub sp: u32,
+}
+
+/// Unwind the guest call stack described by `registers` (with `registers[15]` being the address of the current instruction, not the pipelined PC)
+/// through the PT_ARM_EXIDX tables of `images`.  Stack & table words are big-endian if `big_endian` (BE8.)  Returns at most 64 frames, innermost first.
+pub fn backtrace(images: &[LoadedImage], mem: &arm::Memory, registers: &[u32; 16], big_endian: bool) -> Vec<Frame> {
+    let mut r = *registers;
+    let mut frames = vec![Frame { pc: r[15] & !1, sp: r[13] }];
+    while frames.len() < 64 {
+        let first = frames.len() == 1;
+        let lookup = if first { r[15] & !1 } else { (r[15] & !1).wrapping_sub(2) }; // the call instruction, not the return address (which may be another function's start)
+        let unwound = match find_entry(images, mem, lookup, big_endian) {
+            Some(entry) => Unwinder { mem, big_endian, r: &mut r }.unwind(entry),
+            None if first => { r[15] = r[14]; Some(()) }, // not in any table: best guess, a leaf function that hasn't touched the stack
+            None => None,
+        };
+        let (pc, sp) = (r[15] & !1, r[13]);
+        if unwound.is_none() || pc == 0 { break }
+        let Some(caller) = frames.last().copied() else { break };
+        if (pc, sp) == (caller.pc, caller.sp) { break } // no progress
+        frames.push(Frame { pc, sp });
+    }
+    frames
+}
+
+impl Process {
+    /// [`backtrace`] the guest from `pc` (e.g. [`arm::Fault::pc`]) and its current registers, through the unwind tables of its [`Process::images`]
+    pub fn backtrace(&self, pc: u32) -> Vec<Frame> {
+        let mut registers = self.cpu.registers;
+        registers[15] = pc;
+        backtrace(&self.images, &self.mem, &registers, self.cpu.cpsr.e())
+    }
+
+    /// The [`Process::backtrace`] from `pc` symbolized one frame per line, for [`Process::step`]'s errors, or `""` if it doesn't get past `pc`'s own frame
+    pub(super) fn describe_backtrace(&self, pc: u32) -> String {
+        let frames = self.backtrace(pc);
+        if frames.len() < 2 { return String::new() }
+        let mut lines = String::from("\nbacktrace:");
+        for (i, frame) in frames.iter().enumerate() { lines += &format!("\n    #{:<2} {}", i, symbolize(&self.images, frame.pc)); }
+        lines
+    }
+}
+
+/// Where a function's unwinding instructions are: inline in its index entry, or in `.ARM.extab`
+enum Entry {
+    CantUnwind,
+    /// The instruction bytes, in order
+    Inline([u8; 3]),
+    /// The address of the `.ARM.extab` entry
+    Table(u32),
+}
+
+/// The .ARM.exidx entry of the function containing `pc`, if an image has a table covering it
+fn find_entry(images: &[LoadedImage], mem: &arm::Memory, pc: u32, big_endian: bool) -> Option<Entry> {
+    let image = images.iter().find(|image| image.span.contains(&pc))?;
+    let exidx = image.exidx.clone()?;
+    let word = |addr: u32| read_word(mem, addr, big_endian);
+    let entries = (exidx.end - exidx.start) / 8;
+
+    let (mut lo, mut hi) = (0, entries); // binary search for the last entry starting at or before pc
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let entry = exidx.start + mid * 8;
+        if prel31(entry, word(entry)?) <= pc { lo = mid + 1 } else { hi = mid }
+    }
+    let entry = exidx.start + lo.checked_sub(1)? * 8;
+    match word(entry + 4)? {
+        1                           => Some(Entry::CantUnwind), // EXIDX_CANTUNWIND
+        data if data >> 31 == 1     => Some(Entry::Inline(compact0(data)?)),
+        data                        => Some(Entry::Table(prel31(entry + 4, data))),
+    }
+}
+
+/// A personality routine 0 (Su16) word: 0x80 followed by 3 unwinding instructions
+fn compact0(word: u32) -> Option<[u8; 3]> {
+    if word >> 24 != 0x80 { return None } // personality routines 1 & 2 are only allowed in .ARM.extab
+    Some([(word >> 16) as u8, (word >> 8) as u8, word as u8])
+}
+
+/// Resolve a place relative 31 bit offset at `place`
+fn prel31(place: u32, word: u32) -> u32 { place.wrapping_add(((word << 1) as i32 >> 1) as u32) }
+
+fn read_word(mem: &arm::Memory, addr: u32, big_endian: bool) -> Option<u32> {
+    let mut word = [0u8; 4];
+    read_guest(mem, addr, &mut word).ok()?;
+    Some(if big_endian { u32::from_be_bytes(word) } else { u32::from_le_bytes(word) })
+}
+
+/// Executes unwinding instructions against a virtual register set
+struct Unwinder<'u> {
+    mem:        &'u arm::Memory,
+    big_endian: bool,
+    r:          &'u mut [u32; 16],
+}
+
+impl Unwinder<'_> {
+    /// Unwind one frame according to `entry`, leaving the caller's registers in `r` (with r15 = the return address).  [`None`] if the frame can't be unwound.
+    fn unwind(&mut self, entry: Entry) -> Option<()> {
+        let bytes = match entry {
+            Entry::CantUnwind   => return None,
+            Entry::Inline(bytes) => bytes.to_vec(),
+            Entry::Table(extab) => {
+                let word = |addr: u32| read_word(self.mem, addr, self.big_endian);
+                let first = word(extab)?;
+                let (mut bytes, more, mut addr) = match first >> 24 {
+                    0x80                => (compact0(first)?.to_vec(), 0, extab.wrapping_add(4)), // Su16
+                    0x81 | 0x82         => (vec![(first >> 8) as u8, first as u8], (first >> 16) & 0xFF, extab.wrapping_add(4)), // Lu16, Lu32: N more words follow
+                    _ if first >> 31 == 0 => { // generic model: a personality routine, followed by ARM-format instructions (N words after the first)
+                        let second = word(extab.wrapping_add(4))?;
+                        (vec![(second >> 16) as u8, (second >> 8) as u8, second as u8], second >> 24, extab.wrapping_add(8))
+                    },
+                    _                   => return None, // personality routines 3 ..= 15 are reserved
+                };
+                for _ in 0 .. more {
+                    bytes.extend(word(addr)?.to_be_bytes());
+                    addr = addr.wrapping_add(4);
+                }
+                bytes
+            },
+        };
+        self.execute(&bytes)
+    }
+
+    /// Execute the unwinding instructions `bytes`, as EHABI §10.3 describes
+    fn execute(&mut self, mut bytes: &[u8]) -> Option<()> {
+        let mut vsp = self.r[13];
+        let mut pc_popped = false;
+        let mut next = || -> Option<u8> { let (first, rest) = bytes.split_first()?; bytes = rest; Some(*first) };
+        while let Some(op) = next() {
+            match op {
+                0x00 ..= 0x3F   => vsp = vsp.wrapping_add((u32::from(op) << 2) + 4),
+                0x40 ..= 0x7F   => vsp = vsp.wrapping_sub((u32::from(op & 0x3F) << 2) + 4),
+                0x80 ..= 0x8F   => {
+                    let mask = u16::from(op & 0x0F) << 8 | u16::from(next()?);
+                    if mask == 0 { return None } // refuse to unwind
+                    pc_popped |= mask & 0x800 != 0;
+                    self.pop(&mut vsp, u32::from(mask) << 4)?; // bit 0 = r4
+                },
+                0x90 ..= 0x9F   => match op & 0x0F {
+                    13 | 15     => return None, // reserved
+                    n           => vsp = self.r[usize::from(n)],
+                },
+                0xA0 ..= 0xA7   => self.pop(&mut vsp, ((1 << ((op & 7) + 1)) - 1) << 4)?, // r4 ..= r[4+nnn]
+                0xA8 ..= 0xAF   => self.pop(&mut vsp, ((1 << ((op & 7) + 1)) - 1) << 4 | 1 << 14)?, // r4 ..= r[4+nnn], r14
+                0xB0            => break, // finish
+                0xB1            => match next()? {
+                    mask @ 1 ..= 15 => self.pop(&mut vsp, u32::from(mask))?, // r0 ..= r3
+                    _               => return None, // spare
+                },
+                0xB2            => { // vsp += 0x204 + (uleb128 << 2)
+                    let (mut uleb, mut shift) = (0u32, 0);
+                    loop {
+                        let byte = next()?;
+                        if shift < 32 { uleb |= u32::from(byte & 0x7F) << shift; }
+                        shift += 7;
+                        if byte & 0x80 == 0 { break }
+                    }
+                    vsp = vsp.wrapping_add(0x204).wrapping_add(uleb << 2);
+                },
+                0xB3            => vsp = vsp.wrapping_add((u32::from(next()? & 0x0F) + 1) * 8 + 4), // VFP d[ssss] ..= d[ssss+cccc], saved by FSTMFDX
+                0xB8 ..= 0xBF   => vsp = vsp.wrapping_add((u32::from(op & 7) + 1) * 8 + 4), // VFP d8 ..= d[8+nnn], saved by FSTMFDX
+                0xC0 ..= 0xC5   => vsp = vsp.wrapping_add((u32::from(op & 7) + 1) * 8), // iWMMXt wR10 ..= wR[10+nnn]
+                0xC6 | 0xC8 | 0xC9 => vsp = vsp.wrapping_add((u32::from(next()? & 0x0F) + 1) * 8), // iWMMXt wR[ssss] ..=, VFP d[16+ssss] ..=, VFP d[ssss] ..= (VPUSH)
+                0xC7            => match next()? {
+                    mask @ 1 ..= 15 => vsp = vsp.wrapping_add(4 * mask.count_ones()), // iWMMXt wCGR0 ..= wCGR3
+                    _               => return None, // spare
+                },
+                0xD0 ..= 0xD7   => vsp = vsp.wrapping_add((u32::from(op & 7) + 1) * 8), // VFP d8 ..= d[8+nnn], saved by VPUSH
+                _               => return None, // spare
+            }
+        }
+        self.r[13] = vsp;
+        if !pc_popped { self.r[15] = self.r[14]; }
+        Some(())
+    }
+
+    /// Pop the registers of `mask` (bit n = rn) off the virtual stack, lowest first.  Popping SP itself overrides the virtual SP.
+    fn pop(&mut self, vsp: &mut u32, mask: u32) -> Option<()> {
+        let mut sp_popped = None;
+        for reg in (0 .. 16).filter(|reg| mask & (1 << reg) != 0) {
+            let value = read_word(self.mem, *vsp, self.big_endian)?;
+            *vsp = vsp.wrapping_add(4);
+            if reg == 13 { sp_popped = Some(value) } else { self.r[reg] = value }
+        }
+        if let Some(sp) = sp_popped { *vsp = sp; }
+        Some(())
+    }
+}
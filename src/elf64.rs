@@ -0,0 +1,138 @@
+use std::io;
+use std::mem::size_of_val;
+
+use bytemuck::{bytes_of_mut, Pod, Zeroable};
+use read_write_at::ReadAtMut;
+
+// References:
+// - System V gABI, ELF Header (ELF-64's e_ident & Ehdr): https://www.sco.com/developers/gabi/latest/ch4.eheader.html
+// - ELF for the Arm(R) 64-bit Architecture (AAELF64) §5.1 ELF Header: https://github.com/ARM-software/abi-aa/blob/main/aaelf64/aaelf64.rst
+//
+// Limitations:
+// - Headers only: there's no 64-bit CPU or Memory backend to load into yet
+// - Little-endian EM_AARCH64 only
+
+
+
+pub type Addr       = u64;
+pub type Off        = u64;
+pub type Section    = u16;
+pub type Versym     = u16;
+
+pub const EM_AARCH64 : u16 = crate::Machine::EM_AARCH64;
+
+/// **E**lf **H**ea**d**e**r**
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Ehdr {
+    pub e_ident:        [u8; 16],
+    pub e_type:         u16,
+    pub e_machine:      u16,
+    pub e_version:      u32,
+    pub e_entry:        Addr,
+    pub e_phoff:        Off,
+    pub e_shoff:        Off,
+    pub e_flags:        u32,
+    pub e_ehsize:       u16,
+    pub e_phentsize:    u16,
+    pub e_phnum:        u16,
+    pub e_shentsize:    u16,
+    pub e_shnum:        u16,
+    pub e_shstrndx:     u16,
+}
+
+// **P**rogram **H**ea**d**e**r**
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Phdr {
+    pub p_type:     u32,
+    pub p_flags:    u32, // moved up from after p_memsz, for alignment
+    pub p_offset:   Off,
+    pub p_vaddr:    Addr,
+    pub p_paddr:    Addr,
+    pub p_filesz:   u64,
+    pub p_memsz:    u64,
+    pub p_align:    u64,
+}
+
+/// **S**ection **h**ea**d**e**r**
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Shdr {
+    pub sh_name:        u32,
+    pub sh_type:        u32,
+    pub sh_flags:       u64,
+    pub sh_addr:        Addr,
+    pub sh_offset:      Off,
+    pub sh_size:        u64,
+    pub sh_link:        u32,
+    pub sh_info:        u32,
+    pub sh_addralign:   u64,
+    pub sh_entsize:     u64,
+}
+
+/// **Dyn**amic section entry
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Dyn {
+    pub d_tag:      i64,
+    pub d_val:      u64, // or d_ptr
+}
+
+/// **Sym**bol table entry (reordered relative to [`crate::elf32::Sym`], for alignment)
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Sym {
+    pub st_name:    u32,
+    pub st_info:    u8,
+    pub st_other:   u8,
+    pub st_shndx:   Section,
+    pub st_value:   Addr,
+    pub st_size:    u64,
+}
+
+/// **Rel**ocation entry
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Rel {
+    pub r_offset:   Addr,
+    pub r_info:     u64, // symbol << 32 | type
+}
+
+/// **Rel**ocation entry with **a**ddend
+#[derive(Clone, Copy, Debug, Zeroable, Pod)] #[repr(C)] pub struct Rela {
+    pub r_offset:   Addr,
+    pub r_info:     u64,
+    pub r_addend:   i64,
+}
+
+macro_rules! invalid_data {
+    ( $reason:expr ) => {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, concat!("uvm::elf64: ", $reason)))
+    };
+}
+
+/// The headers of a 64-bit ELF executable
+#[derive(Clone, Debug)]
+pub struct File {
+    pub ehdr:   Ehdr,
+    pub phdrs:  Vec<Phdr>,
+}
+
+impl File {
+    /// Read and validate the ELF header & program header table of `elf`
+    pub fn read(elf: &mut impl ReadAtMut) -> io::Result<Self> {
+        let mut e_ident = [0u8; 16];
+        elf.read_exact_at(&mut e_ident[..], 0)?;
+        if e_ident[0..=3]   != *b"\x7FELF"  { invalid_data!("not an elf file (invalid magic)") } // EI_MAG0..=3
+        if e_ident[4]       != 2            { invalid_data!("not a 64-bit elf (see elf32 for 32-bit elfs)") } // EI_CLASS
+        if e_ident[5]       != 1            { invalid_data!("only little-endian elfs are currently supported") } // EI_DATA
+        if e_ident[6]       != 1            { invalid_data!("only EI_VERSION == 1 elfs are currently supported") } // EI_VERSION
+
+        let mut ehdr = Ehdr { e_ident, .. Zeroable::zeroed() };
+        elf.read_exact_at(&mut bytes_of_mut(&mut ehdr)[16..], 16)?;
+        if ehdr.e_machine   != EM_AARCH64   { invalid_data!("only AArch64 elfs are currently supported (e_machine != EM_AARCH64)") }
+        if ehdr.e_version   != 1            { invalid_data!("only e_version == 1 elfs are currently supported") }
+        if ehdr.e_phoff     == 0            { invalid_data!("executable elfs must have a program header table (e_phoff == 0)") }
+        if usize::from(ehdr.e_ehsize) < size_of_val(&ehdr) { invalid_data!("e_ehsize < size_of::<Ehdr>()") }
+        if ehdr.e_phentsize == 0            { invalid_data!("program header table entries must have nonzero size (e_phentsize == 0)") }
+
+        let mut phdrs = Vec::new();
+        for iph in 0 .. ehdr.e_phnum {
+            let mut phdr = Phdr::zeroed();
+            let phdr_read = size_of_val(&phdr).min(ehdr.e_phentsize.into());
+            let phdr_off = ehdr.e_phoff.checked_add(u64::from(iph) * u64::from(ehdr.e_phentsize)).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "uvm::elf64: e_phoff overflows"))?;
+            elf.read_exact_at(&mut bytes_of_mut(&mut phdr)[..phdr_read], phdr_off)?;
+            phdrs.push(phdr);
+        }
+        Ok(Self { ehdr, phdrs })
+    }
+}
@@ -1,2 +1,5 @@
 #[path = "arm/_arm.rs"] pub mod arm;
-pub mod elf32;
+#[path = "elf32/_elf32.rs"] pub mod elf32;
+pub mod elf64;
+#[path = "loader/_loader.rs"] pub mod loader;
+mod machine; pub use machine::*;
@@ -0,0 +1,95 @@
+pub mod ihex;
+pub mod raw;
+pub mod srec;
+
+use crate::arm;
+
+use std::io;
+
+// References:
+// ARMv7? https://developer.arm.com/documentation/ddi0406/cb/ (B1.8.1 Exception vectors and the exception base address: where bare-metal images expect to start)
+//
+// Limitations:
+// - Bare-metal images only: linux executables go through elf32 (or Machine) instead, which also sets up a process around them
+// - Images are copied into memory up front: nothing is demand paged or shared with the host file
+
+
+
+/// A bare-metal guest loaded from a non-ELF image: memory holding the image, and a CPU about to execute its entry point
+pub struct BareMetal {
+    pub mem:    arm::Memory,
+    /// In Supervisor mode with IRQs & FIQs masked, as if just out of reset, with the PC at `entry`
+    pub cpu:    arm::Cpu,
+    /// Where execution starts, with bit 0 set for Thumb code
+    pub entry:  u32,
+}
+
+impl BareMetal {
+    /// Empty memory, and a `config` CPU about to execute `entry` (in Thumb state if bit 0 is set)
+    pub fn new(config: arm::CpuConfig, entry: u32) -> Self {
+        let mut cpu = arm::Cpu::with_config(config);
+        cpu.set_mode(arm::Mode::Supervisor);
+        cpu.cpsr |= arm::Psr::I | arm::Psr::F;
+        cpu.cpsr.set(arm::Psr::T, entry & 1 != 0);
+        cpu.set_next_instruction_addr(entry & !1);
+        Self { mem: arm::Memory::new(), cpu, entry }
+    }
+}
+
+/// The contents of an image format with addresses (e.g. [`ihex`] or [`srec`]): the data its records hold, and the entry point of its start address record
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Records {
+    /// Data & its address, in file order, with records continuing where the previous one left off merged
+    pub chunks: Vec<(u32, Vec<u8>)>,
+    /// From the start address record, if there was one
+    pub entry:  Option<u32>,
+}
+
+impl Records {
+    /// Append `data` at `addr`, merging it into the last chunk if that ends at `addr`
+    pub fn push(&mut self, addr: u32, data: &[u8]) {
+        if data.is_empty() { return }
+        match self.chunks.last_mut() {
+            Some((start, chunk)) if u64::from(*start) + chunk.len() as u64 == u64::from(addr) => chunk.extend_from_slice(data),
+            _ => self.chunks.push((addr, data.to_vec())),
+        }
+    }
+
+    /// [`load_bytes`] every chunk into `mem`
+    pub fn load_into(&self, mem: &arm::Memory, flags: arm::MemoryFlags) -> io::Result<()> {
+        for (addr, data) in &self.chunks { load_bytes(mem, *addr, data, flags)?; }
+        Ok(())
+    }
+
+    /// Load every chunk into a fresh [`BareMetal`] with a default [`arm::CpuConfig`], ready to execute [`Records::entry`] (or 0, the reset vector, without one)
+    pub fn load(&self, flags: arm::MemoryFlags) -> io::Result<BareMetal> {
+        let image = BareMetal::new(arm::CpuConfig::default(), self.entry.unwrap_or(0));
+        self.load_into(&image.mem, flags)?;
+        Ok(image)
+    }
+}
+
+/// Copy `bytes` into `mem` at `addr`, first mapping any pages they cover that aren't mapped yet with `flags`.
+/// Pages that were already mapped (e.g. by an earlier record of the same image) keep their flags, and the rest of their contents.
+pub fn load_bytes(mem: &arm::Memory, addr: u32, bytes: &[u8], flags: arm::MemoryFlags) -> io::Result<()> {
+    if bytes.is_empty() { return Ok(()) }
+    let end = u64::from(addr) + bytes.len() as u64;
+    if end > 1 << 32 { return Err(io::Error::new(io::ErrorKind::InvalidData, format!("uvm::loader: 0x{:x} bytes at 0x{:08x} extend past the end of the address space", bytes.len(), addr))) }
+
+    let mapped = mem.regions();
+    let page_size = u64::from(mem.page_size());
+    let mut page = u64::from(addr) & !(page_size - 1);
+    while page < end {
+        if !mapped.iter().any(|region| u64::from(region.start) <= page && page < region.end) { mem.map(page as u32 .. (page + page_size - 1) as u32, flags); } // (the last byte of the page: the end of the address space would overflow)
+        page += page_size;
+    }
+    mem.try_write_bytes(addr, arm::MemoryFlags::NONE, bytes).map_err(|fault| io::Error::new(io::ErrorKind::InvalidData, format!("uvm::loader: {}", fault)))
+}
+
+/// Decode a record's hexadecimal digits into bytes, or [`None`] if they aren't an even number of hex digits
+fn hex_bytes(digits: &str) -> Option<Vec<u8>> {
+    digits.as_bytes().chunks(2).map(|pair| match pair {
+        [hi, lo]    => Some((char::from(*hi).to_digit(16)? << 4 | char::from(*lo).to_digit(16)?) as u8),
+        _           => None,
+    }).collect()
+}
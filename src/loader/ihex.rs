@@ -0,0 +1,43 @@
+use super::*;
+
+// References:
+// - Intel Hexadecimal Object File Format Specification, Revision A (1988)
+//
+// Limitations:
+// - Extended Segment Addresses (record type 02) don't wrap data within their 64 KiB segment: records crossing a segment's end continue into the next one
+// - Anything after the End of File record is ignored
+
+
+
+/// Parse the Intel HEX (`.hex`, `.ihex`) image `text`: `:`-prefixed records, one per line
+pub fn parse(text: &str) -> io::Result<Records> {
+    let mut records = Records::default();
+    let mut base = 0u32; // from Extended Segment / Linear Address records
+    for (i, line) in text.lines().enumerate() {
+        let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, format!("uvm::loader::ihex: line {}: {}", i + 1, reason));
+        let line = line.trim();
+        if line.is_empty() { continue }
+        let Some(digits) = line.strip_prefix(':') else { return Err(invalid("records must start with `:`")) };
+        let Some(bytes) = hex_bytes(digits) else { return Err(invalid("records must be an even number of hex digits")) };
+        if bytes.len() < 5 || bytes.len() != 5 + usize::from(bytes[0]) { return Err(invalid("record length doesn't match its byte count")) }
+        if bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 { return Err(invalid("checksum mismatch")) }
+
+        let offset = u32::from(u16::from_be_bytes([bytes[1], bytes[2]]));
+        let data = &bytes[4 .. bytes.len() - 1];
+        let word = || data.iter().fold(0u32, |word, b| word << 8 | u32::from(*b));
+        match (bytes[3], data.len()) {
+            (0x00, _) => records.push(base.wrapping_add(offset), data), // Data
+            (0x01, 0) => return Ok(records), // End of File
+            (0x02, 2) => base = word() << 4, // Extended Segment Address: bits 4 ..= 19
+            (0x03, 4) => records.entry = Some((word() >> 16 << 4).wrapping_add(word() & 0xFFFF)), // Start Segment Address: CS:IP
+            (0x04, 2) => base = word() << 16, // Extended Linear Address: bits 16 ..= 31
+            (0x05, 4) => records.entry = Some(word()), // Start Linear Address: EIP
+            (0x00 ..= 0x05, _) => return Err(invalid(&format!("record type {:02X} has the wrong length ({} bytes)", bytes[3], data.len()))),
+            (other, _) => return Err(invalid(&format!("unknown record type {:02X}", other))),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "uvm::loader::ihex: missing End of File record"))
+}
+
+/// [`parse`] the Intel HEX image `text`, and [`Records::load`] it with `flags`
+pub fn load(text: &str, flags: arm::MemoryFlags) -> io::Result<BareMetal> { parse(text)?.load(flags) }
@@ -0,0 +1,17 @@
+use super::*;
+
+// References:
+// - objcopy(1)'s `-O binary` output: https://sourceware.org/binutils/docs/binutils/objcopy.html
+//
+// Limitations:
+// - A single blob at a single address: images with scattered sections want a format with addresses (e.g. Intel HEX, or an ELF)
+
+
+
+/// Load the flat binary `bytes` (e.g. a bootloader or firmware blob, `objcopy -O binary` output) at `base_addr`, mapping it with `flags`,
+/// ready to execute `entry` (in Thumb state if bit 0 is set) with a default [`arm::CpuConfig`]
+pub fn load(bytes: &[u8], base_addr: u32, entry: u32, flags: arm::MemoryFlags) -> io::Result<BareMetal> {
+    let image = BareMetal::new(arm::CpuConfig::default(), entry);
+    load_bytes(&image.mem, base_addr, bytes, flags)?;
+    Ok(image)
+}
@@ -0,0 +1,43 @@
+use super::*;
+
+// References:
+// - srec(5), as documented by the SRecord project: https://srecord.sourceforge.net/man/man5/srec_motorola.html
+//
+// Limitations:
+// - S0 headers are skipped, and the S5 / S6 record counts aren't checked against the data records
+// - Anything after the termination (S7, S8, S9) record is ignored
+
+
+
+/// Parse the Motorola S-record (`.srec`, `.s19`, `.s28`, `.s37`, `.mot`) image `text`: `S`-prefixed records, one per line
+pub fn parse(text: &str) -> io::Result<Records> {
+    let mut records = Records::default();
+    for (i, line) in text.lines().enumerate() {
+        let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, format!("uvm::loader::srec: line {}: {}", i + 1, reason));
+        let line = line.trim();
+        if line.is_empty() { continue }
+        let [b'S', kind, ..] = *line.as_bytes() else { return Err(invalid("records must start with `S` and a type digit")) };
+        let addr_len = match kind {
+            b'0' | b'1' | b'5' | b'9'   => 2,
+            b'2' | b'6' | b'8'          => 3,
+            b'3' | b'7'                 => 4,
+            _                           => return Err(invalid(&format!("unknown record type S{}", char::from(kind)))),
+        };
+        let Some(bytes) = hex_bytes(&line[2..]) else { return Err(invalid("records must be an even number of hex digits")) };
+        if bytes.is_empty() || bytes.len() != 1 + usize::from(bytes[0]) { return Err(invalid("record length doesn't match its byte count")) }
+        if bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0xFF { return Err(invalid("checksum mismatch")) }
+        if bytes.len() < 2 + addr_len { return Err(invalid("record is too short for its address")) }
+
+        let addr = bytes[1 ..= addr_len].iter().fold(0u32, |addr, b| addr << 8 | u32::from(*b));
+        let data = &bytes[1 + addr_len .. bytes.len() - 1];
+        match kind {
+            b'1' | b'2' | b'3'          => records.push(addr, data), // Data
+            b'7' | b'8' | b'9'          => { records.entry = Some(addr); return Ok(records) }, // Termination, with the start address
+            _                           => {}, // S0 header, S5 / S6 record count
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "uvm::loader::srec: missing termination (S7, S8, or S9) record"))
+}
+
+/// [`parse`] the Motorola S-record image `text`, and [`Records::load`] it with `flags`
+pub fn load(text: &str, flags: arm::MemoryFlags) -> io::Result<BareMetal> { parse(text)?.load(flags) }
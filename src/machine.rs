@@ -0,0 +1,96 @@
+use crate::elf32;
+
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+use read_write_at::ReadAtMut;
+
+// References:
+// - System V gABI, ELF Header (e_machine): https://www.sco.com/developers/gabi/latest/ch4.eheader.html
+// - ELF for the Arm(R) Architecture (AAELF32), and for the Arm(R) 64-bit Architecture (AAELF64): https://github.com/ARM-software/abi-aa
+// - RISC-V ELF psABI: https://github.com/riscv-non-isa/riscv-elf-psabi-doc
+//
+// Limitations:
+// - Only Machine::Arm has a CPU backend: AArch64 & RISC-V executables are recognized (see elf64 for their headers), but not runnable yet
+
+
+
+/// The architecture an ELF executable was built for (its `e_machine`), which picks the CPU backend [`run_with`] loads it into
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Machine {
+    /// EM_ARM (40): 32-bit ARM & Thumb, run by [`crate::arm`] via [`elf32::load_with`]
+    Arm,
+    /// EM_AARCH64 (183): 64-bit ARM
+    AArch64,
+    /// EM_RISCV (243): RISC-V, either width
+    RiscV,
+    /// Any other `e_machine`
+    Other(u16),
+}
+
+impl Machine {
+    pub const EM_ARM        : u16 = 40;
+    pub const EM_AARCH64    : u16 = 183;
+    pub const EM_RISCV      : u16 = 243;
+
+    pub fn from_e_machine(e_machine: u16) -> Self {
+        match e_machine {
+            Self::EM_ARM        => Self::Arm,
+            Self::EM_AARCH64    => Self::AArch64,
+            Self::EM_RISCV      => Self::RiscV,
+            other               => Self::Other(other),
+        }
+    }
+
+    pub fn e_machine(self) -> u16 {
+        match self {
+            Self::Arm           => Self::EM_ARM,
+            Self::AArch64       => Self::EM_AARCH64,
+            Self::RiscV         => Self::EM_RISCV,
+            Self::Other(other)  => other,
+        }
+    }
+
+    /// Read the `e_machine` of `elf`, a 32 or 64-bit ELF file of either byte order.  Only the identification bytes & `e_machine` are validated.
+    pub fn read(elf: &mut impl ReadAtMut) -> io::Result<Self> {
+        let mut header = [0u8; 20]; // e_ident, e_type, e_machine: the same offsets for both ELF classes
+        elf.read_exact_at(&mut header[..], 0)?;
+        if header[0..=3] != *b"\x7FELF" { return Err(invalid("not an elf file (invalid magic)")) } // EI_MAG0..=3
+        let e_machine = [header[18], header[19]];
+        match header[5] { // EI_DATA
+            1 => Ok(Self::from_e_machine(u16::from_le_bytes(e_machine))),
+            2 => Ok(Self::from_e_machine(u16::from_be_bytes(e_machine))),
+            _ => Err(invalid("EI_DATA is neither little nor big-endian")),
+        }
+    }
+
+    /// `true` if uvm has a CPU backend for this machine, that [`Machine::run_with`] can run executables on
+    pub fn is_supported(self) -> bool { matches!(self, Self::Arm) }
+
+    /// Load & run the linux executable `elf`, which was built for this machine, on its CPU backend until the guest exits (which exits the host process with the guest's exit status) or faults
+    pub fn run_with(self, elf: &mut impl ReadAtMut, config: &elf32::LoadConfig) -> io::Result<()> {
+        match self {
+            Self::Arm   => elf32::run_with(elf, config),
+            other       => Err(io::Error::new(io::ErrorKind::Unsupported, format!("uvm::Machine: {} executables aren't supported yet: there's no CPU backend for them", other))),
+        }
+    }
+}
+
+impl Display for Machine {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Arm           => write!(f, "ARM"),
+            Self::AArch64       => write!(f, "AArch64"),
+            Self::RiscV         => write!(f, "RISC-V"),
+            Self::Other(other)  => write!(f, "e_machine {}", other),
+        }
+    }
+}
+
+/// [`run_with`] the default [`elf32::LoadConfig`]: no arguments, and an empty environment
+pub fn run(elf: &mut impl ReadAtMut) -> io::Result<()> { run_with(elf, &elf32::LoadConfig::default()) }
+
+/// Load & run the linux executable `elf` on whichever CPU backend its [`Machine`] calls for, until the guest exits or faults
+pub fn run_with(elf: &mut impl ReadAtMut, config: &elf32::LoadConfig) -> io::Result<()> { Machine::read(elf)?.run_with(elf, config) }
+
+fn invalid(reason: impl Display) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("uvm::Machine: {}", reason)) }
@@ -0,0 +1,151 @@
+//! Single instruction reproducers for the decoders: each encoding is stepped against both memory backends,
+//! and UNDEFINED encodings must come back as [`StepOutcome::Undefined`] rather than panicking the host.
+
+use uvm::arm::*;
+
+const CODE : u32 = 0x1000;
+const DATA : u32 = 0x2000;
+
+/// A [`Memory::new`] and a [`Memory::new_flat`], each with `CODE .. DATA` executable and `DATA ..` writable
+fn memories() -> [Memory; 2] {
+    [Memory::new(), Memory::new_flat()].map(|mem| {
+        mem.map(CODE .. DATA, MemoryFlags::READ | MemoryFlags::EXECUTE);
+        mem.map(DATA .. DATA + 0x1000, MemoryFlags::READ | MemoryFlags::WRITE);
+        mem
+    })
+}
+
+/// Step `code` (ARM words, or Thumb halfwords if `thumb`) at `CODE` on an ARMv7-A, after `setup`, once per backend and for `steps` steps
+fn run(code: &[u8], thumb: bool, steps: usize, setup: impl Fn(&mut Cpu, &Memory)) -> Vec<(Cpu, Memory, StepOutcome)> {
+    memories().into_iter().map(|mem| {
+        mem.write_bytes(CODE, MemoryFlags::NONE, code);
+        let mut cpu = Cpu::with_config(CpuConfig::new(Arch::V7A));
+        cpu.cpsr.set(Psr::T, thumb);
+        cpu.set_next_instruction_addr(CODE);
+        cpu.registers[0] = DATA;
+        setup(&mut cpu, &mem);
+        let mut outcome = StepOutcome::Continue;
+        for _ in 0 .. steps { outcome = cpu.step1(&mem); }
+        (cpu, mem, outcome)
+    }).collect()
+}
+
+fn arm(op: u32, setup: impl Fn(&mut Cpu, &Memory)) -> Vec<(Cpu, Memory, StepOutcome)> { run(&op.to_le_bytes(), false, 1, setup) }
+
+fn assert_undefined(ops: &[u32]) {
+    for &op in ops {
+        for (_, _, outcome) in arm(op, |_, _| {}) {
+            assert!(matches!(outcome, StepOutcome::Undefined { pc: CODE, op: o } if o == op), "{:08x}: {:?}", op, outcome);
+        }
+    }
+}
+
+fn assert_alignment_fault(outcome: StepOutcome) {
+    assert!(matches!(outcome, StepOutcome::DataAbort { fault, .. } if fault.kind == MemFaultKind::Alignment), "{:?}", outcome);
+}
+
+#[test] fn neon() {
+    for (cpu, _, outcome) in arm(0xF2010802, |cpu, _| { cpu.vfp.d[1] = 0x0102_0304_0506_07FF; cpu.vfp.d[2] = 0x0101_0101_0101_0101; }) { // vadd.i8 d0, d1, d2
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!(cpu.vfp.d[0], 0x0203_0405_0607_0800);
+    }
+    for (cpu, _, outcome) in arm(0xF3B00001, |cpu, _| cpu.vfp.d[1] = 0x0102_0304_0506_0708) { // vrev64.8 d0, d1
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!(cpu.vfp.d[0], 0x0807_0605_0403_0201);
+    }
+    for (cpu, _, outcome) in arm(0xF3B20202, |cpu, _| { cpu.vfp.d[2] = 0x0004_0003_0002_0001; cpu.vfp.d[3] = 0x1108_0007_0006_0005; }) { // vmovn.i16 d0, q1
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!(cpu.vfp.d[0], 0x0807_0605_0403_0201);
+    }
+    assert_undefined(&[
+        0xF240F840, // vadd.i8 with Q = 1 and an odd Vd (d31)
+        0xF2000841 | 1 << 16, // vadd.i8 with Q = 1 and an odd Vn, Vm
+        0xF3B80080, // vrev32.32
+        0xF3B40100, // vrev16.16
+        0xF3BC0200, // vpaddl with size = 0b11
+        0xF3BE0200, // vmovn with size = 0b11
+        0xF3B20203, // vmovn from an odd Qm
+        0xF2B10902, // vext.8 of D registers with imm4 > 7
+        0xF2B10042 | 1 << 12, // vext.8 of Q registers with an odd Vd
+        0xF3B00C01, // vdup (scalar) with imm4<2:0> = 0
+        0xF3B11C40 | 1 << 12, // vdup.8 (scalar) to an odd Q register
+    ]);
+}
+
+#[test] fn vfp() {
+    for (cpu, _, outcome) in arm(0xEC900B04, |_, mem| mem.write_u64_aligned(DATA + 8, MemoryFlags::WRITE, 0x1234_5678_9ABC_DEF0)) { // vldmia r0, {d0, d1}
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!(cpu.vfp.d[1], 0x1234_5678_9ABC_DEF0);
+    }
+    for (_, _, outcome) in arm(0xED900A00, |cpu, _| cpu.registers[0] = DATA + 2) { assert_alignment_fault(outcome) } // vldr s0, [r0]
+    for (_, _, outcome) in arm(0xED800B00, |cpu, _| cpu.registers[0] = DATA + 2) { assert_alignment_fault(outcome) } // vstr d0, [r0]
+    assert_undefined(&[
+        0xECD0FAFF, // vldmia r0, {s31, ...} with imm8 = 255
+        0xEC900B22, // vldmia r0, {d0 - d16}
+        0xEC900A00, // vldmia r0, {}
+    ]);
+}
+
+#[test] fn media() {
+    for (cpu, _, outcome) in arm(0xE6510F92, |cpu, _| { cpu.registers[1] = 0x80FF_0102; cpu.registers[2] = 0x8001_0203; }) { // uadd8 r0, r1, r2
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!(cpu.registers[0], 0x0000_0305);
+        assert_eq!(cpu.cpsr.ge(), 0b1100);
+    }
+    for (cpu, _, outcome) in arm(0xE6310F12, |cpu, _| { cpu.registers[1] = 0x7FFF_FFFE; cpu.registers[2] = 0x0001_FFFC; }) { // shadd16 r0, r1, r2
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!(cpu.registers[0], 0x4000_FFFD);
+    }
+    assert_undefined(&[
+        0xE6010F12, // parallel add / subtract with op1 = 000
+        0xE6410F12, // parallel add / subtract with op1 = 100
+    ]);
+}
+
+#[test] fn loads() {
+    for (_, _, outcome) in arm(0xE1C020D0, |cpu, _| { cpu.registers[0] = DATA + 2; cpu.cp15.sctlr |= 2; }) { assert_alignment_fault(outcome) } // ldrd r2, r3, [r0] with SCTLR.A
+    for (_, _, outcome) in arm(0xE1C020F0, |cpu, _| { cpu.registers[0] = DATA + 2; cpu.cp15.sctlr |= 2; }) { assert_alignment_fault(outcome) } // strd r2, r3, [r0] with SCTLR.A
+    for (_, _, outcome) in arm(0xE1C020D0, |cpu, _| { cpu.registers[0] = DATA + 4; cpu.cp15.sctlr |= 2; }) { assert_eq!(outcome, StepOutcome::Continue) }
+    for (_, _, outcome) in arm(0xE1901F9F, |cpu, _| cpu.registers[0] = DATA + 2) { assert_alignment_fault(outcome) } // ldrex r1, [r0]
+    assert_undefined(&[
+        0xE1B01F9F, // ldrexd r1, r2, [r0]: odd Rt
+        0xE1B0EF9F, // ldrexd lr, pc, [r0]
+    ]);
+}
+
+#[test] fn coprocessor() {
+    #[derive(Clone, Debug)] struct Cdp;
+    impl Coprocessor for Cdp { fn cdp(&mut self, _opc1: u32, _crd: u32, _crn: u32, _crm: u32, _opc2: u32) -> Option<()> { Some(()) } }
+    for (_, _, outcome) in arm(0xFE321723, |cpu, _| { cpu.set_coprocessor(7, Cdp); }) { assert_eq!(outcome, StepOutcome::Continue) } // cdp2 p7, 3, c1, c2, c3, 1
+    assert_undefined(&[0xFE321723]); // ...with nothing attached
+}
+
+#[test] fn thumb() {
+    // it eq; bl (skipped); movs r0, #1
+    for (cpu, _, outcome) in run(&[0x08, 0xBF, 0x00, 0xF0, 0x00, 0xF8, 0x01, 0x20], true, 3, |cpu, _| cpu.cpsr.set(Psr::Z, false)) {
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!((cpu.registers[0], cpu.next_instruction_addr()), (1, CODE + 8));
+    }
+    // pop {r0}: a misaligned SP's low bits are ignored, or fault with SCTLR.A
+    for (cpu, _, outcome) in run(&[0x01, 0xBC], true, 1, |cpu, mem| { mem.write_u32_aligned(DATA, MemoryFlags::WRITE, 0x1234_5678); cpu.registers[13] = DATA + 2; }) {
+        assert_eq!(outcome, StepOutcome::Continue);
+        assert_eq!(cpu.registers[0], 0x1234_5678);
+    }
+    for (cpu, _, outcome) in run(&[0x01, 0xC9], true, 1, |cpu, _| { cpu.registers[1] = DATA + 2; cpu.cp15.sctlr |= 2; }) { // ldmia r1!, {r0}
+        assert_alignment_fault(outcome);
+        assert_eq!(cpu.registers[1], DATA + 2);
+    }
+}
+
+#[test] fn top_of_memory() {
+    for mem in [Memory::new(), Memory::new_flat()] {
+        mem.map(0xFFFF_F000 .. 0xFFFF_FFFF, MemoryFlags::READ | MemoryFlags::WRITE | MemoryFlags::EXECUTE);
+        mem.write_u32_aligned(0xFFFF_FFFC, MemoryFlags::WRITE, 0xEB00_0000); // bl +0
+        assert_eq!(mem.read_u32_aligned(0xFFFF_FFFC, MemoryFlags::READ), 0xEB00_0000);
+
+        let mut cpu = Cpu::with_config(CpuConfig::new(Arch::V7A));
+        cpu.set_next_instruction_addr(0xFFFF_FFFC);
+        assert_eq!(cpu.step1(&mem), StepOutcome::Continue);
+        assert_eq!((cpu.registers[14], cpu.next_instruction_addr()), (0, 4));
+    }
+}